@@ -4,6 +4,7 @@
 //! including bash, zsh, fish, and PowerShell completion scripts.
 
 use crate::error::Result;
+use crate::report::Output;
 use std::fmt::Write;
 
 /// Shell completion generator.
@@ -43,7 +44,7 @@ impl CompletionGenerator {
         // Command completion
         writeln!(script, "    case ${{prev}} in")?;
         writeln!(script, "        env)")?;
-        writeln!(script, "            COMPREPLY=($(compgen -W 'init switch scan validate sync generate status' -- \"$cur\"))")?;
+        writeln!(script, "            COMPREPLY=($(compgen -W 'init switch scan validate sync generate status get set unset list run hook diff matrix encrypt decrypt keys rekey rotate' -- \"$cur\"))")?;
         writeln!(script, "            return")?;
         writeln!(script, "            ;;")?;
         writeln!(script)?;
@@ -117,7 +118,7 @@ impl CompletionGenerator {
         writeln!(script, "                validate)")?;
         writeln!(
             script,
-            "                    COMPREPLY=($(compgen -W '--env --check-unused' -- \"$cur\"))"
+            "                    COMPREPLY=($(compgen -W '--env --check-unused --raw' -- \"$cur\"))"
         )?;
         writeln!(script, "                    ;;")?;
         writeln!(script, "                sync)")?;
@@ -132,7 +133,7 @@ impl CompletionGenerator {
         writeln!(script, "                status)")?;
         writeln!(
             script,
-            "                    COMPREPLY=($(compgen -W '--verbose' -- \"$cur\"))"
+            "                    COMPREPLY=($(compgen -W '--verbose --raw' -- \"$cur\"))"
         )?;
         writeln!(script, "                    ;;")?;
         writeln!(script, "                *)")?;
@@ -189,6 +190,49 @@ impl CompletionGenerator {
         writeln!(script, "        'sync:Sync environments safely'")?;
         writeln!(script, "        'generate:Generate .env.example file'")?;
         writeln!(script, "        'status:Show current environment status'")?;
+        writeln!(script, "        'get:Print the value of a variable'")?;
+        writeln!(script, "        'set:Set a variable in an environment'")?;
+        writeln!(
+            script,
+            "        'unset:Remove a variable from an environment'"
+        )?;
+        writeln!(
+            script,
+            "        'list:List the variables of an environment'"
+        )?;
+        writeln!(
+            script,
+            "        'run:Run a command with environment variables injected'"
+        )?;
+        writeln!(
+            script,
+            "        'hook:Print a shell hook that loads the current environment on each prompt'"
+        )?;
+        writeln!(
+            script,
+            "        'diff:Show differences between two environments'"
+        )?;
+        writeln!(
+            script,
+            "        'matrix:Show which variables each environment defines'"
+        )?;
+        writeln!(
+            script,
+            "        'encrypt:Encrypt the values of an environment file'"
+        )?;
+        writeln!(
+            script,
+            "        'decrypt:Decrypt the values of an environment file'"
+        )?;
+        writeln!(script, "        'keys:Manage encryption recipients'")?;
+        writeln!(
+            script,
+            "        'rekey:Re-encrypt values for the current recipients'"
+        )?;
+        writeln!(
+            script,
+            "        'rotate:Replace a secret with a generated value'"
+        )?;
         writeln!(script, "    )")?;
         writeln!(script)?;
 
@@ -265,6 +309,10 @@ impl CompletionGenerator {
             script,
             "                        '--check-unused[Check for unused environment variables]' \\"
         )?;
+        writeln!(
+            script,
+            "                        '--raw[Validate values without expanding references]' \\"
+        )?;
         writeln!(
             script,
             "                        '--help[Show help message]'"
@@ -326,6 +374,10 @@ impl CompletionGenerator {
             script,
             "                        '--verbose[Show detailed information]' \\"
         )?;
+        writeln!(
+            script,
+            "                        '--raw[Show values without expanding references]' \\"
+        )?;
         writeln!(
             script,
             "                        '--help[Show help message]'"
@@ -375,6 +427,19 @@ impl CompletionGenerator {
         )?;
         writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a generate -d 'Generate .env.example file'")?;
         writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a status -d 'Show current environment status'")?;
+        writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a get -d 'Print the value of a variable'")?;
+        writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a set -d 'Set a variable in an environment'")?;
+        writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a unset -d 'Remove a variable from an environment'")?;
+        writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a list -d 'List the variables of an environment'")?;
+        writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a run -d 'Run a command with environment variables injected'")?;
+        writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a hook -d 'Print a shell hook that loads the current environment on each prompt'")?;
+        writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a diff -d 'Show differences between two environments'")?;
+        writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a matrix -d 'Show which variables each environment defines'")?;
+        writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a encrypt -d 'Encrypt the values of an environment file'")?;
+        writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a decrypt -d 'Decrypt the values of an environment file'")?;
+        writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a keys -d 'Manage encryption recipients'")?;
+        writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a rekey -d 'Re-encrypt values for the current recipients'")?;
+        writeln!(script, "complete -c env -n \"__fish_use_subcommand\" -a rotate -d 'Replace a secret with a generated value'")?;
         writeln!(script)?;
 
         // Init command options
@@ -399,6 +464,7 @@ impl CompletionGenerator {
         writeln!(script, "# validate command options")?;
         writeln!(script, "complete -c env -n \"__fish_seen_subcommand_from validate\" -l env -d 'Environment to validate' -xa 'current (env status 2>/dev/null | string match -r '\\*?\\s+\\w+' | string split ' ' | string trim | tail -n +2)'")?;
         writeln!(script, "complete -c env -n \"__fish_seen_subcommand_from validate\" -l check-unused -d 'Check for unused environment variables'")?;
+        writeln!(script, "complete -c env -n \"__fish_seen_subcommand_from validate\" -l raw -d 'Validate values without expanding references'")?;
         writeln!(script)?;
 
         // Sync command options
@@ -419,6 +485,7 @@ impl CompletionGenerator {
         // Status command options
         writeln!(script, "# status command options")?;
         writeln!(script, "complete -c env -n \"__fish_seen_subcommand_from status\" -l verbose -d 'Show detailed information'")?;
+        writeln!(script, "complete -c env -n \"__fish_seen_subcommand_from status\" -l raw -d 'Show values without expanding references'")?;
         writeln!(script)?;
 
         // Help option for all commands
@@ -480,7 +547,7 @@ impl CompletionGenerator {
 
         writeln!(script, "        '^$' {{")?;
         writeln!(script, "            # Main commands")?;
-        writeln!(script, "            @('init', 'switch', 'scan', 'validate', 'sync', 'generate', 'status') | ForEach-Object {{")?;
+        writeln!(script, "            @('init', 'switch', 'scan', 'validate', 'sync', 'generate', 'status', 'get', 'set', 'unset', 'list', 'run', 'hook', 'diff', 'matrix', 'encrypt', 'decrypt', 'keys', 'rekey', 'rotate') | ForEach-Object {{")?;
         writeln!(script, "                [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)")?;
         writeln!(script, "            }}")?;
         writeln!(script, "        }}")?;
@@ -508,13 +575,13 @@ impl CompletionGenerator {
             "            }} elseif ($wordToComplete -match '^--') {{"
         )?;
         writeln!(script, "                # Options")?;
-        writeln!(script, "                @('--env', '--check-unused', '--help') | Where-Object {{ $_ -like \"$wordToComplete*\" }} | ForEach-Object {{")?;
+        writeln!(script, "                @('--env', '--check-unused', '--raw', '--help') | Where-Object {{ $_ -like \"$wordToComplete*\" }} | ForEach-Object {{")?;
         writeln!(script, "                    [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)")?;
         writeln!(script, "                }}")?;
         writeln!(script, "            }} else {{")?;
         writeln!(
             script,
-            "                @('--env', '--check-unused', '--help') | ForEach-Object {{"
+            "                @('--env', '--check-unused', '--raw', '--help') | ForEach-Object {{"
         )?;
         writeln!(script, "                    [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)")?;
         writeln!(script, "                }}")?;
@@ -579,13 +646,13 @@ impl CompletionGenerator {
         writeln!(script, "        '^status$' {{")?;
         writeln!(script, "            if ($wordToComplete -match '^--') {{")?;
         writeln!(script, "                # Options")?;
-        writeln!(script, "                @('--verbose', '--help') | Where-Object {{ $_ -like \"$wordToComplete*\" }} | ForEach-Object {{")?;
+        writeln!(script, "                @('--verbose', '--raw', '--help') | Where-Object {{ $_ -like \"$wordToComplete*\" }} | ForEach-Object {{")?;
         writeln!(script, "                    [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)")?;
         writeln!(script, "                }}")?;
         writeln!(script, "            }} else {{")?;
         writeln!(
             script,
-            "                @('--verbose', '--help') | ForEach-Object {{"
+            "                @('--verbose', '--raw', '--help') | ForEach-Object {{"
         )?;
         writeln!(script, "                    [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)")?;
         writeln!(script, "                }}")?;
@@ -666,7 +733,7 @@ impl std::str::FromStr for Shell {
 }
 
 /// Install shell completion for the current user.
-pub fn install_completion(shell: Shell) -> Result<()> {
+pub fn install_completion(shell: Shell, out: &Output) -> Result<()> {
     let completion_dir = get_completion_dir(shell)?;
     let completion_file = completion_dir.join(format!("env.{}", shell));
 
@@ -680,39 +747,39 @@ pub fn install_completion(shell: Shell) -> Result<()> {
     // Write completion script
     std::fs::write(&completion_file, script)?;
 
-    println!(
+    out.info(format!(
         "✅ Completion installed for {} at: {}",
         shell,
         completion_file.display()
-    );
+    ));
 
     // Show installation instructions
-    show_completion_instructions(shell, &completion_file)?;
+    show_completion_instructions(shell, &completion_file, out)?;
 
     Ok(())
 }
 
 /// Uninstall shell completion.
-pub fn uninstall_completion(shell: Shell) -> Result<()> {
+pub fn uninstall_completion(shell: Shell, out: &Output) -> Result<()> {
     let completion_dir = get_completion_dir(shell)?;
     let completion_file = completion_dir.join(format!("env.{}", shell));
 
     if completion_file.exists() {
         std::fs::remove_file(&completion_file)?;
-        println!(
+        out.info(format!(
             "✅ Completion uninstalled for {} from: {}",
             shell,
             completion_file.display()
-        );
+        ));
 
         // Show uninstallation instructions
-        show_uninstallation_instructions(shell)?;
+        show_uninstallation_instructions(shell, out)?;
     } else {
-        println!(
+        out.info(format!(
             "ℹ️  No completion found for {} at: {}",
             shell,
             completion_file.display()
-        );
+        ));
     }
 
     Ok(())
@@ -770,25 +837,29 @@ fn get_completion_dir(shell: Shell) -> Result<std::path::PathBuf> {
 }
 
 /// Show installation instructions for the specified shell.
-fn show_completion_instructions(shell: Shell, _completion_file: &std::path::Path) -> Result<()> {
+fn show_completion_instructions(
+    shell: Shell,
+    _completion_file: &std::path::Path,
+    out: &Output,
+) -> Result<()> {
     match shell {
         Shell::Bash => {
-            println!("📝 To enable bash completion, add the following to your ~/.bashrc or ~/.bash_profile:");
-            println!("   source ~/.local/share/bash-completion/completions/env");
-            println!("   Or restart your shell to load the completion automatically.");
+            out.info("📝 To enable bash completion, add the following to your ~/.bashrc or ~/.bash_profile:");
+            out.info("   source ~/.local/share/bash-completion/completions/env");
+            out.info("   Or restart your shell to load the completion automatically.");
         }
         Shell::Zsh => {
-            println!("📝 To enable zsh completion, add the following to your ~/.zshrc:");
-            println!("   fpath=(~/.zsh/completions $fpath)");
-            println!("   autoload -U compinit");
-            println!("   compinit");
-            println!("   Or restart your shell to load the completion automatically.");
+            out.info("📝 To enable zsh completion, add the following to your ~/.zshrc:");
+            out.info("   fpath=(~/.zsh/completions $fpath)");
+            out.info("   autoload -U compinit");
+            out.info("   compinit");
+            out.info("   Or restart your shell to load the completion automatically.");
         }
         Shell::Fish => {
-            println!("📝 Fish completion should work automatically after restarting your shell.");
+            out.info("📝 Fish completion should work automatically after restarting your shell.");
         }
         Shell::PowerShell => {
-            println!("📝 PowerShell completion should work automatically. Restart your PowerShell session if needed.");
+            out.info("📝 PowerShell completion should work automatically. Restart your PowerShell session if needed.");
         }
     }
 
@@ -796,19 +867,19 @@ fn show_completion_instructions(shell: Shell, _completion_file: &std::path::Path
 }
 
 /// Show uninstallation instructions.
-fn show_uninstallation_instructions(shell: Shell) -> Result<()> {
+fn show_uninstallation_instructions(shell: Shell, out: &Output) -> Result<()> {
     match shell {
         Shell::Bash => {
-            println!("📝 Remove the 'source ~/.local/share/bash-completion/completions/env' line from your ~/.bashrc or ~/.bash_profile.");
+            out.info("📝 Remove the 'source ~/.local/share/bash-completion/completions/env' line from your ~/.bashrc or ~/.bash_profile.");
         }
         Shell::Zsh => {
-            println!("📝 Remove the completion directory from fpath in your ~/.zshrc if you added it manually.");
+            out.info("📝 Remove the completion directory from fpath in your ~/.zshrc if you added it manually.");
         }
         Shell::Fish => {
-            println!("📝 No additional cleanup required for fish completion.");
+            out.info("📝 No additional cleanup required for fish completion.");
         }
         Shell::PowerShell => {
-            println!("📝 No additional cleanup required for PowerShell completion.");
+            out.info("📝 No additional cleanup required for PowerShell completion.");
         }
     }
 
@@ -18,6 +18,32 @@ use clap::Parser;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Output format for command results (default: text, or the
+    /// `preferences.format` setting)
+    #[arg(long, global = true, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Only print results, without progress messages or tips
+    #[arg(long, short, global = true)]
+    pub quiet: bool,
+
+    /// Do not color output (also disabled by NO_COLOR)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Print plain text without emoji
+    #[arg(long, global = true)]
+    pub no_emoji: bool,
+
+    /// Run as if env was started in DIR
+    #[arg(short = 'C', long = "project", global = true, value_name = "DIR")]
+    pub project: Option<std::path::PathBuf>,
+
+    /// Use this configuration file instead of searching for .env/config.toml
+    /// (also ENV_CLI_CONFIG)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<std::path::PathBuf>,
 }
 
 #[derive(Parser)]
@@ -41,21 +67,33 @@ pub enum Commands {
         /// Directory to scan (default: current directory)
         #[arg(default_value = ".")]
         path: std::path::PathBuf,
-        /// Output format (default: text)
-        #[arg(long, default_value = "text")]
-        format: OutputFormat,
         /// Include hidden files and directories
         #[arg(long)]
         hidden: bool,
+        /// Only fail on findings missing from this baseline file
+        #[arg(long, value_name = "FILE")]
+        baseline: Option<std::path::PathBuf>,
+        /// Write the current findings to a new baseline file
+        #[arg(long, requires = "baseline", conflicts_with = "update_baseline")]
+        create_baseline: bool,
+        /// Replace the baseline with the current findings
+        #[arg(long, requires = "baseline")]
+        update_baseline: bool,
     },
     /// Validate environment configuration
     Validate {
         /// Environment to validate (default: current)
-        #[arg(long, default_value = "current")]
-        env: String,
+        #[arg(long, conflicts_with = "all")]
+        env: Option<String>,
+        /// Validate every configured environment
+        #[arg(long)]
+        all: bool,
         /// Check for unused environment variables
         #[arg(long)]
         check_unused: bool,
+        /// Validate values as written, without expanding ${VAR} references
+        #[arg(long)]
+        raw: bool,
     },
     /// Sync environments safely
     Sync {
@@ -67,6 +105,31 @@ pub enum Commands {
         #[arg(long)]
         yes: bool,
     },
+    /// Show differences between two environments without changing them
+    Diff {
+        /// First environment name or env file path
+        source: String,
+        /// Second environment name or env file path
+        target: String,
+        /// Compare only which keys are defined, ignoring values
+        #[arg(long)]
+        keys_only: bool,
+        /// Show sensitive values instead of masking them
+        #[arg(long)]
+        show_secrets: bool,
+        /// Compare values as written, without expanding ${VAR} references
+        #[arg(long)]
+        raw: bool,
+        /// Exit with a failure status when differences are found
+        #[arg(long)]
+        exit_code: bool,
+    },
+    /// Show which variables each environment defines
+    Matrix {
+        /// Check values as written, without expanding ${VAR} references
+        #[arg(long)]
+        raw: bool,
+    },
     /// Generate .env.example file
     Generate {
         /// Output file path (default: .env.example)
@@ -87,6 +150,155 @@ pub enum Commands {
         /// Show detailed information
         #[arg(long)]
         verbose: bool,
+        /// Show values as written, without expanding ${VAR} references
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Print the value of a variable
+    Get {
+        /// Variable name
+        key: String,
+        /// Environment to read from (default: current)
+        #[arg(long)]
+        env: Option<String>,
+        /// Print the value as written, without expanding ${VAR} references
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Set a variable in an environment
+    Set {
+        /// Variable name
+        key: String,
+        /// Value to assign
+        value: String,
+        /// Environment to modify (default: current)
+        #[arg(long)]
+        env: Option<String>,
+        /// Store the value inline in config.toml instead of the environment file
+        #[arg(long)]
+        inline: bool,
+    },
+    /// Remove a variable from an environment
+    Unset {
+        /// Variable name
+        key: String,
+        /// Environment to modify (default: current)
+        #[arg(long)]
+        env: Option<String>,
+        /// Remove the inline value from config.toml instead of the environment file
+        #[arg(long)]
+        inline: bool,
+    },
+    /// List the variables of an environment
+    List {
+        /// Environment to list (default: current)
+        #[arg(long)]
+        env: Option<String>,
+        /// Show sensitive values instead of masking them
+        #[arg(long)]
+        show_secrets: bool,
+        /// Show values as written, without expanding ${VAR} references
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Show which file each variable's value comes from
+    Explain {
+        /// Variable to explain (default: every variable)
+        variable: Option<String>,
+        /// Environment to explain (default: current)
+        #[arg(long)]
+        env: Option<String>,
+        /// Show sensitive values instead of masking them
+        #[arg(long)]
+        show_secrets: bool,
+    },
+    /// Replace a secret with a freshly generated value
+    Rotate {
+        /// Variable to rotate
+        var: String,
+        /// Environment to update (default: current)
+        #[arg(long)]
+        env: Option<String>,
+        /// Length of the new value
+        #[arg(long, default_value_t = 32)]
+        length: usize,
+        /// Characters the new value is drawn from
+        #[arg(long, value_enum, default_value = "alnum")]
+        charset: SecretCharset,
+        /// Do not keep the old value as <VAR>_PREVIOUS; it stays in the backup only
+        #[arg(long)]
+        no_previous: bool,
+        /// Directory to scan for code using the variable (default: current)
+        #[arg(long, default_value = ".")]
+        scan_dir: std::path::PathBuf,
+    },
+    /// Encrypt the values of an environment file
+    Encrypt {
+        /// Environment to encrypt (default: current)
+        #[arg(long)]
+        env: Option<String>,
+        /// Only encrypt sensitive variables
+        #[arg(long)]
+        secrets_only: bool,
+    },
+    /// Decrypt the values of an environment file
+    Decrypt {
+        /// Environment to decrypt (default: current)
+        #[arg(long)]
+        env: Option<String>,
+        /// Print the decrypted file instead of rewriting it
+        #[arg(long)]
+        stdout: bool,
+    },
+    /// Manage the public keys that encrypted values are sealed for
+    Keys {
+        #[command(subcommand)]
+        action: KeysAction,
+    },
+    /// Show or edit the effective configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Re-encrypt values for the current recipient list
+    Rekey {
+        /// Environment to re-encrypt (default: all)
+        #[arg(long)]
+        env: Option<String>,
+    },
+    /// Run a command with an environment's variables injected
+    Run {
+        /// Environment to load (default: current)
+        #[arg(long)]
+        env: Option<String>,
+        /// Only inject variables whose whole names match this regular expression
+        /// (repeatable)
+        #[arg(long)]
+        only: Vec<String>,
+        /// Start the command with only the environment's variables
+        #[arg(long)]
+        no_inherit: bool,
+        /// Refuse to run if the environment fails validation
+        #[arg(long)]
+        validate: bool,
+        /// Command to run, after `--`
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Print a shell hook that loads the current environment on each prompt
+    Hook {
+        /// Shell type (bash, zsh, fish)
+        shell: String,
+    },
+    /// Print shell statements exporting the current environment
+    #[command(hide = true)]
+    Export {
+        /// Shell type (bash, zsh, fish)
+        #[arg(default_value = "bash")]
+        shell: String,
+        /// Only print changes since the previous invocation
+        #[arg(long)]
+        diff: bool,
     },
     /// Generate shell completion scripts
     Completion {
@@ -101,9 +313,77 @@ pub enum Commands {
     },
 }
 
-#[derive(clap::ValueEnum, Clone, Debug)]
+/// Subcommands of `env keys`.
+#[derive(clap::Subcommand, Clone, Debug)]
+pub enum KeysAction {
+    /// Create your identity and print its public key
+    Generate {
+        /// Replace an existing identity
+        #[arg(long)]
+        force: bool,
+    },
+    /// Add a recipient's public key to the project
+    Add {
+        /// Public key, as printed by `env keys generate`
+        key: String,
+        /// Name to list the recipient under (default: the key)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Remove a recipient by name or public key
+    Remove {
+        /// Recipient name or public key
+        recipient: String,
+    },
+    /// List the project's recipients
+    List,
+}
+
+/// Subcommands of `env config`.
+#[derive(clap::Subcommand, Clone, Debug)]
+pub enum ConfigAction {
+    /// Print the project configuration merged over the user configuration
+    Show {
+        /// List every setting with the file it comes from
+        #[arg(long)]
+        origin: bool,
+    },
+    /// Open the project configuration in your editor
+    Edit {
+        /// Edit the user configuration instead
+        #[arg(long)]
+        user: bool,
+    },
+    /// Upgrade the project configuration to the current format, keeping a backup
+    Migrate,
+}
+
+/// Formats command results can be printed in.
+///
+/// Every command supports text, JSON and YAML; the other formats are only
+/// offered by the commands they make sense for.
+#[derive(
+    clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
 pub enum OutputFormat {
     Text,
     Json,
     Yaml,
+    /// SARIF 2.1.0, for `scan` and `validate`
+    Sarif,
+    /// JUnit XML, for `validate`
+    Junit,
+    /// Markdown table, for `matrix`
+    Markdown,
+    /// CSV table, for `matrix`
+    Csv,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum SecretCharset {
+    Base64,
+    Hex,
+    Alnum,
+    Symbols,
 }
@@ -6,9 +6,10 @@ use crate::cli::completion::{
     install_completion, uninstall_completion, CompletionGenerator, Shell,
 };
 use crate::error::Result;
+use crate::report::Output;
 
 /// Execute the completion command.
-pub async fn execute(shell: String, install: bool, uninstall: bool) -> Result<()> {
+pub async fn execute(shell: String, install: bool, uninstall: bool, out: &Output) -> Result<()> {
     // Parse shell type
     let shell: Shell = shell.parse()?;
 
@@ -20,12 +21,12 @@ pub async fn execute(shell: String, install: bool, uninstall: bool) -> Result<()
     }
 
     if install {
-        install_completion(shell)?;
+        install_completion(shell, out)?;
         return Ok(());
     }
 
     if uninstall {
-        uninstall_completion(shell)?;
+        uninstall_completion(shell, out)?;
         return Ok(());
     }
 
@@ -33,7 +34,7 @@ pub async fn execute(shell: String, install: bool, uninstall: bool) -> Result<()
     let generator = CompletionGenerator::new();
     let script = generator.generate(shell)?;
 
-    // Print completion script to stdout
+    // The script is a raw payload for the shell, exempt from the output flags
     println!("{}", script);
 
     Ok(())
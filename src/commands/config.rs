@@ -0,0 +1,273 @@
+//! Config command implementation.
+//!
+//! `config show` prints the effective configuration: the project's
+//! `.env/config.toml` merged over the user configuration (see
+//! [`crate::config::user`]). With `--origin` every setting is listed with the
+//! files it comes from. `config edit` opens either file in the preferred
+//! editor, and `config migrate` rewrites the project's in the current format
+//! (see [`crate::config::migrate`]).
+
+use super::copy_to_backups;
+use crate::cli::ConfigAction;
+use crate::config::{keys, user, Config, ConfigSource, Project, CONFIG_VERSION};
+use crate::error::{EnvCliError, Result};
+use crate::report::{Output, Report};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Run a `config` subcommand.
+pub async fn execute(action: ConfigAction, project: &Project, out: &Output) -> Result<()> {
+    match action {
+        ConfigAction::Show { origin } => show(origin, project, out),
+        ConfigAction::Edit { user } => edit(user, project, out),
+        ConfigAction::Migrate => migrate(project, out),
+    }
+}
+
+/// The effective configuration.
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+struct EffectiveConfig {
+    config: Config,
+    #[serde(skip)]
+    toml: String,
+}
+
+impl Report for EffectiveConfig {
+    fn print_text(&self, out: &Output) {
+        out.line(self.toml.trim_end());
+    }
+}
+
+/// Every effective setting, with where it comes from.
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+struct SettingList {
+    settings: Vec<Setting>,
+}
+
+#[derive(Debug, Serialize)]
+struct Setting {
+    /// Dotted key, with `[index]` for entries of arrays of tables
+    key: String,
+    value: toml::Value,
+    /// Files the value comes from, highest precedence first; empty for
+    /// built-in defaults
+    origin: Vec<PathBuf>,
+}
+
+impl Setting {
+    fn origin_label(&self) -> String {
+        if self.origin.is_empty() {
+            return "default".to_string();
+        }
+        self.origin
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Report for SettingList {
+    fn print_text(&self, out: &Output) {
+        let labels: Vec<String> = self.settings.iter().map(Setting::origin_label).collect();
+        let width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        for (setting, label) in self.settings.iter().zip(&labels) {
+            out.line(format!(
+                "{:<width$}  {} = {}",
+                label,
+                setting.key,
+                setting.value,
+                width = width
+            ));
+        }
+    }
+}
+
+fn show(origin: bool, project: &Project, out: &Output) -> Result<()> {
+    out.ensure_supported::<SettingList>()?;
+    let config = project.load_config()?;
+
+    if !origin {
+        return out.render(&EffectiveConfig {
+            toml: toml::to_string_pretty(&config)?,
+            config,
+        });
+    }
+
+    let sources: Vec<(PathBuf, toml::Value)> = project
+        .config_sources()?
+        .into_iter()
+        .map(|source| (source.path, toml::Value::Table(source.table)))
+        .collect();
+    let origins: Vec<(&Path, Option<&toml::Value>)> = sources
+        .iter()
+        .map(|(path, table)| (path.as_path(), Some(table)))
+        .collect();
+
+    let mut settings = Vec::new();
+    collect(
+        "",
+        &toml::Value::try_from(&config)?,
+        &origins,
+        &mut settings,
+    );
+    out.render(&SettingList { settings })
+}
+
+/// List the leaves of `value` under `key`, with the files among `sources`
+/// (lowest precedence first, each with its value at `key`) that set them.
+fn collect(
+    key: &str,
+    value: &toml::Value,
+    sources: &[(&Path, Option<&toml::Value>)],
+    settings: &mut Vec<Setting>,
+) {
+    match value {
+        toml::Value::Table(table) => {
+            for (name, value) in table {
+                let sources: Vec<_> = sources
+                    .iter()
+                    .map(|(path, source)| (*path, source.and_then(|s| s.get(name))))
+                    .collect();
+                collect(&keys::join(key, name), value, &sources, settings);
+            }
+        }
+        toml::Value::Array(entries)
+            if !entries.is_empty() && entries.iter().all(toml::Value::is_table) =>
+        {
+            // Merging puts the entries of the highest-precedence file first,
+            // then the ones only lower files have
+            let mut merged: Vec<(&Path, &toml::Value)> = Vec::new();
+            for (path, source) in sources.iter().rev() {
+                for entry in source.and_then(toml::Value::as_array).into_iter().flatten() {
+                    if !merged.iter().any(|(_, seen)| *seen == entry) {
+                        merged.push((path, entry));
+                    }
+                }
+            }
+            for (index, entry) in entries.iter().enumerate() {
+                let source: Vec<_> = merged
+                    .get(index)
+                    .map(|(path, raw)| (*path, Some(*raw)))
+                    .into_iter()
+                    .collect();
+                collect(&format!("{}[{}]", key, index), entry, &source, settings);
+            }
+        }
+        _ => {
+            let mut origin: Vec<PathBuf> = sources
+                .iter()
+                .rev()
+                .filter(|(_, source)| source.is_some())
+                .map(|(path, _)| path.to_path_buf())
+                .collect();
+            // Only arrays combine values from several files
+            if !value.is_array() {
+                origin.truncate(1);
+            }
+            settings.push(Setting {
+                key: key.to_string(),
+                value: value.clone(),
+                origin,
+            });
+        }
+    }
+}
+
+/// Open the project or user configuration in the preferred editor.
+fn edit(user: bool, project: &Project, out: &Output) -> Result<()> {
+    let path = if user {
+        let path = user::user_config_path().ok_or_else(|| {
+            EnvCliError::Config("Unable to determine the user configuration directory".to_string())
+        })?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if !path.exists() {
+            std::fs::write(&path, "")?;
+        }
+        path
+    } else {
+        if !project.is_initialized() {
+            return Err(EnvCliError::NotInitialized);
+        }
+        project.config_path().to_path_buf()
+    };
+
+    let editor = project
+        .preferences()?
+        .editor
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status()
+        .map_err(|e| EnvCliError::Command(format!("Failed to run '{}': {}", editor, e)))?;
+    if !status.success() {
+        return Err(EnvCliError::Command(format!(
+            "'{}' exited with {}",
+            editor, status
+        )));
+    }
+
+    // Report mistakes while the file is still fresh in mind
+    ConfigSource::read(&path)?;
+    if project.is_initialized() {
+        project.load_config()?;
+    }
+    out.info(format!("✓ Checked {}", path.display()));
+
+    Ok(())
+}
+
+/// Rewrite the project configuration in the current format.
+fn migrate(project: &Project, out: &Output) -> Result<()> {
+    if !project.is_initialized() {
+        return Err(EnvCliError::NotInitialized);
+    }
+    let path = project.config_path();
+    let mut source = ConfigSource::read(path)?;
+    let applied = source.migrate()?;
+    let Some(first) = applied.first() else {
+        out.info(format!(
+            "✓ {} is already at version {}",
+            path.display(),
+            CONFIG_VERSION
+        ));
+        return Ok(());
+    };
+    // Check the result before anything is written
+    let config = Config::from_sources(std::slice::from_ref(&source))?;
+
+    let backup_path = copy_to_backups(project, path, "config", "toml")?;
+    out.info(format!("✓ Created backup: {}", backup_path.display()));
+
+    project.save_config(&config)?;
+    out.info(format!(
+        "✓ Migrated {} from version {} to {}",
+        path.display(),
+        first.from,
+        CONFIG_VERSION
+    ));
+    for migration in &applied {
+        out.info(format!(
+            "  {} → {}: {}",
+            migration.from,
+            migration.from + 1,
+            migration.description
+        ));
+    }
+    if !keys::unknown_keys(&source.table).is_empty() {
+        out.warn("⚠️  Unknown keys were not carried over; the backup still has them");
+    }
+
+    Ok(())
+}
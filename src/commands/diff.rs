@@ -0,0 +1,180 @@
+//! Diff command implementation.
+//!
+//! Compares two environments, or two env files, without modifying either.
+//! The comparison reuses [`EnvironmentSync::detect_conflicts`], so `diff`
+//! reports exactly what `sync` would act on.
+
+use super::status::mask_sensitive_value;
+use super::{load_target, load_variables};
+use crate::config::Project;
+use crate::env::{EnvManager, ExpandOptions};
+use crate::error::{EnvCliError, Result};
+use crate::report::{Output, Report, Style};
+use crate::sync::{ConflictType, EnvironmentSync, SyncConfig};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Differences between two environments.
+#[derive(Debug, Default, Serialize)]
+struct DiffReport {
+    source: String,
+    target: String,
+    /// Keys defined only in the target
+    added: Vec<DiffEntry>,
+    /// Keys defined only in the source
+    removed: Vec<DiffEntry>,
+    /// Keys defined in both with different values
+    changed: Vec<ChangedEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct DiffEntry {
+    key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChangedEntry {
+    key: String,
+    source: String,
+    target: String,
+}
+
+impl DiffReport {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Show the differences between two environments or env files.
+pub async fn execute(
+    source: String,
+    target: String,
+    keys_only: bool,
+    show_secrets: bool,
+    raw: bool,
+    exit_code: bool,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    out.ensure_supported::<DiffReport>()?;
+    let source_vars = load_side(project, &source, raw)?;
+    let target_vars = load_side(project, &target, raw)?;
+
+    let engine = EnvironmentSync::new(SyncConfig::default());
+    let conflicts = engine.detect_conflicts(&source_vars, &target_vars);
+
+    let show = |key: &str, value: &str| {
+        if show_secrets {
+            value.to_string()
+        } else {
+            mask_sensitive_value(key, value)
+        }
+    };
+
+    let mut report = DiffReport {
+        source,
+        target,
+        ..Default::default()
+    };
+    for conflict in conflicts {
+        match conflict.conflict_type {
+            ConflictType::MissingInSource => report.added.push(DiffEntry {
+                value: (!keys_only).then(|| show(&conflict.variable, &conflict.target_value)),
+                key: conflict.variable,
+            }),
+            ConflictType::MissingInTarget => report.removed.push(DiffEntry {
+                value: (!keys_only).then(|| show(&conflict.variable, &conflict.source_value)),
+                key: conflict.variable,
+            }),
+            _ if keys_only => {}
+            _ => report.changed.push(ChangedEntry {
+                source: show(&conflict.variable, &conflict.source_value),
+                target: show(&conflict.variable, &conflict.target_value),
+                key: conflict.variable,
+            }),
+        }
+    }
+
+    out.render(&report)?;
+
+    if exit_code && !report.is_empty() {
+        return Err(EnvCliError::Validation(format!(
+            "'{}' and '{}' differ",
+            report.source, report.target
+        )));
+    }
+
+    Ok(())
+}
+
+/// Load one side of the comparison: an existing file path, or an environment name.
+fn load_side(project: &Project, name: &str, raw: bool) -> Result<HashMap<String, String>> {
+    let env_manager = if Path::new(name).is_file() {
+        let mut env_manager = EnvManager::for_project(project);
+        env_manager.load_from_file(Path::new(name))?;
+        if !raw {
+            env_manager.expand(ExpandOptions::default())?;
+        }
+        env_manager
+    } else {
+        let (config, env_name) = load_target(project, Some(name.to_string()))?;
+        load_variables(project, &config, &env_name, raw)?
+    };
+
+    Ok(env_manager
+        .list()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect())
+}
+
+impl Report for DiffReport {
+    fn print_text(&self, out: &Output) {
+        out.line(out.paint(Style::Bold, format!("--- {}", self.source)));
+        out.line(out.paint(Style::Bold, format!("+++ {}", self.target)));
+
+        if self.is_empty() {
+            out.line("No differences");
+            return;
+        }
+
+        let mut lines: Vec<(&str, String)> = Vec::new();
+        for entry in &self.removed {
+            let line = match &entry.value {
+                Some(value) => format!("- {}={}", entry.key, value),
+                None => format!("- {}", entry.key),
+            };
+            lines.push((&entry.key, out.paint(Style::Red, line)));
+        }
+        for entry in &self.added {
+            let line = match &entry.value {
+                Some(value) => format!("+ {}={}", entry.key, value),
+                None => format!("+ {}", entry.key),
+            };
+            lines.push((&entry.key, out.paint(Style::Green, line)));
+        }
+        for entry in &self.changed {
+            let line = format!(
+                "{}\n{}",
+                out.paint(Style::Red, format!("- {}={}", entry.key, entry.source)),
+                out.paint(Style::Green, format!("+ {}={}", entry.key, entry.target))
+            );
+            lines.push((&entry.key, line));
+        }
+        lines.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (_, line) in lines {
+            out.line(line);
+        }
+
+        out.line("");
+        out.line(format!(
+            "{} added, {} removed, {} changed",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        ));
+    }
+}
@@ -0,0 +1,109 @@
+//! Encrypt and decrypt command implementations.
+//!
+//! `encrypt` seals each value of an environment file in place, for the
+//! configured recipients or with the project key, generating the project key
+//! on first use. `decrypt` reverses it, or
+//! prints the plaintext without touching the file. See [`crate::crypto`] for
+//! the format.
+
+use super::validate::is_sensitive_key;
+use super::{git_ignore, load_target};
+use crate::config::Project;
+use crate::crypto::{self, Keyring, ProjectKey};
+use crate::env::EnvDocument;
+use crate::error::Result;
+use crate::report::Output;
+
+/// Encrypt the values of an environment file.
+pub async fn encrypt(
+    env: Option<String>,
+    secrets_only: bool,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    let (config, env_name) = load_target(project, env)?;
+    let env_file = config.environment_file(&env_name);
+
+    let mut keyring = Keyring::load(project)?;
+    if !keyring.can_encrypt() {
+        let key = ProjectKey::generate();
+        let key_path = project.key_path();
+        key.save(&key_path)?;
+        if let (Some(dir), Some(name)) = (key_path.parent(), key_path.file_name()) {
+            git_ignore(dir, &name.to_string_lossy())?;
+        }
+        out.info(format!("✓ Generated project key: {}", key_path.display()));
+        out.info("  Keep it out of version control and share it through a secret manager.");
+        out.info(format!(
+            "  CI can provide it through the {} environment variable.",
+            crypto::KEY_ENV_VAR
+        ));
+        keyring = keyring.with_project_key(key);
+    }
+
+    let mut document = EnvDocument::load(&env_file)?;
+    let encrypted = document.update_values(|name, value| {
+        if crypto::is_encrypted(value) || (secrets_only && !is_sensitive_key(name, &config)) {
+            return Ok(None);
+        }
+        keyring.encrypt_value(name, value).map(Some)
+    })?;
+
+    if encrypted == 0 {
+        out.info(format!("Nothing to encrypt in '{}'", env_name));
+        return Ok(());
+    }
+
+    document.save(&env_file)?;
+    out.info(format!(
+        "✓ Encrypted {} value(s) in '{}'",
+        encrypted, env_name
+    ));
+
+    Ok(())
+}
+
+/// Decrypt the values of an environment file, in place or to stdout.
+pub async fn decrypt(
+    env: Option<String>,
+    stdout: bool,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    let (config, env_name) = load_target(project, env)?;
+    let env_file = config.environment_file(&env_name);
+
+    let mut document = EnvDocument::load(&env_file)?;
+    if !crypto::document_is_encrypted(&document) {
+        if stdout {
+            // The file is a raw payload, exempt from the output flags
+            print!("{}", document);
+        } else {
+            out.info(format!("'{}' is not encrypted", env_name));
+        }
+        return Ok(());
+    }
+
+    let keyring = Keyring::load(project)?;
+    let decrypted = document.update_values(|name, value| {
+        if crypto::is_encrypted(value) {
+            keyring.decrypt_value(name, value).map(Some)
+        } else {
+            Ok(None)
+        }
+    })?;
+
+    if stdout {
+        print!("{}", document);
+        return Ok(());
+    }
+
+    document.save(&env_file)?;
+    out.info(format!(
+        "✓ Decrypted {} value(s) in '{}'",
+        decrypted, env_name
+    ));
+    out.info("  The file now holds plaintext secrets; run 'env encrypt' before committing it.");
+
+    Ok(())
+}
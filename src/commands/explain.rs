@@ -0,0 +1,192 @@
+//! Explain command implementation.
+//!
+//! Shows the layers an environment is read from, following `extends` and
+//! `.local` override files, and which of them each value comes from.
+
+use super::status::mask_sensitive_value;
+use super::{load_target, load_variables};
+use crate::config::Project;
+use crate::env::{Layer, LayerKind};
+use crate::error::{EnvCliError, Result};
+use crate::report::{Output, Report};
+use serde::Serialize;
+
+/// Where an environment's values come from.
+#[derive(Debug, Serialize)]
+pub struct ExplainReport {
+    pub environment: String,
+    /// Every layer of the inheritance chain, lowest precedence first
+    pub layers: Vec<LayerStatus>,
+    pub variables: Vec<ExplainedVariable>,
+    #[serde(skip)]
+    single: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LayerStatus {
+    #[serde(flatten)]
+    pub layer: Layer,
+    /// Whether the file exists; only `.local` files are optional
+    pub present: bool,
+}
+
+/// A variable's value and every layer that set it.
+#[derive(Debug, Serialize)]
+pub struct ExplainedVariable {
+    pub name: String,
+    /// The value in effect, with references expanded
+    pub value: String,
+    pub origin: Layer,
+    /// Values as written, lowest precedence first; the last one wins
+    pub definitions: Vec<Definition>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Definition {
+    #[serde(flatten)]
+    pub layer: Layer,
+    pub value: String,
+}
+
+impl Report for ExplainReport {
+    fn print_text(&self, out: &Output) {
+        let width = self
+            .layers
+            .iter()
+            .map(|status| status.layer.to_string().chars().count())
+            .max()
+            .unwrap_or(0);
+
+        if !self.single {
+            out.line(format!(
+                "Layers of '{}', lowest precedence first:",
+                self.environment
+            ));
+            for status in &self.layers {
+                out.line(format!(
+                    "  {:<width$}  {}{}",
+                    status.layer.to_string(),
+                    status.layer.file.display(),
+                    if status.present { "" } else { " (not present)" },
+                    width = width
+                ));
+            }
+
+            out.line("");
+            out.line(format!("Variables ({}):", self.variables.len()));
+            let name_width = self
+                .variables
+                .iter()
+                .map(|variable| variable.name.chars().count())
+                .max()
+                .unwrap_or(0);
+            for variable in &self.variables {
+                out.line(format!(
+                    "  {:<width$}  {}",
+                    variable.name,
+                    variable.origin,
+                    width = name_width
+                ));
+            }
+            return;
+        }
+
+        for variable in &self.variables {
+            out.line(format!(
+                "{} = {} (in '{}')",
+                variable.name, variable.value, self.environment
+            ));
+            let file_width = variable
+                .definitions
+                .iter()
+                .map(|definition| definition.layer.file.display().to_string().chars().count())
+                .max()
+                .unwrap_or(0);
+            let last = variable.definitions.len().saturating_sub(1);
+            for (i, definition) in variable.definitions.iter().enumerate() {
+                out.line(format!(
+                    "  {:<width$}  {:<file_width$}  {}{}",
+                    definition.layer.to_string(),
+                    definition.layer.file.display().to_string(),
+                    definition.value,
+                    if i == last { "  ← in effect" } else { "" },
+                    width = width,
+                    file_width = file_width
+                ));
+            }
+        }
+    }
+}
+
+/// Explain where one variable, or every variable, of an environment is set.
+pub async fn execute(
+    variable: Option<String>,
+    env: Option<String>,
+    show_secrets: bool,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    out.ensure_supported::<ExplainReport>()?;
+    let (config, env_name) = load_target(project, env)?;
+    let env_manager = load_variables(project, &config, &env_name, false)?;
+
+    let shown = |key: &str, value: &str| {
+        if show_secrets {
+            value.to_string()
+        } else {
+            mask_sensitive_value(key, value)
+        }
+    };
+
+    let names: Vec<String> = match &variable {
+        Some(name) if env_manager.get(name).is_none() => {
+            return Err(EnvCliError::Environment(format!(
+                "Variable '{}' is not set in environment '{}'",
+                name, env_name
+            )));
+        }
+        Some(name) => vec![name.clone()],
+        None => {
+            let mut names: Vec<String> = env_manager.list().map(|(key, _)| key.clone()).collect();
+            names.sort();
+            names
+        }
+    };
+
+    let variables = names
+        .into_iter()
+        .filter_map(|name| {
+            let origin = env_manager.origin(&name)?.clone();
+            let definitions = env_manager
+                .history(&name)
+                .into_iter()
+                .map(|(layer, value)| Definition {
+                    layer: layer.clone(),
+                    value: shown(&name, value),
+                })
+                .collect();
+            Some(ExplainedVariable {
+                value: shown(&name, env_manager.get(&name)?),
+                name,
+                origin,
+                definitions,
+            })
+        })
+        .collect();
+
+    let layers = config
+        .layers(&env_name)?
+        .into_iter()
+        .map(|layer| LayerStatus {
+            present: layer.kind == LayerKind::Inline || layer.file.exists(),
+            layer,
+        })
+        .collect();
+
+    out.render(&ExplainReport {
+        environment: env_name,
+        layers,
+        variables,
+        single: variable.is_some(),
+    })
+}
@@ -1,49 +1,194 @@
 //! Advanced generate command implementation for EC-03.
 
+use crate::config::{Config, Project};
 use crate::env::EnvUsage;
 use crate::error::Result;
-use crate::scan::CodeScanner;
+use crate::report::{Output, Report};
+use crate::scan::{CodeScanner, SecuritySeverity};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+/// What `env generate` wrote.
+#[derive(Debug, Serialize)]
+pub struct GenerateReport {
+    /// The example file, unless no variables were found
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<PathBuf>,
+    pub files_scanned: usize,
+    pub variables: Vec<GeneratedVariable>,
+    pub security_issues: SecuritySummary,
+}
+
+/// A variable written to the example file.
+#[derive(Debug, Serialize)]
+pub struct GeneratedVariable {
+    pub name: String,
+    pub category: &'static str,
+    pub locations: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SecuritySummary {
+    pub total: usize,
+    pub critical: usize,
+    pub high: usize,
+}
+
+/// Display order of the variable categories.
+const CATEGORIES: [&str; 6] = [
+    "Database",
+    "API & Authentication",
+    "Security",
+    "Network",
+    "Logging & Debugging",
+    "General",
+];
+
+impl Report for GenerateReport {
+    fn print_text(&self, out: &Output) {
+        let Some(output) = &self.output else {
+            out.line("\n⚠️  No environment variables found in the codebase.");
+            out.info("💡 Make sure you're scanning the correct directory.");
+            return;
+        };
+
+        out.line(format!(
+            "\n📋 Generated .env.example with {} variables:",
+            self.variables.len()
+        ));
+        for category in CATEGORIES {
+            let variables: Vec<_> = self
+                .variables
+                .iter()
+                .filter(|variable| variable.category == category)
+                .collect();
+            if variables.is_empty() {
+                continue;
+            }
+            out.line(format!("  🔧 {} ({}):", category, variables.len()));
+            for variable in variables {
+                out.line(format!(
+                    "    - {} (used in {} location(s))",
+                    variable.name, variable.locations
+                ));
+            }
+        }
+
+        if let Some(docs_path) = &self.documentation {
+            out.line(format!(
+                "\n✅ Documentation generated: {}",
+                docs_path.display()
+            ));
+        }
+
+        if self.security_issues.total > 0 {
+            out.line("\n⚠️  Security Summary:");
+            out.line(format!(
+                "  - Total security issues: {}",
+                self.security_issues.total
+            ));
+            if self.security_issues.critical > 0 {
+                out.line(format!(
+                    "  - {} Critical issues",
+                    self.security_issues.critical
+                ));
+            }
+            if self.security_issues.high > 0 {
+                out.line(format!(
+                    "  - {} High severity issues",
+                    self.security_issues.high
+                ));
+            }
+            out.info("💡 Review the generated documentation for detailed security analysis.");
+        }
+
+        out.line("\n✨ .env.example generated successfully!");
+        out.line(format!("📁 File saved to: {}", output.display()));
+        if let Some(docs_path) = &self.documentation {
+            out.line(format!(
+                "📚 Documentation saved to: {}",
+                docs_path.display()
+            ));
+        }
+
+        out.info("💡 Tips:");
+        out.info("  - Review the generated file and add example values");
+        out.info("  - Add descriptions for complex variables");
+        out.info("  - Consider security implications of each variable");
+        out.info("  - Test the example file with your application");
+    }
+}
+
 /// Generate .env.example file with advanced automated documentation.
 pub async fn execute(
     output: PathBuf,
     comments: bool,
     docs: bool,
     scan_dir: Option<PathBuf>,
+    project: &Project,
+    out: &Output,
 ) -> Result<()> {
-    println!("🔧 Generating .env.example file with advanced features...");
-    println!("📄 Output: {}", output.display());
-    println!("💬 Include comments: {}", comments);
-    println!("📚 Generate documentation: {}", docs);
+    out.ensure_supported::<GenerateReport>()?;
+    out.progress("🔧 Generating .env.example file with advanced features...");
+    out.progress(format!("📄 Output: {}", output.display()));
+    out.progress(format!("💬 Include comments: {}", comments));
+    out.progress(format!("📚 Generate documentation: {}", docs));
 
     let scan_path = scan_dir.unwrap_or_else(|| PathBuf::from("."));
 
+    // Descriptions and defaults come from the variable schema, if there is one
+    let config = if project.is_initialized() {
+        Some(project.load_config()?)
+    } else {
+        None
+    };
+
     // Initialize advanced scanner
-    let scanner = CodeScanner::new()?;
+    let mut scanner = CodeScanner::new()?;
+    if let Some(config) = &config {
+        scanner = scanner.with_exclusions(&config.scan);
+    }
 
-    println!("🔍 Scanning codebase for environment variables...");
-    println!("📁 Scan directory: {}", scan_path.display());
+    out.progress("🔍 Scanning codebase for environment variables...");
+    out.progress(format!("📁 Scan directory: {}", scan_path.display()));
 
     // Perform comprehensive scan
     let scan_result = scanner.scan_directory_advanced(&scan_path).await?;
 
-    println!("✅ Scan completed!");
-    println!("📊 Scan Statistics:");
-    println!("  - Files scanned: {}", scan_result.files_scanned);
-    println!("  - Variables found: {}", scan_result.variables.len());
-    println!(
+    out.progress("✅ Scan completed!");
+    out.progress("📊 Scan Statistics:");
+    out.progress(format!("  - Files scanned: {}", scan_result.files_scanned));
+    out.progress(format!(
+        "  - Variables found: {}",
+        scan_result.variables.len()
+    ));
+    out.progress(format!(
         "  - Languages detected: {}",
         scan_result.languages_detected.len()
-    );
+    ));
+
+    let mut scan_result = scan_result;
+    if let Some(config) = &config {
+        apply_schema(config, &mut scan_result.variables);
+    }
+
+    let mut report = GenerateReport {
+        output: None,
+        documentation: None,
+        files_scanned: scan_result.files_scanned,
+        variables: Vec::new(),
+        security_issues: SecuritySummary::default(),
+    };
 
     // Extract variables for generation
-    let variables: Vec<_> = scan_result.variables.values().collect();
+    let mut variables: Vec<_> = scan_result.variables.values().collect();
+    variables.sort_by(|a, b| a.name.cmp(&b.name));
 
     if variables.is_empty() {
-        println!("\n⚠️  No environment variables found in the codebase.");
-        println!("💡 Make sure you're scanning the correct directory.");
-        return Ok(());
+        return out.render(&report);
     }
 
     // Generate .env.example content
@@ -58,113 +203,86 @@ pub async fn execute(
     // Write .env.example file
     std::fs::write(&output, env_example_content)?;
 
-    println!(
-        "\n📋 Generated .env.example with {} variables:",
-        variables.len()
-    );
-
-    // Group variables by type for display
-    let mut database_vars = Vec::new();
-    let mut api_vars = Vec::new();
-    let mut security_vars = Vec::new();
-    let mut network_vars = Vec::new();
-    let mut logging_vars = Vec::new();
-    let mut general_vars = Vec::new();
-
-    for usage in variables {
-        if usage.name.contains("DATABASE") || usage.name.contains("DB") {
-            database_vars.push(usage);
-        } else if usage.name.contains("API") || usage.name.contains("TOKEN") {
-            api_vars.push(usage);
-        } else if usage.name.to_uppercase().contains("SECRET")
-            || usage.name.to_uppercase().contains("KEY")
-        {
-            security_vars.push(usage);
-        } else if usage.name.contains("PORT") || usage.name.contains("HOST") {
-            network_vars.push(usage);
-        } else if usage.name.contains("LOG") || usage.name.contains("DEBUG") {
-            logging_vars.push(usage);
-        } else {
-            general_vars.push(usage);
-        }
-    }
-
-    let display_category = |name: &str, vars: Vec<&crate::env::EnvUsage>| {
-        if !vars.is_empty() {
-            println!("  🔧 {} ({}):", name, vars.len());
-            for usage in vars {
-                println!(
-                    "    - {} (used in {} location(s))",
-                    usage.name,
-                    usage.files.len()
-                );
-            }
-        }
-    };
-
-    display_category("Database", database_vars);
-    display_category("API & Authentication", api_vars);
-    display_category("Security", security_vars);
-    display_category("Network", network_vars);
-    display_category("Logging & Debugging", logging_vars);
-    display_category("General", general_vars);
+    report.variables = variables
+        .into_iter()
+        .map(|usage| GeneratedVariable {
+            name: usage.name.clone(),
+            category: category(&usage.name),
+            locations: usage.files.len(),
+        })
+        .collect();
 
     // Generate documentation if requested
     if docs {
-        println!("\n📚 Generating comprehensive documentation...");
+        out.progress("📚 Generating comprehensive documentation...");
 
         let documentation = scanner.generate_documentation(&scan_result)?;
         let docs_path = output.with_extension("md");
 
         std::fs::write(&docs_path, documentation)?;
-
-        println!("✅ Documentation generated: {}", docs_path.display());
+        report.documentation = Some(docs_path);
     }
 
-    // Display security summary
-    if !scan_result.security_issues.is_empty() {
-        println!("\n⚠️  Security Summary:");
-        println!(
-            "  - Total security issues: {}",
-            scan_result.security_issues.len()
-        );
-
-        let critical_count = scan_result
-            .security_issues
-            .iter()
-            .filter(|i| matches!(i.severity, crate::scan::SecuritySeverity::Critical))
-            .count();
-        let high_count = scan_result
+    let count = |severity: SecuritySeverity| {
+        scan_result
             .security_issues
             .iter()
-            .filter(|i| matches!(i.severity, crate::scan::SecuritySeverity::High))
-            .count();
+            .filter(|i| i.severity == severity)
+            .count()
+    };
+    report.security_issues = SecuritySummary {
+        total: scan_result.security_issues.len(),
+        critical: count(SecuritySeverity::Critical),
+        high: count(SecuritySeverity::High),
+    };
+    report.output = Some(output);
 
-        if critical_count > 0 {
-            println!("  - {} Critical issues", critical_count);
-        }
-        if high_count > 0 {
-            println!("  - {} High severity issues", high_count);
-        }
+    out.render(&report)
+}
 
-        println!("💡 Review the generated documentation for detailed security analysis.");
+/// Group a variable by what its name suggests it configures.
+fn category(name: &str) -> &'static str {
+    if name.contains("DATABASE") || name.contains("DB") {
+        "Database"
+    } else if name.contains("API") || name.contains("TOKEN") {
+        "API & Authentication"
+    } else if name.to_uppercase().contains("SECRET") || name.to_uppercase().contains("KEY") {
+        "Security"
+    } else if name.contains("PORT") || name.contains("HOST") {
+        "Network"
+    } else if name.contains("LOG") || name.contains("DEBUG") {
+        "Logging & Debugging"
+    } else {
+        "General"
     }
+}
 
-    println!("\n✨ .env.example generated successfully!");
-    println!("📁 File saved to: {}", output.display());
+/// Merge `[variables.NAME]` schema entries into the scanned variables.
+///
+/// Declared variables are included even when the scan did not find them.
+fn apply_schema(config: &Config, variables: &mut HashMap<String, EnvUsage>) {
+    for (name, spec) in &config.variables {
+        let usage = variables
+            .entry(name.clone())
+            .or_insert_with(|| EnvUsage::new(name.clone()));
 
-    if docs {
-        println!(
-            "📚 Documentation saved to: {}",
-            output.with_extension("md").display()
-        );
+        let mut description = spec.description.clone().unwrap_or_default();
+        if let Some(note) = &spec.deprecated {
+            description = format!("{} (deprecated: {})", description, note)
+                .trim()
+                .to_string();
+        }
+        if let Some(example) = &spec.example {
+            description = format!("{} Example: {}", description, example)
+                .trim()
+                .to_string();
+        }
+        if !description.is_empty() {
+            usage.set_description(description);
+        }
+        if let Some(default) = &spec.default {
+            usage.set_default(default.clone());
+        }
+        usage.set_required(spec.required || !spec.required_in.is_empty());
     }
-
-    println!("💡 Tips:");
-    println!("  - Review the generated file and add example values");
-    println!("  - Add descriptions for complex variables");
-    println!("  - Consider security implications of each variable");
-    println!("  - Test the example file with your application");
-
-    Ok(())
 }
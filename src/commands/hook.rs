@@ -0,0 +1,220 @@
+//! Shell integration hook.
+//!
+//! `env hook <shell>` prints a snippet that runs `env export --diff <shell>` on
+//! every prompt. The export command compares the variables of the project's
+//! current environment with what the hook applied last time (kept in the
+//! `ENV_CLI_STATE` shell variable) and prints only the `export`/`unset`
+//! statements needed to catch up. Variables the hook overrode are restored to
+//! their earlier values once they no longer apply, for example after leaving
+//! the project directory or switching environments.
+
+use crate::cli::completion::Shell;
+use crate::config::Project;
+use crate::env::{parser, EnvManager, ExpandOptions};
+use crate::error::{EnvCliError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Shell variable holding the hook's bookkeeping between prompts.
+const STATE_VAR: &str = "ENV_CLI_STATE";
+
+/// Variables applied by the hook, and the values they replaced.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HookState {
+    /// Variables currently exported by the hook
+    applied: BTreeMap<String, String>,
+    /// Value each applied variable had before the hook first set it
+    previous: BTreeMap<String, Option<String>>,
+}
+
+/// Print the shell snippet that installs the hook.
+pub async fn execute(shell: String) -> Result<()> {
+    let shell: Shell = shell.parse()?;
+    let exe = std::env::current_exe()?;
+    let exe = exe.to_string_lossy();
+
+    let snippet = match shell {
+        Shell::Bash => format!(
+            r#"_env_cli_hook() {{
+  local previous_exit_status=$?
+  eval "$("{exe}" export --diff bash)"
+  return $previous_exit_status
+}}
+if [[ ";${{PROMPT_COMMAND[*]:-}};" != *";_env_cli_hook;"* ]]; then
+  PROMPT_COMMAND="_env_cli_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+fi"#
+        ),
+        Shell::Zsh => format!(
+            r#"_env_cli_hook() {{
+  eval "$("{exe}" export --diff zsh)"
+}}
+typeset -ag precmd_functions chpwd_functions
+if (( ! ${{precmd_functions[(I)_env_cli_hook]}} )); then
+  precmd_functions=(_env_cli_hook $precmd_functions)
+fi
+if (( ! ${{chpwd_functions[(I)_env_cli_hook]}} )); then
+  chpwd_functions=(_env_cli_hook $chpwd_functions)
+fi"#
+        ),
+        Shell::Fish => format!(
+            r#"function __env_cli_hook --on-event fish_prompt
+    "{exe}" export --diff fish | source
+end"#
+        ),
+        Shell::PowerShell => {
+            return Err(EnvCliError::InvalidArgument(
+                "The shell hook supports bash, zsh and fish".to_string(),
+            ))
+        }
+    };
+
+    println!("{}", snippet);
+    Ok(())
+}
+
+/// Print shell statements exporting the current environment.
+///
+/// With `diff`, only the changes since the previous invocation are printed,
+/// based on the state recorded in `ENV_CLI_STATE`.
+pub async fn export(shell: String, diff: bool, project: &Project) -> Result<()> {
+    let shell: Shell = shell.parse()?;
+    if shell == Shell::PowerShell {
+        return Err(EnvCliError::InvalidArgument(
+            "Export supports bash, zsh and fish".to_string(),
+        ));
+    }
+
+    let state: HookState = std::env::var(STATE_VAR)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+
+    let target = if project.is_initialized() {
+        load_current_variables(project, &state)?
+    } else {
+        BTreeMap::new()
+    };
+
+    let mut statements = Vec::new();
+
+    if !diff {
+        for (key, value) in &target {
+            statements.extend(export_statement(shell, key, value));
+        }
+        print_statements(&statements);
+        return Ok(());
+    }
+
+    let mut next = HookState::default();
+
+    // Restore or unset variables that no longer apply
+    for key in state.applied.keys() {
+        if target.contains_key(key) {
+            continue;
+        }
+        match state.previous.get(key).cloned().flatten() {
+            Some(original) => statements.extend(export_statement(shell, key, &original)),
+            None => statements.extend(unset_statement(shell, key)),
+        }
+    }
+
+    // Export new and changed variables
+    for (key, value) in &target {
+        let original = match state.previous.get(key) {
+            Some(original) if state.applied.contains_key(key) => original.clone(),
+            _ => std::env::var(key).ok(),
+        };
+        if state.applied.get(key) != Some(value) {
+            statements.extend(export_statement(shell, key, value));
+        }
+        next.applied.insert(key.clone(), value.clone());
+        next.previous.insert(key.clone(), original);
+    }
+
+    if next.applied.is_empty() {
+        if std::env::var_os(STATE_VAR).is_some() {
+            statements.extend(unset_statement(shell, STATE_VAR));
+        }
+    } else if !statements.is_empty() || std::env::var_os(STATE_VAR).is_none() {
+        statements.extend(export_statement(
+            shell,
+            STATE_VAR,
+            &serde_json::to_string(&next)?,
+        ));
+    }
+
+    print_statements(&statements);
+    Ok(())
+}
+
+/// Load the expanded variables of the project's current environment.
+///
+/// The process environment already holds what the hook exported last time,
+/// so references to those variables read the values from before the hook
+/// instead; otherwise `PATH=${PATH}:/opt/bin` would grow at every prompt.
+fn load_current_variables(
+    project: &Project,
+    state: &HookState,
+) -> Result<BTreeMap<String, String>> {
+    let env_name = match project.current_environment() {
+        Ok(name) => name,
+        Err(_) => return Ok(BTreeMap::new()),
+    };
+    let config = project.load_config()?;
+
+    let mut env_manager = EnvManager::for_project(project);
+    env_manager.load_layers(config.layers(&env_name)?)?;
+    env_manager.expand(ExpandOptions {
+        process_env_overrides: state
+            .previous
+            .iter()
+            .filter(|(key, _)| state.applied.contains_key(*key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+        ..Default::default()
+    })?;
+
+    Ok(env_manager
+        .list()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect())
+}
+
+fn print_statements(statements: &[String]) {
+    for statement in statements {
+        println!("{}", statement);
+    }
+}
+
+/// The statement exporting `key`, or `None` when `key` is not a valid
+/// variable name: names are written unquoted into code the shell evaluates.
+fn export_statement(shell: Shell, key: &str, value: &str) -> Option<String> {
+    if !parser::is_valid_env_key(key) {
+        return None;
+    }
+    Some(match shell {
+        Shell::Fish => format!("set -gx {} {};", key, fish_quote(value)),
+        _ => format!("export {}={};", key, posix_quote(value)),
+    })
+}
+
+/// The statement unsetting `key`; see [`export_statement`].
+fn unset_statement(shell: Shell, key: &str) -> Option<String> {
+    if !parser::is_valid_env_key(key) {
+        return None;
+    }
+    Some(match shell {
+        Shell::Fish => format!("set -e {};", key),
+        _ => format!("unset {};", key),
+    })
+}
+
+/// Quote a value for bash and zsh.
+fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Quote a value for fish.
+fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
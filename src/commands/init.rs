@@ -1,20 +1,50 @@
 //! Initialize command implementation.
 
-use crate::config::{default_config_path, Config, Environment};
+use super::git_ignore;
+use crate::config::{Config, Environment, Project, CONFIG_VERSION};
 use crate::error::{EnvCliError, Result};
-use std::path::PathBuf;
+use crate::report::{Output, Report};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// What `env init` created.
+#[derive(Debug, Serialize)]
+pub struct InitReport {
+    pub project: String,
+    pub directory: PathBuf,
+    pub config_file: PathBuf,
+    pub environments: Vec<String>,
+    pub current_environment: String,
+}
+
+impl Report for InitReport {
+    fn print_text(&self, out: &Output) {
+        out.line("✓ Created .env directory structure");
+        out.line("✓ Created env-cli configuration file");
+        out.line("✓ Created environment templates");
+        out.line(format!(
+            "✓ Set '{}' as current environment",
+            self.current_environment
+        ));
+
+        out.info("\nProject initialized successfully!");
+        out.info("Run 'env status' to see the current state.");
+        out.info("Run 'env switch <environment>' to switch environments.");
+    }
+}
 
 /// Initialize a new env-cli project structure.
-pub async fn execute(force: bool) -> Result<()> {
-    println!("Initializing project environment structure...");
+pub async fn execute(force: bool, project: &Project, out: &Output) -> Result<()> {
+    out.ensure_supported::<InitReport>()?;
+    out.progress("Initializing project environment structure...");
 
     // Check if already initialized
-    let env_dir = PathBuf::from(".env");
-    let config_path = default_config_path();
+    let env_dir = project.env_dir();
+    let config_path = project.config_path().to_path_buf();
 
     if env_dir.exists() && !force {
         return Err(EnvCliError::AlreadyInitialized(
-            "Project already has .env directory. Use --force to reinitialize.".to_string(),
+            "Project already has a .env directory".to_string(),
         ));
     }
 
@@ -22,24 +52,24 @@ pub async fn execute(force: bool) -> Result<()> {
     create_directory_structure(&env_dir)?;
 
     // Create default configuration
-    create_default_config(&config_path)?;
+    let config = create_default_config(project.root(), &config_path)?;
 
     // Create environment templates
     create_environment_templates(&env_dir)?;
 
-    // Set up current environment symlink
-    setup_current_environment(&env_dir, "development")?;
+    // Keep personal overrides out of version control
+    git_ignore(&env_dir, "*.local.env")?;
 
-    println!("✓ Created .env directory structure");
-    println!("✓ Created env-cli configuration file");
-    println!("✓ Created environment templates");
-    println!("✓ Set 'development' as current environment");
-
-    println!("\nProject initialized successfully!");
-    println!("Run 'env status' to see the current state.");
-    println!("Run 'env switch <environment>' to switch environments.");
-
-    Ok(())
+    // Set up current environment symlink
+    setup_current_environment(&env_dir, &config.default_environment)?;
+
+    out.render(&InitReport {
+        project: config.project,
+        directory: env_dir,
+        config_file: config_path,
+        environments: config.environments.into_iter().map(|e| e.name).collect(),
+        current_environment: config.default_environment,
+    })
 }
 
 /// Create the directory structure for env-cli.
@@ -57,10 +87,11 @@ fn create_directory_structure(env_dir: &PathBuf) -> Result<()> {
 }
 
 /// Create default configuration file.
-fn create_default_config(config_path: &PathBuf) -> Result<()> {
-    let project_name = get_project_name()?;
+fn create_default_config(root: &Path, config_path: &PathBuf) -> Result<Config> {
+    let project_name = get_project_name(root)?;
 
     let config = Config {
+        version: CONFIG_VERSION,
         project: project_name,
         default_environment: "development".to_string(),
         environments: vec![
@@ -68,27 +99,39 @@ fn create_default_config(config_path: &PathBuf) -> Result<()> {
                 name: "development".to_string(),
                 description: Some("Development environment".to_string()),
                 file: Some(PathBuf::from(".env/environments/development.env")),
-                variables: std::collections::HashMap::new(),
+                extends: None,
+                variables: Default::default(),
             },
             Environment {
                 name: "staging".to_string(),
                 description: Some("Staging environment".to_string()),
                 file: Some(PathBuf::from(".env/environments/staging.env")),
-                variables: std::collections::HashMap::new(),
+                extends: None,
+                variables: Default::default(),
             },
             Environment {
                 name: "production".to_string(),
                 description: Some("Production environment".to_string()),
                 file: Some(PathBuf::from(".env/environments/production.env")),
-                variables: std::collections::HashMap::new(),
+                extends: None,
+                variables: Default::default(),
             },
         ],
         scan: Default::default(),
         validation: Default::default(),
+        variables: Default::default(),
+        rules: Default::default(),
+        recipients: Default::default(),
+        preferences: Default::default(),
+        root: root.to_path_buf(),
+        path: config_path.clone(),
     };
 
+    if let Some(parent) = config_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
     crate::config::save_config(&config, config_path)?;
-    Ok(())
+    Ok(config)
 }
 
 /// Create environment template files.
@@ -205,10 +248,10 @@ fn setup_current_environment(env_dir: &PathBuf, env_name: &str) -> Result<()> {
     Ok(())
 }
 
-/// Get project name from Cargo.toml or current directory.
-fn get_project_name() -> Result<String> {
+/// Get project name from Cargo.toml or the project directory.
+fn get_project_name(root: &Path) -> Result<String> {
     // Try to read from Cargo.toml
-    if let Ok(cargo_toml) = std::fs::read_to_string("Cargo.toml") {
+    if let Ok(cargo_toml) = std::fs::read_to_string(root.join("Cargo.toml")) {
         for line in cargo_toml.lines() {
             if line.trim().starts_with("name = ") {
                 // Extract name from: name = "project-name"
@@ -224,7 +267,7 @@ fn get_project_name() -> Result<String> {
     }
 
     // Fallback to directory name
-    match std::env::current_dir() {
+    match root.canonicalize() {
         Ok(path) => {
             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
                 Ok(name.to_string())
@@ -0,0 +1,222 @@
+//! Keys and rekey command implementations.
+//!
+//! `keys` manages the `[[recipients]]` list in `config.toml` and the user's
+//! own identity. `rekey` re-encrypts every sealed value for the current list,
+//! which is how access is granted to new recipients and withdrawn from
+//! removed ones. See [`crate::crypto::recipients`] for the format.
+
+use crate::cli::KeysAction;
+use crate::config::{Project, Recipient};
+use crate::crypto::{self, recipients, Identity, Keyring, PublicKey};
+use crate::env::EnvDocument;
+use crate::error::{EnvCliError, Result};
+use crate::report::{Output, Report};
+use serde::Serialize;
+
+/// Run a `keys` subcommand.
+pub async fn execute(action: KeysAction, project: &Project, out: &Output) -> Result<()> {
+    match action {
+        KeysAction::Generate { force } => generate(force, out),
+        KeysAction::Add { key, name } => add(key, name, project, out),
+        KeysAction::Remove { recipient } => remove(recipient, project, out),
+        KeysAction::List => list(project, out),
+    }
+}
+
+/// Re-encrypt the sealed values of one or all environments.
+pub async fn rekey(env: Option<String>, project: &Project, out: &Output) -> Result<()> {
+    let config = project.load_config()?;
+    if config.recipients.is_empty() {
+        return Err(EnvCliError::Config(
+            "No recipients configured. Add one with 'env keys add <public-key>'".to_string(),
+        ));
+    }
+
+    let names: Vec<String> = match env {
+        Some(name) => {
+            config.environment(&name)?;
+            vec![name]
+        }
+        None => config.environments.iter().map(|e| e.name.clone()).collect(),
+    };
+
+    // Open everything before writing anything, so a value this identity
+    // cannot read leaves every file untouched
+    let keyring = Keyring::load(project)?;
+    let mut rekeyed = Vec::new();
+    for name in names {
+        let env_file = config.environment_file(&name);
+        if !env_file.exists() {
+            continue;
+        }
+
+        let mut document = EnvDocument::load(&env_file)?;
+        let count = document.update_values(|key, value| {
+            if !crypto::is_encrypted(value) {
+                return Ok(None);
+            }
+            let plaintext = keyring.decrypt_value(key, value)?;
+            keyring.encrypt_value(key, &plaintext).map(Some)
+        })?;
+        if count > 0 {
+            rekeyed.push((name, env_file, document, count));
+        }
+    }
+
+    if rekeyed.is_empty() {
+        out.info("No encrypted values to re-encrypt");
+        return Ok(());
+    }
+
+    for (name, env_file, document, count) in rekeyed {
+        document.save(&env_file)?;
+        out.info(format!(
+            "✓ Re-encrypted {} value(s) in '{}' for {} recipient(s)",
+            count,
+            name,
+            config.recipients.len()
+        ));
+    }
+
+    Ok(())
+}
+
+fn generate(force: bool, out: &Output) -> Result<()> {
+    let path = recipients::default_identity_path().ok_or_else(|| {
+        EnvCliError::Config("Unable to determine the user configuration directory".to_string())
+    })?;
+    if path.exists() && !force {
+        let existing = Identity::load()?.map(|identity| identity.public_key());
+        return Err(EnvCliError::Config(format!(
+            "An identity already exists at {}{}. Use --force to replace it",
+            path.display(),
+            existing
+                .map(|key| format!(" (public key {})", key))
+                .unwrap_or_default()
+        )));
+    }
+
+    let identity = Identity::generate();
+    identity.save(&path)?;
+
+    out.info(format!("✓ Generated identity: {}", path.display()));
+    out.info(format!("  Public key: {}", identity.public_key()));
+    out.info("  Share the public key; a project member adds it with:");
+    out.info(format!(
+        "    env keys add {} --name <you>",
+        identity.public_key()
+    ));
+
+    Ok(())
+}
+
+fn add(key: String, name: Option<String>, project: &Project, out: &Output) -> Result<()> {
+    let mut config = project.load_own_config()?;
+    let public_key = PublicKey::parse(&key)?;
+    let key = public_key.to_string();
+    let name = name.unwrap_or_else(|| key.clone());
+
+    if let Some(existing) = config.recipients.iter().find(|r| r.key == key) {
+        out.info(format!("'{}' is already a recipient", existing.name));
+        return Ok(());
+    }
+    if config.recipients.iter().any(|r| r.name == name) {
+        return Err(EnvCliError::InvalidArgument(format!(
+            "A recipient named '{}' already exists",
+            name
+        )));
+    }
+
+    config.recipients.push(Recipient {
+        name: name.clone(),
+        key,
+    });
+    project.save_config(&config)?;
+
+    out.info(format!("✓ Added recipient '{}'", name));
+    out.info("  Run 'env rekey' so existing values are readable by the new recipient.");
+
+    Ok(())
+}
+
+fn remove(recipient: String, project: &Project, out: &Output) -> Result<()> {
+    let mut config = project.load_own_config()?;
+
+    let position = config
+        .recipients
+        .iter()
+        .position(|r| r.name == recipient || r.key == recipient)
+        .ok_or_else(|| {
+            EnvCliError::InvalidArgument(format!("No recipient named '{}'", recipient))
+        })?;
+    let removed = config.recipients.remove(position);
+    project.save_config(&config)?;
+
+    out.info(format!("✓ Removed recipient '{}'", removed.name));
+    if config.recipients.is_empty() {
+        out.info("  No recipients are left; new values will use the project key.");
+    } else {
+        out.info("  Run 'env rekey' so the removed key cannot read the committed files.");
+    }
+    out.info("  Anything they could already read should be rotated.");
+
+    Ok(())
+}
+
+/// The project's recipients.
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+struct RecipientList {
+    recipients: Vec<ListedRecipient>,
+}
+
+#[derive(Debug, Serialize)]
+struct ListedRecipient {
+    name: String,
+    key: String,
+    /// Whether the key belongs to the local identity
+    own: bool,
+}
+
+impl Report for RecipientList {
+    fn print_text(&self, out: &Output) {
+        if self.recipients.is_empty() {
+            out.line("No recipients configured");
+            return;
+        }
+
+        let width = self
+            .recipients
+            .iter()
+            .map(|r| r.name.chars().count())
+            .max()
+            .unwrap_or(0);
+        for recipient in &self.recipients {
+            out.line(format!(
+                "{:<width$}  {}{}",
+                recipient.name,
+                recipient.key,
+                if recipient.own { " (you)" } else { "" },
+                width = width
+            ));
+        }
+    }
+}
+
+fn list(project: &Project, out: &Output) -> Result<()> {
+    out.ensure_supported::<RecipientList>()?;
+    let config = project.load_config()?;
+    let own_key = Identity::load()?.map(|identity| identity.public_key().to_string());
+
+    out.render(&RecipientList {
+        recipients: config
+            .recipients
+            .into_iter()
+            .map(|recipient| ListedRecipient {
+                own: own_key.as_deref() == Some(recipient.key.as_str()),
+                name: recipient.name,
+                key: recipient.key,
+            })
+            .collect(),
+    })
+}
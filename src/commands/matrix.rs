@@ -0,0 +1,259 @@
+//! Matrix command implementation.
+//!
+//! Builds a variable-by-environment table covering every environment in the
+//! configuration. Each cell records whether the variable is present, missing,
+//! empty or still a placeholder. Secrets that share the same value across
+//! environments are reported separately, since they usually mean a
+//! production credential was copied somewhere it does not belong.
+
+use super::load_variables;
+use super::validate::{is_placeholder, is_sensitive_key};
+use crate::cli::OutputFormat;
+use crate::config::Project;
+use crate::env::EnvManager;
+use crate::error::Result;
+use crate::report::{Output, Report};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+
+/// State of one variable in one environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CellStatus {
+    Present,
+    Missing,
+    Empty,
+    Placeholder,
+}
+
+impl CellStatus {
+    fn label(self) -> &'static str {
+        match self {
+            CellStatus::Present => "present",
+            CellStatus::Missing => "missing",
+            CellStatus::Empty => "empty",
+            CellStatus::Placeholder => "placeholder",
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            CellStatus::Present => "✓",
+            CellStatus::Missing => "✗",
+            CellStatus::Empty => "∅",
+            CellStatus::Placeholder => "?",
+        }
+    }
+}
+
+/// A secret whose value is identical in several environments.
+#[derive(Debug, Serialize)]
+struct SharedSecret {
+    variable: String,
+    environments: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MatrixReport {
+    environments: Vec<String>,
+    variables: BTreeMap<String, BTreeMap<String, CellStatus>>,
+    shared_secrets: Vec<SharedSecret>,
+}
+
+/// Print the variable-by-environment matrix.
+pub async fn execute(raw: bool, project: &Project, out: &Output) -> Result<()> {
+    out.ensure_supported::<MatrixReport>()?;
+    let config = project.load_config()?;
+
+    let mut loaded: Vec<(String, EnvManager)> = Vec::new();
+    for environment in &config.environments {
+        let has_values = config.environment_file(&environment.name).exists()
+            || environment.extends.is_some()
+            || !environment.variables.is_empty();
+        let env_manager = if has_values {
+            load_variables(project, &config, &environment.name, raw)?
+        } else {
+            EnvManager::for_project(project)
+        };
+        loaded.push((environment.name.clone(), env_manager));
+    }
+
+    let keys: BTreeSet<&String> = loaded
+        .iter()
+        .flat_map(|(_, env_manager)| env_manager.list().map(|(key, _)| key))
+        .collect();
+
+    let mut variables = BTreeMap::new();
+    let mut shared_secrets = Vec::new();
+    for key in keys {
+        let mut cells = BTreeMap::new();
+        let mut by_value: HashMap<&str, Vec<String>> = HashMap::new();
+
+        for (env_name, env_manager) in &loaded {
+            let status = match env_manager.get(key) {
+                None => CellStatus::Missing,
+                Some(value) if value.is_empty() => CellStatus::Empty,
+                Some(value) if is_placeholder(value) => CellStatus::Placeholder,
+                Some(value) => {
+                    by_value.entry(value).or_default().push(env_name.clone());
+                    CellStatus::Present
+                }
+            };
+            cells.insert(env_name.clone(), status);
+        }
+
+        if is_sensitive_key(key, &config) {
+            let mut shared: Vec<Vec<String>> = by_value
+                .into_values()
+                .filter(|environments| environments.len() > 1)
+                .collect();
+            shared.sort();
+            for environments in shared {
+                shared_secrets.push(SharedSecret {
+                    variable: key.clone(),
+                    environments,
+                });
+            }
+        }
+
+        variables.insert(key.clone(), cells);
+    }
+
+    let report = MatrixReport {
+        environments: loaded.into_iter().map(|(name, _)| name).collect(),
+        variables,
+        shared_secrets,
+    };
+
+    out.render(&report)
+}
+
+impl Report for MatrixReport {
+    const EXTRA_FORMATS: &'static [OutputFormat] = &[OutputFormat::Markdown, OutputFormat::Csv];
+
+    fn print_text(&self, out: &Output) {
+        print_text(self, out);
+    }
+
+    fn render_extra(&self, format: OutputFormat) -> Result<String> {
+        Ok(match format {
+            OutputFormat::Csv => csv(self),
+            _ => markdown(self),
+        })
+    }
+}
+
+fn print_text(report: &MatrixReport, out: &Output) {
+    let name_width = report
+        .variables
+        .keys()
+        .map(|key| key.chars().count())
+        .chain(std::iter::once("Variable".len()))
+        .max()
+        .unwrap_or(0);
+    let widths: Vec<usize> = report
+        .environments
+        .iter()
+        .map(|env| env.chars().count().max(1))
+        .collect();
+
+    let mut header = format!("{:<width$}", "Variable", width = name_width);
+    for (env, width) in report.environments.iter().zip(&widths) {
+        header.push_str(&format!("  {:^width$}", env, width = *width));
+    }
+    out.line(&header);
+    out.line("-".repeat(header.chars().count()));
+
+    for (key, cells) in &report.variables {
+        let mut row = format!("{:<width$}", key, width = name_width);
+        for (env, width) in report.environments.iter().zip(&widths) {
+            row.push_str(&format!(
+                "  {:^width$}",
+                cells[env].symbol(),
+                width = *width
+            ));
+        }
+        out.line(row);
+    }
+
+    out.line("");
+    out.line("Legend: ✓ present  ✗ missing  ∅ empty  ? placeholder");
+
+    if !report.shared_secrets.is_empty() {
+        out.line("");
+        out.line("⚠️  Secrets with identical values across environments:");
+        for shared in &report.shared_secrets {
+            out.line(format!(
+                "  - {}: {}",
+                shared.variable,
+                shared.environments.join(", ")
+            ));
+        }
+    }
+}
+
+fn markdown(report: &MatrixReport) -> String {
+    let mut table = String::new();
+    let _ = writeln!(table, "| Variable | {} |", report.environments.join(" | "));
+    let _ = writeln!(
+        table,
+        "|----------|{}",
+        report
+            .environments
+            .iter()
+            .map(|_| ":---:|")
+            .collect::<String>()
+    );
+    for (key, cells) in &report.variables {
+        let row: Vec<&str> = report
+            .environments
+            .iter()
+            .map(|env| cells[env].label())
+            .collect();
+        let _ = writeln!(table, "| `{}` | {} |", key, row.join(" | "));
+    }
+
+    if !report.shared_secrets.is_empty() {
+        table.push_str("\n**Secrets with identical values across environments:**\n\n");
+        for shared in &report.shared_secrets {
+            let _ = writeln!(
+                table,
+                "- `{}`: {}",
+                shared.variable,
+                shared.environments.join(", ")
+            );
+        }
+    }
+    table
+}
+
+fn csv(report: &MatrixReport) -> String {
+    let header: Vec<String> = std::iter::once("variable")
+        .chain(report.environments.iter().map(String::as_str))
+        .map(csv_field)
+        .collect();
+    let mut table = format!("{}\n", header.join(","));
+
+    for (key, cells) in &report.variables {
+        let row: Vec<String> = std::iter::once(csv_field(key))
+            .chain(
+                report
+                    .environments
+                    .iter()
+                    .map(|env| cells[env].label().to_string()),
+            )
+            .collect();
+        let _ = writeln!(table, "{}", row.join(","));
+    }
+    table
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
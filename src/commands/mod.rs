@@ -3,47 +3,267 @@
 //! This module contains the business logic for each CLI command.
 
 pub mod completion;
+pub mod config;
+pub mod diff;
+pub mod encrypt;
+pub mod explain;
 pub mod generate;
+pub mod hook;
 pub mod init;
+pub mod keys;
+pub mod matrix;
+pub mod rotate;
+pub mod run;
 pub mod scan;
 pub mod status;
 pub mod switch;
 pub mod sync;
 pub mod validate;
+pub mod vars;
 
 use crate::cli::Commands;
+use crate::config::{Config, Project};
+use crate::env::{EnvManager, ExpandOptions};
 use crate::error::Result;
+use crate::report::Output;
+use chrono::Utc;
+use std::path::{Path, PathBuf};
 
 /// Result type for command execution
 pub type CommandResult = Result<()>;
 
-/// Execute the given command.
-pub async fn execute_command(command: Commands) -> Result<()> {
+/// Execute the given command against `project`, writing its results to `out`.
+pub async fn execute_command(command: Commands, project: &Project, out: &Output) -> Result<()> {
     match command {
-        Commands::Init { force } => init::execute(force).await,
-        Commands::Switch { environment, yes } => switch::execute(environment, yes).await,
+        Commands::Init { force } => init::execute(force, project, out).await,
+        Commands::Switch { environment, yes } => {
+            switch::execute(environment, yes, project, out).await
+        }
         Commands::Scan {
             path,
-            format,
             hidden,
-        } => scan::execute(path, format, hidden).await,
-        Commands::Validate { env, check_unused } => validate::execute(env, check_unused).await,
+            baseline,
+            create_baseline,
+            update_baseline,
+        } => {
+            scan::execute(
+                path,
+                hidden,
+                baseline,
+                create_baseline,
+                update_baseline,
+                project,
+                out,
+            )
+            .await
+        }
+        Commands::Validate {
+            env,
+            all,
+            check_unused,
+            raw,
+        } => validate::execute(env, all, check_unused, raw, project, out).await,
         Commands::Sync {
             source,
             target,
             yes,
-        } => sync::execute(source, target, yes).await,
+        } => sync::execute(source, target, yes, project, out).await,
+        Commands::Diff {
+            source,
+            target,
+            keys_only,
+            show_secrets,
+            raw,
+            exit_code,
+        } => {
+            diff::execute(
+                source,
+                target,
+                keys_only,
+                show_secrets,
+                raw,
+                exit_code,
+                project,
+                out,
+            )
+            .await
+        }
+        Commands::Matrix { raw } => matrix::execute(raw, project, out).await,
         Commands::Generate {
             output,
             comments,
             docs,
             scan_dir,
-        } => generate::execute(output, comments, docs, Some(scan_dir)).await,
-        Commands::Status { verbose } => status::execute(verbose).await,
+        } => generate::execute(output, comments, docs, Some(scan_dir), project, out).await,
+        Commands::Status { verbose, raw } => status::execute(verbose, raw, project, out).await,
+        Commands::Get { key, env, raw } => vars::get(key, env, raw, project, out).await,
+        Commands::Set {
+            key,
+            value,
+            env,
+            inline,
+        } => vars::set(key, value, env, inline, project, out).await,
+        Commands::Unset { key, env, inline } => vars::unset(key, env, inline, project, out).await,
+        Commands::List {
+            env,
+            show_secrets,
+            raw,
+        } => vars::list(env, show_secrets, raw, project, out).await,
+        Commands::Explain {
+            variable,
+            env,
+            show_secrets,
+        } => explain::execute(variable, env, show_secrets, project, out).await,
+        Commands::Rotate {
+            var,
+            env,
+            length,
+            charset,
+            no_previous,
+            scan_dir,
+        } => {
+            rotate::execute(
+                var,
+                env,
+                length,
+                charset,
+                no_previous,
+                scan_dir,
+                project,
+                out,
+            )
+            .await
+        }
+        Commands::Encrypt { env, secrets_only } => {
+            encrypt::encrypt(env, secrets_only, project, out).await
+        }
+        Commands::Decrypt { env, stdout } => encrypt::decrypt(env, stdout, project, out).await,
+        Commands::Keys { action } => keys::execute(action, project, out).await,
+        Commands::Rekey { env } => keys::rekey(env, project, out).await,
+        Commands::Config { action } => config::execute(action, project, out).await,
+        Commands::Run {
+            env,
+            only,
+            no_inherit,
+            validate,
+            command,
+        } => run::execute(env, only, no_inherit, validate, command, project, out).await,
+        Commands::Hook { shell } => hook::execute(shell).await,
+        Commands::Export { shell, diff } => hook::export(shell, diff, project).await,
         Commands::Completion {
             shell,
             install,
             uninstall,
-        } => completion::execute(shell, install, uninstall).await,
+        } => completion::execute(shell, install, uninstall, out).await,
+    }
+}
+
+/// Load the project configuration and resolve the environment to operate on.
+pub(crate) fn load_target(project: &Project, env: Option<String>) -> Result<(Config, String)> {
+    let config = project.load_config()?;
+    let env_name = match env {
+        Some(name) => name,
+        None => project.current_environment()?,
+    };
+    config.environment(&env_name)?;
+
+    Ok((config, env_name))
+}
+
+/// Load the variables of an environment, expanding references unless `raw`.
+///
+/// Values come from every layer of the environment's inheritance chain;
+/// see [`Config::layers`].
+pub(crate) fn load_variables(
+    project: &Project,
+    config: &Config,
+    env_name: &str,
+    raw: bool,
+) -> Result<EnvManager> {
+    let mut env_manager = EnvManager::for_project(project);
+    env_manager.load_layers(config.layers(env_name)?)?;
+    if !raw {
+        env_manager.expand(ExpandOptions::default())?;
+    }
+    Ok(env_manager)
+}
+
+/// Copy an environment file into `.env/backups` before it is modified.
+pub(crate) fn backup_environment(
+    project: &Project,
+    env_name: &str,
+    env_file: &Path,
+) -> Result<Option<PathBuf>> {
+    if !env_file.exists() {
+        return Ok(None);
+    }
+
+    copy_to_backups(project, env_file, env_name, "env").map(Some)
+}
+
+/// Copy `file` into the backups directory as `<name>_<timestamp>.<extension>`.
+///
+/// A backup made in the same second gets a counter suffix instead of
+/// replacing the earlier one.
+pub(crate) fn copy_to_backups(
+    project: &Project,
+    file: &Path,
+    name: &str,
+    extension: &str,
+) -> Result<PathBuf> {
+    let backups_dir = project.backups_dir();
+    std::fs::create_dir_all(&backups_dir)?;
+
+    let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
+    let mut source = std::fs::File::open(file)?;
+    let mut attempt = 0;
+    loop {
+        let file_name = match attempt {
+            0 => format!("{}_{}.{}", name, timestamp, extension),
+            n => format!("{}_{}_{}.{}", name, timestamp, n, extension),
+        };
+        let backup_path = backups_dir.join(file_name);
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&backup_path)
+        {
+            Ok(mut backup) => {
+                std::io::copy(&mut source, &mut backup)?;
+                return Ok(backup_path);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Add a pattern to the `.gitignore` in `dir`, unless it is already there.
+pub(crate) fn git_ignore(dir: &Path, pattern: &str) -> Result<()> {
+    let gitignore = dir.join(".gitignore");
+
+    let mut content = std::fs::read_to_string(&gitignore).unwrap_or_default();
+    if content.lines().any(|line| line.trim() == pattern) {
+        return Ok(());
     }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&format!("{}\n", pattern));
+    std::fs::write(&gitignore, content)?;
+
+    Ok(())
+}
+
+/// Ask a yes/no question on stderr and read the answer from stdin.
+pub(crate) fn confirm(question: &str) -> Result<bool> {
+    use std::io::Write;
+
+    eprint!("{} [y/N] ", question);
+    std::io::stderr().flush()?;
+
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
 }
@@ -0,0 +1,170 @@
+//! Rotate command implementation.
+//!
+//! Replaces a secret with a freshly generated value. The old value is kept as
+//! `<VAR>_PREVIOUS` so consumers can be redeployed during a grace period, and
+//! in the backup written before the change. Each rotation is appended to the
+//! audit log, and the code locations reading the variable are listed so it
+//! is clear what needs a redeploy.
+
+use super::{backup_environment, load_target, load_variables};
+use crate::cli::SecretCharset;
+use crate::config::{Project, ScanConfig};
+use crate::crypto;
+use crate::env::{parser, EnvDocument};
+use crate::error::{EnvCliError, Result};
+use crate::report::Output;
+use crate::scan::CodeScanner;
+use crate::sync::{self, AuditLogEntry, SyncOperation};
+use crate::utils;
+use chrono::Utc;
+use std::path::PathBuf;
+
+/// Rotate a variable to a new random value.
+pub async fn execute(
+    var: String,
+    env: Option<String>,
+    length: usize,
+    charset: SecretCharset,
+    no_previous: bool,
+    scan_dir: PathBuf,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    let start_time = std::time::Instant::now();
+
+    if !parser::is_valid_env_key(&var) {
+        return Err(EnvCliError::InvalidArgument(format!(
+            "Invalid environment variable key: '{}'",
+            var
+        )));
+    }
+    if length == 0 {
+        return Err(EnvCliError::InvalidArgument(
+            "--length must be at least 1".to_string(),
+        ));
+    }
+
+    let (config, env_name) = load_target(project, env)?;
+    let env_file = config.environment_file(&env_name);
+    let current = load_variables(project, &config, &env_name, false)?
+        .get(&var)
+        .cloned()
+        .ok_or_else(|| {
+            EnvCliError::Validation(format!(
+                "'{}' is not set in '{}'. Use 'env set' to add it first.",
+                var, env_name
+            ))
+        })?;
+
+    if let Some(backup) = backup_environment(project, &env_name, &env_file)? {
+        out.info(format!("✓ Created backup: {}", backup.display()));
+    }
+
+    let value = utils::generate_secret_from(length, charset_bytes(charset));
+    let previous_key = format!("{}_PREVIOUS", var);
+
+    let mut document = EnvDocument::load(&env_file)?;
+    let sealed = crypto::seal_for_document(project, &document, &var, &value)?;
+    let sealed_previous = if no_previous {
+        None
+    } else {
+        Some(crypto::seal_for_document(
+            project,
+            &document,
+            &previous_key,
+            &current,
+        )?)
+    };
+
+    document.set(&var, &sealed);
+    if let Some(sealed_previous) = &sealed_previous {
+        document.set(&previous_key, sealed_previous);
+    }
+    document.save(&env_file)?;
+
+    out.info(format!("✓ Rotated {} in '{}'", var, env_name));
+    if sealed_previous.is_some() {
+        out.info(format!(
+            "  The old value is kept as {}; remove it with 'env unset {}' once every consumer is redeployed.",
+            previous_key, previous_key
+        ));
+    } else {
+        out.info("  The old value is only kept in the backup.");
+    }
+
+    let mut rotated = vec![var.clone()];
+    if sealed_previous.is_some() {
+        rotated.push(previous_key);
+    }
+    let audit_log_path = project.audit_log_path();
+    sync::append_audit_entry(
+        &audit_log_path,
+        AuditLogEntry {
+            timestamp: Utc::now(),
+            operation: SyncOperation::Rotation,
+            source_env: env_name.clone(),
+            target_env: env_name,
+            variables_synced: rotated,
+            conflicts_resolved: Vec::new(),
+            errors: Vec::new(),
+            duration_ms: start_time.elapsed().as_millis() as u64,
+            user: std::env::var("USER")
+                .ok()
+                .or_else(|| std::env::var("USERNAME").ok()),
+        },
+    )?;
+    out.info(format!(
+        "📝 Audit log updated: {}",
+        audit_log_path.display()
+    ));
+
+    print_usages(&var, &scan_dir, &config.scan, out).await
+}
+
+/// List the code locations that read `var`.
+async fn print_usages(
+    var: &str,
+    scan_dir: &PathBuf,
+    scan: &ScanConfig,
+    out: &Output,
+) -> Result<()> {
+    let scanner = CodeScanner::new()?.with_exclusions(scan);
+    let usage = scanner
+        .scan_directory(scan_dir)
+        .await?
+        .into_iter()
+        .find(|usage| usage.name == var);
+
+    out.info("");
+    match usage {
+        Some(usage) => {
+            let mut locations: Vec<(&String, &usize)> =
+                usage.files.iter().zip(&usage.lines).collect();
+            locations.sort();
+            out.info(format!(
+                "{} is used in {} location(s); redeploy what reads it:",
+                var,
+                locations.len()
+            ));
+            for (file, line) in locations {
+                out.info(format!("  {}:{}", file, line));
+            }
+        }
+        None => out.info(format!(
+            "No code in {} references {}",
+            scan_dir.display(),
+            var
+        )),
+    }
+
+    Ok(())
+}
+
+fn charset_bytes(charset: SecretCharset) -> &'static [u8] {
+    match charset {
+        SecretCharset::Base64 => utils::CHARSET_BASE64,
+        SecretCharset::Hex => utils::CHARSET_HEX,
+        SecretCharset::Alnum => utils::CHARSET_ALNUM,
+        SecretCharset::Symbols => utils::CHARSET_SYMBOLS,
+    }
+}
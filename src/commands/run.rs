@@ -0,0 +1,109 @@
+//! Run command implementation.
+//!
+//! Starts a child process with an environment's variables injected. On Unix
+//! the CLI replaces itself with the child via `exec`, so signals reach the
+//! command directly and its exit status becomes ours. Elsewhere the child is
+//! spawned and its exit code is passed through.
+
+use super::load_target;
+use super::validate::check_environment;
+use crate::config::Project;
+use crate::env::{EnvManager, ExpandOptions};
+use crate::error::{EnvCliError, Result};
+use crate::report::Output;
+use regex::Regex;
+use std::collections::HashMap;
+use std::process::Command;
+
+/// Run a command with the selected environment's variables.
+pub async fn execute(
+    env: Option<String>,
+    only: Vec<String>,
+    no_inherit: bool,
+    validate: bool,
+    command: Vec<String>,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| EnvCliError::InvalidArgument("No command given to run".to_string()))?;
+
+    let (config, env_name) = load_target(project, env)?;
+
+    let mut env_manager = EnvManager::for_project(project);
+    env_manager.load_layers(config.layers(&env_name)?)?;
+    env_manager.expand(ExpandOptions {
+        use_process_env: !no_inherit,
+        ..Default::default()
+    })?;
+
+    if validate {
+        let result = check_environment(&config, &env_name, &env_manager);
+        if !result.is_valid() {
+            out.warn(format!("✗ Environment '{}' failed validation:", env_name));
+            for error in result
+                .format_errors
+                .iter()
+                .chain(&result.rule_errors)
+                .chain(&result.security_errors)
+            {
+                out.warn(format!("  - {}", error));
+            }
+            return Err(EnvCliError::Validation(format!(
+                "Refusing to run: environment '{}' is invalid",
+                env_name
+            )));
+        }
+    }
+
+    let variables = select_variables(&env_manager, &only)?;
+
+    let mut child = Command::new(program);
+    child.args(args);
+    if no_inherit {
+        child.env_clear();
+    }
+    child.envs(&variables);
+
+    run_child(child, program)
+}
+
+/// Pick the variables to inject, honoring `--only` patterns. Each pattern is
+/// a regular expression that must match a whole name.
+fn select_variables(env_manager: &EnvManager, only: &[String]) -> Result<HashMap<String, String>> {
+    let patterns = only
+        .iter()
+        .map(|pattern| {
+            Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| {
+                EnvCliError::InvalidArgument(format!("Invalid --only pattern '{}': {}", pattern, e))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(env_manager
+        .list()
+        .filter(|(key, _)| patterns.is_empty() || patterns.iter().any(|p| p.is_match(key)))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect())
+}
+
+#[cfg(unix)]
+fn run_child(mut child: Command, program: &str) -> Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    // `exec` only returns on failure
+    let err = child.exec();
+    Err(EnvCliError::Command(format!(
+        "Failed to run '{}': {}",
+        program, err
+    )))
+}
+
+#[cfg(not(unix))]
+fn run_child(mut child: Command, program: &str) -> Result<()> {
+    let status = child
+        .status()
+        .map_err(|e| EnvCliError::Command(format!("Failed to run '{}': {}", program, e)))?;
+    std::process::exit(status.code().unwrap_or(1));
+}
@@ -1,150 +1,381 @@
 //! Advanced scan command implementation for EC-03.
+//!
+//! With `--baseline`, findings recorded in the baseline file are accepted and
+//! the scan only fails on new ones. `--create-baseline` and
+//! `--update-baseline` write the current findings to that file instead.
 
 use crate::cli::OutputFormat;
-use crate::error::Result;
-use crate::scan::CodeScanner;
-use std::path::PathBuf;
+use crate::config::Project;
+use crate::env::EnvUsage;
+use crate::error::{EnvCliError, Result};
+use crate::report::sarif::{self, Level};
+use crate::report::{Output, Report};
+use crate::scan::{suppress, Baseline, CodeScanner, RuleInfo, SecurityIssue, SecuritySeverity};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-/// Scan code for environment variable usage with advanced features.
-pub async fn execute(path: PathBuf, format: OutputFormat, hidden: bool) -> Result<()> {
-    println!("🔍 Scanning code for environment variable usage...");
-    println!("📁 Path: {}", path.display());
-    println!("⚙️  Format: {:?}", format);
+/// Results of `env scan`.
+#[derive(Debug, Serialize)]
+pub struct ScanReport {
+    scan_result: ScanSummary,
+    #[serde(skip)]
+    hidden: bool,
+    #[serde(skip)]
+    baseline: bool,
+    /// Every rule the scanner checks, for SARIF output
+    #[serde(skip)]
+    rules: Vec<RuleInfo>,
+    #[serde(skip)]
+    root: PathBuf,
+    /// Project root that SARIF locations are relative to
+    #[serde(skip)]
+    project_root: PathBuf,
+}
 
-    // Initialize advanced scanner
-    let scanner = CodeScanner::with_config(true, None)?;
+#[derive(Debug, Serialize)]
+struct ScanSummary {
+    files_scanned: usize,
+    variables_found: usize,
+    patterns_matched: usize,
+    security_issues_count: usize,
+    baselined_issues_count: usize,
+    ignored_issues_count: usize,
+    scan_duration_ms: u128,
+    languages_detected: BTreeMap<String, usize>,
+    variables: Vec<VariableUsage>,
+    security_issues: Vec<SecurityIssue>,
+}
 
-    println!("🚀 Starting advanced scan with parallel processing...");
+#[derive(Debug, Serialize)]
+struct VariableUsage {
+    name: String,
+    files: Vec<String>,
+    usage_count: usize,
+    locations: Vec<UsageLocation>,
+}
 
-    // Perform advanced scan
-    let scan_result = scanner.scan_directory_advanced(&path).await?;
-
-    println!("✅ Scan completed successfully!");
-    println!("📊 Scan Statistics:");
-    println!("  - Files scanned: {}", scan_result.files_scanned);
-    println!("  - Variables found: {}", scan_result.variables.len());
-    println!("  - Patterns matched: {}", scan_result.patterns_matched);
-    println!("  - Security issues: {}", scan_result.security_issues.len());
-    println!("  - Scan duration: {:?}", scan_result.scan_duration);
-
-    // Language distribution
-    if !scan_result.languages_detected.is_empty() {
-        println!("\n🏷️  Languages detected:");
-        for (language, count) in &scan_result.languages_detected {
-            println!("  - {}: {} files", language, count);
+#[derive(Debug, Serialize)]
+struct UsageLocation {
+    file: String,
+    line: usize,
+    column: usize,
+    context: String,
+}
+
+impl From<&EnvUsage> for VariableUsage {
+    fn from(usage: &EnvUsage) -> Self {
+        Self {
+            name: usage.name.clone(),
+            files: usage.files.clone(),
+            usage_count: usage.files.len(),
+            locations: (0..usage.files.len())
+                .map(|i| UsageLocation {
+                    file: usage.files[i].clone(),
+                    line: usage.lines[i],
+                    column: usage.columns[i],
+                    context: usage.contexts[i].clone(),
+                })
+                .collect(),
         }
     }
+}
 
-    // Display environment variables
-    if !scan_result.variables.is_empty() {
-        println!("\n🔧 Environment variables found:");
+impl Report for ScanReport {
+    const EXTRA_FORMATS: &'static [OutputFormat] = &[OutputFormat::Sarif];
 
-        let mut variables: Vec<_> = scan_result.variables.values().collect();
-        variables.sort_by(|a, b| a.name.cmp(&b.name));
+    fn print_text(&self, out: &Output) {
+        let summary = &self.scan_result;
+        out.line("✅ Scan completed successfully!");
+        out.line("📊 Scan Statistics:");
+        out.line(format!("  - Files scanned: {}", summary.files_scanned));
+        out.line(format!("  - Variables found: {}", summary.variables_found));
+        out.line(format!(
+            "  - Patterns matched: {}",
+            summary.patterns_matched
+        ));
+        out.line(format!(
+            "  - Security issues: {}",
+            summary.security_issues_count
+        ));
+        if self.baseline {
+            out.line(format!(
+                "  - Accepted by baseline: {}",
+                summary.baselined_issues_count
+            ));
+        }
+        if summary.ignored_issues_count > 0 {
+            out.line(format!(
+                "  - Ignored inline: {}",
+                summary.ignored_issues_count
+            ));
+        }
+        out.line(format!("  - Scan duration: {}ms", summary.scan_duration_ms));
 
-        for usage in variables {
-            let _display_value = if hidden { "***HIDDEN***" } else { "empty" };
+        // Language distribution
+        if !summary.languages_detected.is_empty() {
+            out.line("\n🏷️  Languages detected:");
+            for (language, count) in &summary.languages_detected {
+                out.line(format!("  - {}: {} files", language, count));
+            }
+        }
 
-            if usage.files.len() == 1 {
-                println!("  {} - Used in {}", usage.name, usage.files[0]);
-            } else {
-                println!("  {} - Used in {} locations", usage.name, usage.files.len());
-                if !hidden && usage.files.len() <= 3 {
+        // Display environment variables
+        if !summary.variables.is_empty() {
+            out.line("\n🔧 Environment variables found:");
+
+            for usage in &summary.variables {
+                if let [location] = usage.locations.as_slice() {
+                    out.line(format!(
+                        "  {} - Used in {}:{}:{}",
+                        usage.name, location.file, location.line, location.column
+                    ));
+                    continue;
+                }
+
+                out.line(format!(
+                    "  {} - Used in {} locations",
+                    usage.name,
+                    usage.files.len()
+                ));
+                if !self.hidden && usage.files.len() <= 3 {
                     for file in &usage.files {
-                        println!("    - {}", file);
+                        out.line(format!("    - {}", file));
                     }
-                } else if !hidden {
-                    println!(
+                } else if !self.hidden {
+                    out.line(format!(
                         "    - {} and {} more",
                         usage.files[0..2].join(", "),
                         usage.files.len() - 2
-                    );
+                    ));
                 }
             }
         }
-    }
 
-    // Display security issues
-    if !scan_result.security_issues.is_empty() {
-        println!("\n⚠️  Security Issues:");
-        for issue in &scan_result.security_issues {
-            let severity_icon = match issue.severity {
-                crate::scan::SecuritySeverity::Critical => "🚨",
-                crate::scan::SecuritySeverity::High => "❌",
-                crate::scan::SecuritySeverity::Medium => "⚠️",
-                crate::scan::SecuritySeverity::Low => "ℹ️",
-            };
-            println!(
-                "  {} {}: {}:{}",
-                severity_icon, issue.message, issue.file, issue.line
-            );
+        // Display security issues
+        if !summary.security_issues.is_empty() {
+            out.line("\n⚠️  Security Issues:");
+            for issue in &summary.security_issues {
+                let severity_icon = match issue.severity {
+                    SecuritySeverity::Critical => "🚨",
+                    SecuritySeverity::High => "❌",
+                    SecuritySeverity::Medium => "⚠️",
+                    SecuritySeverity::Low => "ℹ️",
+                };
+                let variable = issue
+                    .variable
+                    .as_ref()
+                    .map(|name| format!(" in {}", name))
+                    .unwrap_or_default();
+                out.line(format!(
+                    "  {} [{}] {}{} ({} confidence): {}:{}:{}",
+                    severity_icon,
+                    issue.rule_id,
+                    issue.message,
+                    variable,
+                    issue.confidence,
+                    issue.file,
+                    issue.line,
+                    issue.column
+                ));
+                out.line(format!("      {}", issue.snippet));
+            }
         }
+
+        out.line("\n✨ Scan completed successfully!");
     }
 
-    // Output in different formats
-    match format {
-        OutputFormat::Text => {
-            println!("\n✨ Scan completed successfully!");
-        }
-        OutputFormat::Json => {
-            println!("\n📄 JSON Output:");
-            let json_output = serde_json::json!({
-                "scan_result": {
-                    "files_scanned": scan_result.files_scanned,
-                    "variables_found": scan_result.variables.len(),
-                    "patterns_matched": scan_result.patterns_matched,
-                    "security_issues_count": scan_result.security_issues.len(),
-                    "scan_duration_ms": scan_result.scan_duration.as_millis(),
-                    "languages_detected": scan_result.languages_detected,
-                    "variables": scan_result.variables.values().map(|usage| {
-                        serde_json::json!({
-                            "name": usage.name,
-                            "files": usage.files,
-                            "usage_count": usage.files.len()
-                        })
-                    }).collect::<Vec<_>>(),
-                    "security_issues": scan_result.security_issues.iter().map(|issue| {
-                        serde_json::json!({
-                            "severity": format!("{:?}", issue.severity),
-                            "message": issue.message,
-                            "file": issue.file,
-                            "line": issue.line
-                        })
-                    }).collect::<Vec<_>>()
-                }
-            });
-            println!("{}", serde_json::to_string_pretty(&json_output)?);
+    fn render_extra(&self, _format: OutputFormat) -> Result<String> {
+        let log = sarif_log(
+            &self.rules,
+            &self.scan_result.security_issues,
+            &self.root,
+            &self.project_root,
+        );
+        Ok(serde_json::to_string_pretty(&log)?)
+    }
+}
+
+/// Scan code for environment variable usage with advanced features.
+pub async fn execute(
+    path: PathBuf,
+    hidden: bool,
+    baseline: Option<PathBuf>,
+    create_baseline: bool,
+    update_baseline: bool,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    out.ensure_supported::<ScanReport>()?;
+    out.progress("🔍 Scanning code for environment variable usage...");
+    out.progress(format!("📁 Path: {}", path.display()));
+
+    // Initialize advanced scanner
+    let mut scanner = CodeScanner::with_config(true, None)?;
+    if project.is_initialized() {
+        scanner = scanner.with_exclusions(&project.load_config()?.scan);
+    }
+
+    out.progress("🚀 Starting advanced scan with parallel processing...");
+
+    // Perform advanced scan
+    let mut scan_result = scanner.scan_directory_advanced(&path).await?;
+
+    // The fingerprints in the baseline itself look like high-entropy strings
+    if let Some(baseline_path) = baseline.as_ref().and_then(|p| p.canonicalize().ok()) {
+        scan_result.security_issues.retain(|issue| {
+            Path::new(&issue.file).canonicalize().ok().as_ref() != Some(&baseline_path)
+        });
+    }
+
+    // Accepted findings are left out of everything reported below
+    let mut baselined = 0;
+    let checked_baseline = match &baseline {
+        Some(baseline_path) if create_baseline || update_baseline => {
+            let summary = write_baseline(
+                baseline_path,
+                &scan_result.security_issues,
+                &path,
+                update_baseline,
+            )?;
+            out.info(summary);
+            baselined = std::mem::take(&mut scan_result.security_issues).len();
+            None
         }
-        OutputFormat::Yaml => {
-            println!("\n📄 YAML Output:");
-            let yaml_output = serde_json::json!({
-                "scan_result": {
-                    "files_scanned": scan_result.files_scanned,
-                    "variables_found": scan_result.variables.len(),
-                    "patterns_matched": scan_result.patterns_matched,
-                    "security_issues_count": scan_result.security_issues.len(),
-                    "scan_duration_ms": scan_result.scan_duration.as_millis(),
-                    "languages_detected": scan_result.languages_detected,
-                    "variables": scan_result.variables.values().map(|usage| {
-                        serde_json::json!({
-                            "name": usage.name,
-                            "files": usage.files,
-                            "usage_count": usage.files.len()
-                        })
-                    }).collect::<Vec<_>>(),
-                    "security_issues": scan_result.security_issues.iter().map(|issue| {
-                        serde_json::json!({
-                            "severity": format!("{:?}", issue.severity),
-                            "message": issue.message,
-                            "file": issue.file,
-                            "line": issue.line
-                        })
-                    }).collect::<Vec<_>>()
-                }
-            });
-            println!("{}", serde_yaml::to_string(&yaml_output)?);
+        Some(baseline_path) => {
+            let (new, accepted) = Baseline::load(baseline_path)?
+                .partition(std::mem::take(&mut scan_result.security_issues), &path);
+            scan_result.security_issues = new;
+            baselined = accepted.len();
+            Some(baseline_path)
         }
+        None => None,
+    };
+
+    let mut variables: Vec<VariableUsage> = scan_result
+        .variables
+        .values()
+        .map(VariableUsage::from)
+        .collect();
+    variables.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let report = ScanReport {
+        scan_result: ScanSummary {
+            files_scanned: scan_result.files_scanned,
+            variables_found: variables.len(),
+            patterns_matched: scan_result.patterns_matched,
+            security_issues_count: scan_result.security_issues.len(),
+            baselined_issues_count: baselined,
+            ignored_issues_count: scan_result.ignored_issues,
+            scan_duration_ms: scan_result.scan_duration.as_millis(),
+            languages_detected: scan_result.languages_detected.into_iter().collect(),
+            variables,
+            security_issues: scan_result.security_issues,
+        },
+        hidden,
+        baseline: baseline.is_some(),
+        rules: scanner.security_rules(),
+        root: path,
+        project_root: project.root().to_path_buf(),
+    };
+    out.render(&report)?;
+
+    check_baseline(checked_baseline, &report.scan_result.security_issues)
+}
+
+/// Fail when a baseline was checked and findings remain that it lacks.
+fn check_baseline(baseline_path: Option<&PathBuf>, new_issues: &[SecurityIssue]) -> Result<()> {
+    match baseline_path {
+        Some(baseline_path) if !new_issues.is_empty() => Err(EnvCliError::Validation(format!(
+            "{} security issue(s) not in baseline {}",
+            new_issues.len(),
+            baseline_path.display()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Build a SARIF log of the findings, declaring every rule the scanner has.
+fn sarif_log(
+    rules: &[RuleInfo],
+    issues: &[SecurityIssue],
+    root: &Path,
+    project_root: &Path,
+) -> sarif::Log {
+    let rules = rules
+        .iter()
+        .map(|rule| {
+            let (level, score) = match rule.severity {
+                SecuritySeverity::Critical => (Level::Error, 9.5),
+                SecuritySeverity::High => (Level::Error, 8.0),
+                SecuritySeverity::Medium => (Level::Warning, 5.5),
+                SecuritySeverity::Low => (Level::Note, 2.0),
+            };
+            sarif::Rule::new(
+                rule.id,
+                &format!("{} in source code", rule.description),
+                level,
+            )
+            .with_tag("security")
+            .with_tag("secrets")
+            .with_security_severity(score)
+        })
+        .collect();
+
+    let mut log = sarif::Log::new(rules);
+    for issue in issues {
+        let message = match &issue.variable {
+            Some(variable) => format!("{} in {}", issue.message, variable),
+            None => issue.message.clone(),
+        };
+        let location = sarif::Location::file(&sarif::uri(Path::new(&issue.file), project_root))
+            .with_region(issue.line, issue.column, Some(issue.end_column))
+            .with_snippet(&issue.snippet);
+        log.add_result(
+            &issue.rule_id,
+            &message,
+            Some(location),
+            Some(suppress::fingerprint(issue, root)),
+        );
+    }
+    log
+}
+
+/// Record `issues` as the accepted findings in `baseline_path`, returning a
+/// summary of the change.
+fn write_baseline(
+    baseline_path: &Path,
+    issues: &[SecurityIssue],
+    root: &Path,
+    update: bool,
+) -> Result<String> {
+    let exists = baseline_path.exists();
+    if exists && !update {
+        return Err(EnvCliError::Config(format!(
+            "Baseline {} already exists. Use --update-baseline to replace it",
+            baseline_path.display()
+        )));
     }
 
-    Ok(())
+    let baseline = Baseline::from_issues(issues, root);
+    let summary = if exists {
+        let previous = Baseline::load(baseline_path)?;
+        let (added, kept) = previous.partition(issues.to_vec(), root);
+        format!(
+            "✓ Updated baseline {}: {} added, {} removed, {} kept",
+            baseline_path.display(),
+            added.len(),
+            previous.findings.len() - kept.len(),
+            kept.len()
+        )
+    } else {
+        format!(
+            "✓ Created baseline {} with {} finding(s)",
+            baseline_path.display(),
+            baseline.findings.len()
+        )
+    };
+
+    baseline.save(baseline_path)?;
+    Ok(summary)
 }
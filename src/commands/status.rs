@@ -1,175 +1,345 @@
 //! Status command implementation.
 
-use crate::config::{default_config_path, load_config};
-use crate::env::EnvManager;
-use crate::error::{EnvCliError, Result};
-use chrono::DateTime;
-use std::fs::read_link;
-use std::path::PathBuf;
+use crate::config::{Config, Project};
+use crate::env::{EnvManager, ExpandOptions, Layer};
+use crate::error::Result;
+use crate::report::{Output, Report};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// State of the project and its current environment.
+#[derive(Debug, Default, Serialize)]
+pub struct StatusReport {
+    pub initialized: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_environment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment_file: Option<PathBuf>,
+    pub variable_count: usize,
+    pub environments: Vec<String>,
+    pub backup_count: usize,
+    /// Present with `--verbose`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<StatusDetails>,
+}
+
+/// Detailed information shown by `status --verbose`.
+#[derive(Debug, Serialize)]
+pub struct StatusDetails {
+    pub project: String,
+    pub config_file: PathBuf,
+    pub default_environment: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<DateTime<Utc>>,
+    /// Whether `${VAR}` references were expanded
+    pub expanded: bool,
+    pub variables: Vec<VariableStatus>,
+    pub security_issues: usize,
+    pub security_warnings: usize,
+    /// Times of the most recent environment backups, newest first
+    pub recent_backups: Vec<DateTime<Utc>>,
+    /// Entries under `.env`, as indented paths
+    pub structure: Vec<String>,
+}
+
+/// One variable of the current environment, or of the schema.
+#[derive(Debug, Serialize)]
+pub struct VariableStatus {
+    pub name: String,
+    pub state: VariableState,
+    /// Masked value, or the default when the variable is not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+    /// Layer the value was read from, for set variables
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Layer>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableState {
+    Set,
+    Missing,
+    Default,
+}
+
+impl Report for StatusReport {
+    fn print_text(&self, out: &Output) {
+        out.line("Environment Status");
+
+        if !self.initialized {
+            out.line("  ✗ Not an env-cli project");
+            out.info("  Run 'env init' to initialize.");
+            return;
+        }
+        out.line("  ✓ Project initialized");
+        out.line("  ✓ Configuration loaded");
+
+        let Some(current_env) = &self.current_environment else {
+            out.line("  ⚠ No current environment set");
+            out.info("    Use 'env switch <environment>' to set one.");
+            return;
+        };
+        out.line(format!("  ✓ Current environment: {}", current_env));
+
+        if let Some(env_file) = &self.environment_file {
+            out.line(format!(
+                "  ✓ Environment file: {} ({} variables)",
+                env_file.file_name().unwrap_or_default().to_string_lossy(),
+                self.variable_count
+            ));
+        }
+        out.line(format!(
+            "  Available environments: {}",
+            self.environments.join(", ")
+        ));
+        if self.backup_count > 0 {
+            out.line(format!("  ✓ Backup files: {}", self.backup_count));
+        }
+
+        match &self.details {
+            Some(details) => self.print_details(details, out),
+            None => out.info("\nRun with --verbose for detailed information."),
+        }
+    }
+}
+
+impl StatusReport {
+    fn print_details(&self, details: &StatusDetails, out: &Output) {
+        out.line("\nDetailed Information:");
+        out.line(format!("  Project: {}", details.project));
+        out.line(format!(
+            "  Configuration file: {}",
+            details.config_file.display()
+        ));
+        if let Some(env_file) = &self.environment_file {
+            out.line(format!("  Environment file: {}", env_file.display()));
+        }
+        out.line(format!(
+            "  Default environment: {}",
+            details.default_environment
+        ));
+        out.line(format!("  Total environments: {}", self.environments.len()));
+        out.line(format!(
+            "  Current environment variables: {}",
+            self.variable_count
+        ));
+        if let Some(description) = &details.description {
+            out.line(format!("  Environment description: {}", description));
+        }
+        if let Some(modified) = details.last_modified {
+            out.line(format!(
+                "  Last modified: {}",
+                modified.format("%Y-%m-%d %H:%M:%S UTC")
+            ));
+        }
+
+        if details.expanded {
+            out.line("\nVariable Status (references expanded, use --raw to disable):");
+        } else {
+            out.line("\nVariable Status (values as written):");
+        }
+        if self.variable_count == 0 {
+            out.line("  No environment variables set");
+        }
+        for variable in &details.variables {
+            out.line(format!("  {}", variable));
+        }
+
+        out.line("\nSecurity Status:");
+        if details.security_issues == 0 {
+            out.line("  ✓ No critical security issues");
+        } else {
+            out.line(format!(
+                "  ✗ {} critical security issues found",
+                details.security_issues
+            ));
+        }
+        if details.security_warnings > 0 {
+            out.line(format!(
+                "  ⚠ {} security warnings",
+                details.security_warnings
+            ));
+        }
+
+        out.line("\nRecent Activity:");
+        if details.recent_backups.is_empty() {
+            out.line("  No recent activity found");
+        }
+        for backup in &details.recent_backups {
+            out.line(format!(
+                "  [{}] Environment backup created",
+                backup.format("%Y-%m-%d %H:%M")
+            ));
+        }
+
+        out.line("\nProject Structure:");
+        for entry in &details.structure {
+            out.line(entry);
+        }
+    }
+}
+
+impl fmt::Display for VariableStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.value.as_deref().unwrap_or_default();
+        match self.state {
+            VariableState::Set => write!(f, "✓ {} - {}", self.name, value)?,
+            VariableState::Missing => return write!(f, "✗ {} - Missing (required)", self.name),
+            VariableState::Default => {
+                return write!(f, "○ {} - Not set, default: {}", self.name, value)
+            }
+        }
+        if let Some(description) = &self.description {
+            write!(f, " ({})", description)?;
+        }
+        if let Some(note) = &self.deprecated {
+            write!(f, " [deprecated: {}]", note)?;
+        }
+        if let Some(origin) = &self.origin {
+            write!(f, " [from {}]", origin)?;
+        }
+        Ok(())
+    }
+}
 
 /// Show current environment status.
-pub async fn execute(verbose: bool) -> Result<()> {
-    println!("Environment Status");
+pub async fn execute(verbose: bool, raw: bool, project: &Project, out: &Output) -> Result<()> {
+    out.ensure_supported::<StatusReport>()?;
 
     // Check if we're in an env-cli project
-    let env_dir = PathBuf::from(".env");
-    if !env_dir.exists() {
-        println!("  ✗ Not an env-cli project");
-        println!("  Run 'env init' to initialize.");
-        return Ok(());
+    if !project.env_dir().exists() {
+        return out.render(&StatusReport::default());
     }
 
-    println!("  ✓ Project initialized");
-
     // Load configuration
-    let config_path = default_config_path();
-    let config = load_config(&config_path)?;
-    println!("  ✓ Configuration loaded");
+    let config = project.load_config()?;
+    let mut report = StatusReport {
+        initialized: true,
+        environments: config.environments.iter().map(|e| e.name.clone()).collect(),
+        ..Default::default()
+    };
 
     // Get current environment
-    let current_env = match get_current_environment() {
+    let current_env = match project.current_environment() {
         Ok(env) => env,
-        Err(_) => {
-            println!("  ⚠ No current environment set");
-            println!("    Use 'env switch <environment>' to set one.");
-            return Ok(());
-        }
+        Err(_) => return out.render(&report),
     };
-    println!("  ✓ Current environment: {}", current_env);
 
     // Load environment variables
-    let env_file =
-        if let Some(env_config) = config.environments.iter().find(|e| e.name == current_env) {
-            if let Some(file) = &env_config.file {
-                file.clone()
-            } else {
-                env_dir
-                    .join("environments")
-                    .join(format!("{}.env", current_env))
-            }
-        } else {
-            env_dir
-                .join("environments")
-                .join(format!("{}.env", current_env))
-        };
+    let env_file = config.environment_file(&current_env);
 
-    let mut env_manager = EnvManager::new();
-    let var_count = if env_file.exists() {
-        env_manager.load_from_file(&env_file)?;
-        env_manager.list().count()
-    } else {
-        0
-    };
-
-    println!(
-        "  ✓ Environment file: {} ({} variables)",
-        env_file.file_name().unwrap_or_default().to_string_lossy(),
-        var_count
-    );
-
-    // Show available environments
-    println!(
-        "  Available environments: {}",
-        config
-            .environments
-            .iter()
-            .map(|e| e.name.as_str())
-            .collect::<Vec<_>>()
-            .join(", ")
-    );
+    let mut env_manager = EnvManager::for_project(project);
+    if env_file.exists() {
+        if config.environment(&current_env).is_ok() {
+            env_manager.load_layers(config.layers(&current_env)?)?;
+        } else {
+            env_manager.load_from_file(&env_file)?;
+        }
+        if !raw {
+            env_manager.expand(ExpandOptions::default())?;
+        }
+    }
+    report.variable_count = env_manager.list().count();
 
     // Check for backups
-    let backups_dir = env_dir.join("backups");
-    let backup_count = if backups_dir.exists() {
+    let backups_dir = project.backups_dir();
+    report.backup_count = if backups_dir.exists() {
         std::fs::read_dir(&backups_dir)?.count()
     } else {
         0
     };
 
-    if backup_count > 0 {
-        println!("  ✓ Backup files: {}", backup_count);
-    }
-
     if verbose {
-        print_detailed_status(&config, &current_env, &env_manager, &env_file).await?;
-    } else {
-        println!("\nRun with --verbose for detailed information.");
+        report.details = Some(detailed_status(
+            project,
+            &config,
+            &current_env,
+            &env_manager,
+            &env_file,
+            raw,
+        )?);
     }
+    report.current_environment = Some(current_env);
+    report.environment_file = Some(env_file);
 
-    Ok(())
+    out.render(&report)
 }
 
-/// Print detailed status information.
-async fn print_detailed_status(
-    config: &crate::config::Config,
+/// Collect detailed status information.
+fn detailed_status(
+    project: &Project,
+    config: &Config,
     current_env: &str,
     env_manager: &EnvManager,
     env_file: &PathBuf,
-) -> Result<()> {
-    println!("\nDetailed Information:");
-    println!("  Project: {}", config.project);
-    println!("  Configuration file: .env/config.toml");
-    println!("  Environment file: {}", env_file.display());
-    println!("  Default environment: {}", config.default_environment);
-    println!("  Total environments: {}", config.environments.len());
-    println!(
-        "  Current environment variables: {}",
-        env_manager.list().count()
-    );
-
-    // Show environment details
-    if let Some(env_config) = config.environments.iter().find(|e| e.name == current_env) {
-        if let Some(description) = &env_config.description {
-            println!("  Environment description: {}", description);
-        }
-    }
-
-    // Show file modification time
-    if let Ok(metadata) = std::fs::metadata(env_file) {
-        if let Ok(modified) = metadata.modified() {
-            if let Some(datetime) = DateTime::from_timestamp(
-                modified.duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64,
-                0,
-            ) {
-                println!(
-                    "  Last modified: {}",
-                    datetime.format("%Y-%m-%d %H:%M:%S UTC")
-                );
-            }
-        }
-    }
+    raw: bool,
+) -> Result<StatusDetails> {
+    let description = config
+        .environments
+        .iter()
+        .find(|e| e.name == current_env)
+        .and_then(|e| e.description.clone());
+
+    // File modification time
+    let last_modified = match std::fs::metadata(env_file).and_then(|m| m.modified()) {
+        Ok(modified) => DateTime::from_timestamp(
+            modified.duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64,
+            0,
+        ),
+        Err(_) => None,
+    };
 
     // Variable status
-    println!("\nVariable Status:");
-    let mut variables: Vec<_> = env_manager.list().collect();
-    variables.sort_by_key(|(k, _)| *k);
+    let mut variables = Vec::new();
+    let mut set: Vec<_> = env_manager.list().collect();
+    set.sort_by_key(|(k, _)| *k);
 
-    if variables.is_empty() {
-        println!("  No environment variables set");
-    } else {
+    if !set.is_empty() {
         // Show required variables first
-        if !config.validation.required.is_empty() {
-            for required in &config.validation.required {
-                if let Some(value) = env_manager.get(required) {
-                    let masked_value = mask_sensitive_value(required, value);
-                    println!("  ✓ {} - {}", required, masked_value);
-                } else {
-                    println!("  ✗ {} - Missing (required)", required);
-                }
-            }
+        for required in &config.validation.required {
+            variables.push(match env_manager.get(required) {
+                Some(value) => describe_variable(config, env_manager, required, value),
+                None => VariableStatus::missing(required),
+            });
         }
 
         // Show other variables
-        for (key, value) in variables {
+        for (key, value) in set {
             if !config.validation.required.contains(key) {
-                let masked_value = mask_sensitive_value(key, value);
-                println!("  ✓ {} - {}", key, masked_value);
+                variables.push(describe_variable(config, env_manager, key, value));
             }
         }
     }
 
+    // Show schema variables that are not set
+    for (key, spec) in &config.variables {
+        if env_manager.get(key).is_some() || config.validation.required.contains(key) {
+            continue;
+        }
+        if let Some(default) = &spec.default {
+            variables.push(VariableStatus {
+                name: key.clone(),
+                state: VariableState::Default,
+                value: Some(default.clone()),
+                description: None,
+                deprecated: None,
+                origin: None,
+            });
+        } else if spec.is_required_in(current_env) {
+            variables.push(VariableStatus::missing(key));
+        }
+    }
+
     // Security status
-    println!("\nSecurity Status:");
     let mut security_issues = 0;
     let mut security_warnings = 0;
 
@@ -187,81 +357,71 @@ async fn print_detailed_status(
         }
     }
 
-    if security_issues == 0 {
-        println!("  ✓ No critical security issues");
-    } else {
-        println!("  ✗ {} critical security issues found", security_issues);
-    }
-
-    if security_warnings > 0 {
-        println!("  ⚠ {} security warnings", security_warnings);
-    }
-
-    // Recent activity
-    println!("\nRecent Activity:");
-    show_recent_activity().await?;
-
-    // Project structure
-    println!("\nProject Structure:");
-    let env_dir = PathBuf::from(".env");
-    show_directory_tree(&env_dir, 2)?;
-
-    Ok(())
+    let mut structure = Vec::new();
+    directory_tree(&project.env_dir(), 2, &mut structure);
+
+    Ok(StatusDetails {
+        project: config.project.clone(),
+        config_file: project.config_path().to_path_buf(),
+        default_environment: config.default_environment.clone(),
+        description,
+        last_modified,
+        expanded: !raw,
+        variables,
+        security_issues,
+        security_warnings,
+        recent_backups: recent_activity(&project.backups_dir())?,
+        structure,
+    })
 }
 
-/// Get the current environment name.
-fn get_current_environment() -> Result<String> {
-    let current_path = PathBuf::from(".env/.current");
-
-    if !current_path.exists() {
-        return Err(EnvCliError::Environment(
-            "No current environment set".to_string(),
-        ));
-    }
-
-    #[cfg(unix)]
-    {
-        let target = read_link(&current_path)?;
-
-        if let Some(file_name) = target
-            .file_name()
-            .and_then(|n: &std::ffi::OsStr| n.to_str())
-        {
-            if let Some(env_name) = file_name.strip_suffix(".env") {
-                return Ok(env_name.to_string());
-            }
+impl VariableStatus {
+    fn missing(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            state: VariableState::Missing,
+            value: None,
+            description: None,
+            deprecated: None,
+            origin: None,
         }
     }
+}
 
-    #[cfg(windows)]
-    {
-        let target = read_link(&current_path)?;
+/// Describe a set variable, masking secrets and adding its schema description
+/// and origin.
+fn describe_variable(
+    config: &crate::config::Config,
+    env_manager: &EnvManager,
+    key: &str,
+    value: &str,
+) -> VariableStatus {
+    let spec = config.variables.get(key);
+    let shown = match spec {
+        Some(spec) if spec.secret => "****".to_string(),
+        _ => mask_sensitive_value(key, value),
+    };
 
-        if let Some(file_name) = target
-            .file_name()
-            .and_then(|n: &std::ffi::OsStr| n.to_str())
-        {
-            if let Some(env_name) = file_name.strip_suffix(".env") {
-                return Ok(env_name.to_string());
-            }
-        }
+    VariableStatus {
+        name: key.to_string(),
+        state: VariableState::Set,
+        value: Some(shown),
+        description: spec.and_then(|spec| spec.description.clone()),
+        deprecated: spec.and_then(|spec| spec.deprecated.clone()),
+        origin: env_manager.origin(key).cloned(),
     }
-
-    Err(EnvCliError::Environment(
-        "Unable to determine current environment".to_string(),
-    ))
 }
 
 /// Mask sensitive values for display.
-fn mask_sensitive_value(key: &str, value: &str) -> String {
+pub(crate) fn mask_sensitive_value(key: &str, value: &str) -> String {
     let sensitive_keywords = ["password", "secret", "key", "token", "auth"];
     let key_lower = key.to_lowercase();
 
     if sensitive_keywords.iter().any(|&kw| key_lower.contains(kw)) {
-        if value.len() <= 4 {
+        if value.chars().count() <= 4 {
             "****".to_string()
         } else {
-            format!("{}****", &value[..4])
+            format!("{}****", value.chars().take(4).collect::<String>())
         }
     } else {
         value.to_string()
@@ -298,17 +458,13 @@ fn is_insecure_value(value: &str, security_config: &crate::config::SecurityConfi
     false
 }
 
-/// Show recent activity logs.
-async fn show_recent_activity() -> Result<()> {
-    let env_dir = PathBuf::from(".env");
-    let backups_dir = env_dir.join("backups");
-
+/// Times of the five most recent environment backups.
+fn recent_activity(backups_dir: &Path) -> Result<Vec<DateTime<Utc>>> {
     if !backups_dir.exists() {
-        println!("  No recent activity found");
-        return Ok(());
+        return Ok(Vec::new());
     }
 
-    let mut entries: Vec<_> = std::fs::read_dir(&backups_dir)?
+    let mut entries: Vec<_> = std::fs::read_dir(backups_dir)?
         .filter_map(|entry| entry.ok())
         .filter(|entry| entry.file_name().to_string_lossy().ends_with(".env"))
         .collect();
@@ -326,38 +482,23 @@ async fn show_recent_activity() -> Result<()> {
         b_time.cmp(&a_time)
     });
 
-    let mut activity_count = 0;
+    let mut activity = Vec::new();
     for entry in entries.iter().take(5) {
-        if let Ok(metadata) = entry.metadata() {
-            if let Ok(modified) = metadata.modified() {
-                if let Some(datetime) = DateTime::from_timestamp(
-                    modified.duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64,
-                    0,
-                ) {
-                    let filename = entry.file_name().to_string_lossy().into_owned();
-                    if let Some(env_name) = filename.strip_suffix(".env") {
-                        if let Some(_timestamp) = env_name.split('_').last() {
-                            println!(
-                                "  [{}] Environment backup created",
-                                datetime.format("%Y-%m-%d %H:%M")
-                            );
-                            activity_count += 1;
-                        }
-                    }
-                }
+        if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+            if let Some(datetime) = DateTime::from_timestamp(
+                modified.duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64,
+                0,
+            ) {
+                activity.push(datetime);
             }
         }
     }
 
-    if activity_count == 0 {
-        println!("  No recent activity found");
-    }
-
-    Ok(())
+    Ok(activity)
 }
 
-/// Show directory tree structure.
-fn show_directory_tree(dir: &PathBuf, indent: usize) -> Result<()> {
+/// Collect the directory tree under `dir`, one indented entry per line.
+fn directory_tree(dir: &PathBuf, indent: usize, lines: &mut Vec<String>) {
     let indent_str = " ".repeat(indent);
 
     if let Ok(entries) = std::fs::read_dir(dir) {
@@ -369,15 +510,13 @@ fn show_directory_tree(dir: &PathBuf, indent: usize) -> Result<()> {
             let path = entry.path();
 
             if path.is_dir() {
-                println!("{}{}/", indent_str, name);
+                lines.push(format!("{}{}/", indent_str, name));
                 if name != "." && name != ".." {
-                    show_directory_tree(&path, indent + 2)?;
+                    directory_tree(&path, indent + 2, lines);
                 }
             } else {
-                println!("{}{}", indent_str, name);
+                lines.push(format!("{}{}", indent_str, name));
             }
         }
     }
-
-    Ok(())
 }
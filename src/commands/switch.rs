@@ -1,27 +1,25 @@
 //! Switch command implementation.
 
-use crate::config::{default_config_path, load_config};
-use crate::env::EnvManager;
+use super::confirm;
+use crate::config::{Config, Project};
+use crate::env::{EnvManager, ExpandOptions};
 use crate::error::{EnvCliError, Result};
+use crate::report::Output;
 use chrono::Utc;
 // use std::fs;
 use std::path::PathBuf;
 
 /// Switch to a different environment.
-pub async fn execute(environment: String, yes: bool) -> Result<()> {
-    println!("Switching to environment: {}", environment);
-
-    // Check if we're in an env-cli project
-    let env_dir = PathBuf::from(".env");
-    if !env_dir.exists() {
-        return Err(EnvCliError::Config(
-            "Not an env-cli project. Run 'env init' first.".to_string(),
-        ));
-    }
+pub async fn execute(
+    environment: String,
+    yes: bool,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    out.progress(format!("Switching to environment: {}", environment));
 
     // Load configuration
-    let config_path = default_config_path();
-    let config = load_config(&config_path)?;
+    let config = project.load_config()?;
 
     // Validate target environment exists
     let target_env = config
@@ -42,63 +40,53 @@ pub async fn execute(environment: String, yes: bool) -> Result<()> {
         })?;
 
     // Show current environment if it exists
-    if let Ok(current) = get_current_environment() {
+    if let Ok(current) = project.current_environment() {
         if current == environment {
-            println!("Already using '{}' environment.", environment);
+            out.info(format!("Already using '{}' environment.", environment));
             return Ok(());
         }
 
         if !yes {
-            println!("Current environment: {}", current);
-            println!("Target environment: {}", target_env.name.clone());
+            out.info(format!("Current environment: {}", current));
+            out.info(format!("Target environment: {}", target_env.name));
 
             if let Some(desc) = &target_env.description {
-                println!("Description: {}", desc);
+                out.info(format!("Description: {}", desc));
             }
 
-            println!(
+            out.info(format!(
                 "\nThis will create a backup of the current environment and switch to '{}'.",
                 environment
-            );
-            print!("Continue? [y/N] ");
-            use std::io::Write;
-            std::io::stdout().flush()?;
-
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-
-            if !input.trim().to_lowercase().starts_with('y') {
-                println!("Operation cancelled.");
+            ));
+            if !confirm("Continue?")? {
+                out.info("Operation cancelled.");
                 return Ok(());
             }
         }
     } else if !yes {
-        println!(
+        out.info(format!(
             "No current environment set. Will set '{}' as the active environment.",
             environment
-        );
+        ));
         if let Some(desc) = &target_env.description {
-            println!("Description: {}", desc);
+            out.info(format!("Description: {}", desc));
         }
 
-        print!("Continue? [y/N] ");
-        use std::io::Write;
-        std::io::stdout().flush()?;
-
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input)?;
-
-        if !input.trim().to_lowercase().starts_with('y') {
-            println!("Operation cancelled.");
+        if !confirm("Continue?")? {
+            out.info("Operation cancelled.");
             return Ok(());
         }
     }
 
     // Perform the switch
-    perform_environment_switch(&environment, &config).await?;
+    perform_environment_switch(&environment, project, &config, out).await?;
 
-    println!("\nSuccessfully switched to '{}' environment!", environment);
-    println!("Run 'env status' to verify the switch.");
+    out.info(format!(
+        "\nSuccessfully switched to '{}' environment!",
+        environment
+    ));
+    out.info("Run 'env status' to verify the switch.");
+    out.info("Run 'env run -- <command>' to start a process with these variables.");
 
     Ok(())
 }
@@ -106,29 +94,20 @@ pub async fn execute(environment: String, yes: bool) -> Result<()> {
 /// Perform the actual environment switch.
 async fn perform_environment_switch(
     environment: &str,
-    config: &crate::config::Config,
+    project: &Project,
+    config: &Config,
+    out: &Output,
 ) -> Result<()> {
-    let env_dir = PathBuf::from(".env");
+    let env_dir = project.env_dir();
 
     // Create backup of current environment if it exists
-    if let Ok(current_env) = get_current_environment() {
+    if let Ok(current_env) = project.current_environment() {
         create_backup(&current_env, &env_dir)?;
-        println!("✓ Created backup of current environment");
+        out.info("✓ Created backup of current environment");
     }
 
     // Validate target environment file exists
-    let target_file = if let Some(file) = config
-        .environments
-        .iter()
-        .find(|e| e.name == environment)
-        .and_then(|e| e.file.as_ref())
-    {
-        file.clone()
-    } else {
-        env_dir
-            .join("environments")
-            .join(format!("{}.env", environment))
-    };
+    let target_file = config.environment_file(environment);
 
     if !target_file.exists() {
         return Err(EnvCliError::FileSystem(format!(
@@ -138,26 +117,20 @@ async fn perform_environment_switch(
     }
 
     // Validate environment file can be parsed
-    let mut env_manager = EnvManager::new();
-    env_manager.load_from_file(&target_file)?;
-    println!("✓ Validated environment configuration");
+    let mut env_manager = EnvManager::for_project(project);
+    env_manager.load_layers(config.layers(environment)?)?;
+    env_manager.expand(ExpandOptions::default())?;
+    out.info("✓ Validated environment configuration");
 
     // Check for required variables
     if !config.validation.required.is_empty() {
         let missing = env_manager.validate(&config.validation.required);
         if !missing.is_empty() {
-            println!(
+            out.warn(format!(
                 "Warning: Missing required environment variables: {}",
                 missing.join(", ")
-            );
-            print!("Continue anyway? [y/N] ");
-            use std::io::Write;
-            std::io::stdout().flush()?;
-
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-
-            if !input.trim().to_lowercase().starts_with('y') {
+            ));
+            if !confirm("Continue anyway?")? {
                 return Err(EnvCliError::Validation(
                     "Missing required environment variables".to_string(),
                 ));
@@ -167,64 +140,13 @@ async fn perform_environment_switch(
 
     // Update .current symlink
     update_current_symlink(&env_dir, environment)?;
-    println!("✓ Updated current environment link");
+    out.info("✓ Updated current environment link");
 
-    // Load environment variables into current process (optional)
-    // Note: This only affects the current process, not the shell
-    for (key, value) in env_manager.list() {
-        std::env::set_var(key, value);
-    }
-
-    println!("✓ Switched to environment '{}'", environment);
+    out.info(format!("✓ Switched to environment '{}'", environment));
 
     Ok(())
 }
 
-/// Get the current environment from the .current symlink.
-fn get_current_environment() -> Result<String> {
-    let current_path = PathBuf::from(".env/.current");
-
-    if !current_path.exists() {
-        return Err(EnvCliError::Environment(
-            "No current environment set".to_string(),
-        ));
-    }
-
-    #[cfg(unix)]
-    {
-        let target = std::fs::read_link(&current_path)?;
-
-        // Extract environment name from path like "environments/development.env"
-        if let Some(file_name) = target
-            .file_name()
-            .and_then(|n: &std::ffi::OsStr| n.to_str())
-        {
-            if let Some(env_name) = file_name.strip_suffix(".env") {
-                return Ok(env_name.to_string());
-            }
-        }
-    }
-
-    #[cfg(windows)]
-    {
-        let target = std::fs::read_link(&current_path)?;
-
-        // Extract environment name from path like "environments\development.env"
-        if let Some(file_name) = target
-            .file_name()
-            .and_then(|n: &std::ffi::OsStr| n.to_str())
-        {
-            if let Some(env_name) = file_name.strip_suffix(".env") {
-                return Ok(env_name.to_string());
-            }
-        }
-    }
-
-    Err(EnvCliError::Environment(
-        "Unable to determine current environment".to_string(),
-    ))
-}
-
 /// Create a backup of the current environment.
 fn create_backup(env_name: &str, env_dir: &PathBuf) -> Result<()> {
     let current_path = env_dir.join(".current");
@@ -1,166 +1,278 @@
 //! Advanced sync command implementation for EC-03.
 
+use super::confirm;
+use crate::config::Project;
 use crate::error::Result;
-use crate::sync::{ConflictResolution, EnvironmentSync, SyncConfig};
+use crate::report::{Output, Report};
+use crate::sync::{
+    ChangeType, ConflictResolution, ConflictType, EnvironmentSync, ProposedChange,
+    SecuritySeverity, SecurityViolation, SyncConfig, SyncConflict,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+/// What `env sync` found and did.
+#[derive(Debug, Serialize)]
+pub struct SyncReport {
+    pub source: String,
+    pub target: String,
+    pub proposed_changes: Vec<ProposedChange>,
+    pub conflicts: Vec<SyncConflict>,
+    pub security_violations: Vec<SecurityViolation>,
+    /// Whether the user declined the confirmation prompt
+    pub cancelled: bool,
+    /// Present when the sync ran
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<SyncOutcome>,
+}
+
+/// The changes a sync applied.
+#[derive(Debug, Serialize)]
+pub struct SyncOutcome {
+    pub synced_variables: Vec<String>,
+    pub conflicts_resolved: usize,
+    pub duration_ms: u128,
+    pub backup_created: bool,
+    pub audit_log: PathBuf,
+}
+
+impl SyncReport {
+    fn is_noop(&self) -> bool {
+        self.proposed_changes.is_empty() && self.conflicts.is_empty()
+    }
+
+    /// Print what the sync would change.
+    fn print_plan(&self, out: &Output) {
+        if !self.proposed_changes.is_empty() {
+            out.line("\n📋 Proposed Changes:");
+            for change in &self.proposed_changes {
+                let change_icon = match change.change_type {
+                    ChangeType::Add => "➕",
+                    ChangeType::Update => "🔄",
+                    ChangeType::Remove => "➖",
+                    ChangeType::Conflict => "⚠️",
+                };
+                out.line(format!(
+                    "  {} {}: '{}' -> '{}'",
+                    change_icon,
+                    change.variable,
+                    or_empty(&change.old_value),
+                    or_empty(&change.new_value)
+                ));
+            }
+        }
+
+        if !self.conflicts.is_empty() {
+            out.line("\n⚠️  Conflicts Detected:");
+            for conflict in &self.conflicts {
+                let conflict_icon = match conflict.conflict_type {
+                    ConflictType::ValueMismatch => "💥",
+                    ConflictType::MissingInTarget => "➕",
+                    ConflictType::MissingInSource => "➖",
+                    ConflictType::TypeMismatch => "🔄",
+                    ConflictType::SecurityViolation => "🚨",
+                };
+                out.line(format!(
+                    "  {} {}: Source='{}' vs Target='{}'",
+                    conflict_icon,
+                    conflict.variable,
+                    or_empty(&conflict.source_value),
+                    or_empty(&conflict.target_value)
+                ));
+            }
+        }
+
+        if !self.security_violations.is_empty() {
+            out.line("\n🚨 Security Violations:");
+            for violation in &self.security_violations {
+                let severity_icon = match violation.severity {
+                    SecuritySeverity::Critical => "🚨",
+                    SecuritySeverity::High => "❌",
+                    SecuritySeverity::Medium => "⚠️",
+                    SecuritySeverity::Low => "ℹ️",
+                };
+                out.line(format!(
+                    "  {} {}: {}",
+                    severity_icon, violation.variable, violation.description
+                ));
+            }
+        }
+
+        if self.is_noop() {
+            out.line("\n✅ No changes needed. Environments are already in sync.");
+        }
+    }
+
+    /// Print what the sync did.
+    fn print_outcome(&self, out: &Output) {
+        if self.cancelled {
+            out.line("❌ Synchronization cancelled by user.");
+            return;
+        }
+        let Some(result) = &self.result else {
+            return;
+        };
+
+        out.line("✅ Synchronization completed successfully!");
+        out.line("📊 Sync Results:");
+        out.line(format!(
+            "  - Variables synced: {}",
+            result.synced_variables.len()
+        ));
+        out.line(format!(
+            "  - Conflicts resolved: {}",
+            result.conflicts_resolved
+        ));
+        out.line(format!("  - Duration: {}ms", result.duration_ms));
+        out.line(format!("  - Backup created: {}", result.backup_created));
+
+        if !result.synced_variables.is_empty() {
+            out.line("\n🔄 Synced Variables:");
+            for variable in &result.synced_variables {
+                out.line(format!("  - {}", variable));
+            }
+        }
+
+        out.info(format!(
+            "\n📝 Audit log updated: {}",
+            result.audit_log.display()
+        ));
+        out.info("💡 Run 'env status --verbose' to see detailed changes.");
+    }
+}
+
+impl Report for SyncReport {
+    fn print_text(&self, out: &Output) {
+        self.print_plan(out);
+        self.print_outcome(out);
+    }
+}
+
+/// The file to sync for an argument, and the inline values beneath it.
+///
+/// An argument naming a configured environment, rather than an existing
+/// file, stands for that environment's file.
+fn resolve(project: &Project, argument: &str) -> Result<(PathBuf, BTreeMap<String, String>)> {
+    let path = PathBuf::from(argument);
+    if !project.is_initialized() {
+        return Ok((path, BTreeMap::new()));
+    }
+
+    let config = project.load_config()?;
+    let environment = config.environments.iter().find(|environment| {
+        (environment.name == argument && !path.exists())
+            || config.environment_file(&environment.name) == path
+    });
+    Ok(match environment {
+        Some(environment) => (
+            config.environment_file(&environment.name),
+            environment.variables.clone(),
+        ),
+        None => (path, BTreeMap::new()),
+    })
+}
+
+fn or_empty(value: &str) -> &str {
+    if value.is_empty() {
+        "(empty)"
+    } else {
+        value
+    }
+}
+
 /// Sync environments safely with advanced conflict detection and resolution.
-pub async fn execute(source: String, target: String, yes: bool) -> Result<()> {
-    println!("🔄 Synchronizing environments...");
-    println!("📂 Source: {}", source);
-    println!("📂 Target: {}", target);
+pub async fn execute(
+    source: String,
+    target: String,
+    yes: bool,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    out.ensure_supported::<SyncReport>()?;
+    out.progress("🔄 Synchronizing environments...");
+    out.progress(format!("📂 Source: {}", source));
+    out.progress(format!("📂 Target: {}", target));
 
-    let source_path = PathBuf::from(source);
-    let target_path = PathBuf::from(target);
+    let (source_path, source_inline) = resolve(project, &source)?;
+    let (target_path, target_inline) = resolve(project, &target)?;
 
     // Configure synchronization
     let config = SyncConfig {
         conflict_resolution: ConflictResolution::ManualReview,
         backup_before_sync: true,
-        audit_log_path: PathBuf::from(".env/sync-audit.json"),
+        audit_log_path: project.audit_log_path(),
         security_check: true,
         dry_run: false,
         selective_variables: None,
+        project: project.clone(),
     };
+    let audit_log = config.audit_log_path.clone();
 
-    let mut sync_engine = EnvironmentSync::new(config);
+    let mut sync_engine = EnvironmentSync::new(config)
+        .with_inline_variables(source_path.clone(), source_inline)
+        .with_inline_variables(target_path.clone(), target_inline);
 
-    println!("🔍 Analyzing environments for conflicts...");
+    out.progress("🔍 Analyzing environments for conflicts...");
 
     // Perform dry run first
     let dry_run_result = sync_engine.dry_run_sync(&source_path, &target_path).await?;
 
-    // Display dry run results
-    if !dry_run_result.proposed_changes.is_empty() {
-        println!("\n📋 Proposed Changes:");
-        for change in &dry_run_result.proposed_changes {
-            let change_icon = match change.change_type {
-                crate::sync::ChangeType::Add => "➕",
-                crate::sync::ChangeType::Update => "🔄",
-                crate::sync::ChangeType::Remove => "➖",
-                crate::sync::ChangeType::Conflict => "⚠️",
-            };
-            println!(
-                "  {} {}: '{}' -> '{}'",
-                change_icon,
-                change.variable,
-                if change.old_value.is_empty() {
-                    "(empty)"
-                } else {
-                    &change.old_value
-                },
-                if change.new_value.is_empty() {
-                    "(empty)"
-                } else {
-                    &change.new_value
-                }
-            );
-        }
-    }
-
-    // Display conflicts
-    if !dry_run_result.conflicts.is_empty() {
-        println!("\n⚠️  Conflicts Detected:");
-        for conflict in &dry_run_result.conflicts {
-            let conflict_icon = match conflict.conflict_type {
-                crate::sync::ConflictType::ValueMismatch => "💥",
-                crate::sync::ConflictType::MissingInTarget => "➕",
-                crate::sync::ConflictType::MissingInSource => "➖",
-                crate::sync::ConflictType::TypeMismatch => "🔄",
-                crate::sync::ConflictType::SecurityViolation => "🚨",
-            };
-            println!(
-                "  {} {}: Source='{}' vs Target='{}'",
-                conflict_icon,
-                conflict.variable,
-                if conflict.source_value.is_empty() {
-                    "(empty)"
-                } else {
-                    &conflict.source_value
-                },
-                if conflict.target_value.is_empty() {
-                    "(empty)"
-                } else {
-                    &conflict.target_value
-                }
-            );
-        }
-    }
-
-    // Display security violations
-    if !dry_run_result.security_violations.is_empty() {
-        println!("\n🚨 Security Violations:");
-        for violation in &dry_run_result.security_violations {
-            let severity_icon = match violation.severity {
-                crate::sync::SecuritySeverity::Critical => "🚨",
-                crate::sync::SecuritySeverity::High => "❌",
-                crate::sync::SecuritySeverity::Medium => "⚠️",
-                crate::sync::SecuritySeverity::Low => "ℹ️",
-            };
-            println!(
-                "  {} {}: {}",
-                severity_icon, violation.variable, violation.description
-            );
-        }
-    }
+    let mut report = SyncReport {
+        source,
+        target,
+        proposed_changes: dry_run_result.proposed_changes,
+        conflicts: dry_run_result.conflicts,
+        security_violations: dry_run_result.security_violations,
+        cancelled: false,
+        result: None,
+    };
 
-    if dry_run_result.proposed_changes.is_empty() && dry_run_result.conflicts.is_empty() {
-        println!("\n✅ No changes needed. Environments are already in sync.");
-        return Ok(());
+    if report.is_noop() {
+        return out.render(&report);
     }
 
-    // Confirmation prompt
+    // Confirmation prompt, after showing people what will change
     if !yes {
-        println!(
+        if out.is_text() {
+            report.print_plan(out);
+        }
+        out.info(format!(
             "\n🤔 Estimated sync duration: {:?}",
             dry_run_result.estimated_duration
-        );
-        println!("💡 A backup will be created before sync.");
-
-        print!("Proceed with synchronization? [y/N]: ");
-        use std::io::Write;
-        std::io::stdout().flush()?;
+        ));
+        out.info("💡 A backup will be created before sync.");
 
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input)?;
-
-        let input = input.trim().to_lowercase();
-        if input != "y" && input != "yes" {
-            println!("❌ Synchronization cancelled by user.");
-            return Ok(());
+        if !confirm("Proceed with synchronization?")? {
+            report.cancelled = true;
+            return if out.is_text() {
+                report.print_outcome(out);
+                Ok(())
+            } else {
+                out.render(&report)
+            };
         }
     }
 
-    println!("\n🚀 Starting synchronization...");
+    out.progress("🚀 Starting synchronization...");
 
     // Perform actual synchronization
     let sync_result = sync_engine
         .sync_environments(&source_path, &target_path)
         .await?;
 
-    // Display results
-    println!("✅ Synchronization completed successfully!");
-    println!("📊 Sync Results:");
-    println!(
-        "  - Variables synced: {}",
-        sync_result.synced_variables.len()
-    );
-    println!(
-        "  - Conflicts resolved: {}",
-        sync_result.conflicts_resolved.len()
-    );
-    println!("  - Duration: {:?}", sync_result.duration);
-    println!("  - Backup created: {}", sync_result.backup_created);
-
-    if !sync_result.synced_variables.is_empty() {
-        println!("\n🔄 Synced Variables:");
-        for variable in &sync_result.synced_variables {
-            println!("  - {}", variable);
-        }
-    }
-
-    println!("\n📝 Audit log updated: .env/sync-audit.json");
-    println!("💡 Run 'env status --verbose' to see detailed changes.");
+    report.result = Some(SyncOutcome {
+        synced_variables: sync_result.synced_variables,
+        conflicts_resolved: sync_result.conflicts_resolved.len(),
+        duration_ms: sync_result.duration.as_millis(),
+        backup_created: sync_result.backup_created,
+        audit_log,
+    });
 
-    Ok(())
+    if !yes && out.is_text() {
+        report.print_outcome(out);
+        Ok(())
+    } else {
+        out.render(&report)
+    }
 }
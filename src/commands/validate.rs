@@ -1,252 +1,655 @@
 //! Validate command implementation.
 
-use crate::config::{default_config_path, load_config};
-use crate::env::EnvManager;
+use super::{load_target, load_variables};
+use crate::cli::OutputFormat;
+use crate::config::{Config, Project, ScanConfig};
+use crate::env::{parser, EnvManager, LayerKind};
 use crate::error::{EnvCliError, Result};
+use crate::report::junit::{self, Outcome, TestCase, TestSuite};
+use crate::report::sarif::{self, Level};
+use crate::report::{Output, Report};
 use regex::Regex;
-// use std::fs;
-use std::path::PathBuf;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A check performed by `validate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ValidationCheck {
+    RequiredVariable,
+    FormatPattern,
+    InvalidPattern,
+    SchemaValue,
+    DeprecatedVariable,
+    Rule,
+    InsecureSecret,
+    SensitiveVariable,
+    EmptySecret,
+    UnusedVariable,
+    UsageScanFailed,
+    InlineConflict,
+}
+
+impl ValidationCheck {
+    pub(crate) const ALL: [ValidationCheck; 12] = [
+        ValidationCheck::RequiredVariable,
+        ValidationCheck::FormatPattern,
+        ValidationCheck::InvalidPattern,
+        ValidationCheck::SchemaValue,
+        ValidationCheck::DeprecatedVariable,
+        ValidationCheck::Rule,
+        ValidationCheck::InsecureSecret,
+        ValidationCheck::SensitiveVariable,
+        ValidationCheck::EmptySecret,
+        ValidationCheck::UnusedVariable,
+        ValidationCheck::UsageScanFailed,
+        ValidationCheck::InlineConflict,
+    ];
+
+    /// Stable identifier, used as the SARIF rule id.
+    pub(crate) fn id(self) -> &'static str {
+        match self {
+            ValidationCheck::RequiredVariable => "required-variable",
+            ValidationCheck::FormatPattern => "format-pattern",
+            ValidationCheck::InvalidPattern => "invalid-format-pattern",
+            ValidationCheck::SchemaValue => "schema-value",
+            ValidationCheck::DeprecatedVariable => "deprecated-variable",
+            ValidationCheck::Rule => "validation-rule",
+            ValidationCheck::InsecureSecret => "insecure-secret",
+            ValidationCheck::SensitiveVariable => "sensitive-variable",
+            ValidationCheck::EmptySecret => "empty-secret",
+            ValidationCheck::UnusedVariable => "unused-variable",
+            ValidationCheck::UsageScanFailed => "usage-scan-failed",
+            ValidationCheck::InlineConflict => "inline-conflict",
+        }
+    }
+
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            ValidationCheck::RequiredVariable => {
+                "A variable listed in validation.required or required by its schema is not set"
+            }
+            ValidationCheck::FormatPattern => {
+                "A value does not match its pattern in validation.formats"
+            }
+            ValidationCheck::InvalidPattern => "A pattern in validation.formats is not a valid regex",
+            ValidationCheck::SchemaValue => {
+                "A value does not fit the type declared in its [variables.NAME] schema"
+            }
+            ValidationCheck::DeprecatedVariable => "A variable marked as deprecated is still set",
+            ValidationCheck::Rule => "A cross-variable rule from [[rules]] does not hold",
+            ValidationCheck::InsecureSecret => {
+                "A sensitive variable holds a placeholder, or a value that is too short or too simple"
+            }
+            ValidationCheck::SensitiveVariable => {
+                "A sensitive variable is set; make sure its value is kept secret"
+            }
+            ValidationCheck::EmptySecret => "A password, secret or key variable is empty",
+            ValidationCheck::UnusedVariable => "A variable is not read anywhere in the code",
+            ValidationCheck::UsageScanFailed => "The code could not be scanned for unused variables",
+            ValidationCheck::InlineConflict => {
+                "A variable set inline in config.toml has a different value in the environment's file"
+            }
+        }
+    }
+
+    /// Whether a failure blocks validation or is only reported.
+    pub(crate) fn level(self) -> Level {
+        match self {
+            ValidationCheck::RequiredVariable
+            | ValidationCheck::FormatPattern
+            | ValidationCheck::InvalidPattern
+            | ValidationCheck::SchemaValue
+            | ValidationCheck::Rule
+            | ValidationCheck::InsecureSecret
+            | ValidationCheck::EmptySecret => Level::Error,
+            ValidationCheck::DeprecatedVariable
+            | ValidationCheck::SensitiveVariable
+            | ValidationCheck::UsageScanFailed
+            | ValidationCheck::InlineConflict => Level::Warning,
+            ValidationCheck::UnusedVariable => Level::Note,
+        }
+    }
+
+    fn is_security(self) -> bool {
+        matches!(
+            self,
+            ValidationCheck::InsecureSecret
+                | ValidationCheck::SensitiveVariable
+                | ValidationCheck::EmptySecret
+        )
+    }
+}
+
+/// A problem found by one of the checks.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ValidationIssue {
+    pub(crate) check: ValidationCheck,
+    /// Variable the problem is about, when there is a single one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) variable: Option<String>,
+    /// Name of the failed `[[rules]]` entry, for rule checks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) rule: Option<String>,
+    pub(crate) message: String,
+}
+
+impl ValidationIssue {
+    fn new(check: ValidationCheck, variable: Option<&str>, message: String) -> Self {
+        Self {
+            check,
+            variable: variable.map(str::to_string),
+            rule: None,
+            message,
+        }
+    }
+}
+
+impl Serialize for ValidationCheck {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
 
 /// Validation result information.
-#[derive(Debug)]
-struct ValidationResult {
-    required_passed: bool,
-    format_errors: Vec<String>,
-    security_warnings: Vec<String>,
-    security_errors: Vec<String>,
-    unused_variables: Vec<String>,
-    total_variables: usize,
+#[derive(Debug, Serialize)]
+pub(crate) struct ValidationResult {
+    pub(crate) required_passed: bool,
+    pub(crate) format_errors: Vec<ValidationIssue>,
+    pub(crate) security_warnings: Vec<ValidationIssue>,
+    pub(crate) security_errors: Vec<ValidationIssue>,
+    pub(crate) unused_variables: Vec<String>,
+    pub(crate) deprecation_warnings: Vec<ValidationIssue>,
+    /// Inline `config.toml` values that the environment's file overrides
+    pub(crate) inline_conflicts: Vec<ValidationIssue>,
+    pub(crate) rule_errors: Vec<ValidationIssue>,
+    pub(crate) rules_checked: usize,
+    pub(crate) total_variables: usize,
 }
 
-/// Validate environment configuration.
-pub async fn execute(env: String, check_unused: bool) -> Result<()> {
-    println!("Validating environment configuration for: {}", env);
-
-    // Check if we're in an env-cli project
-    let env_dir = PathBuf::from(".env");
-    if !env_dir.exists() {
-        return Err(EnvCliError::Config(
-            "Not an env-cli project. Run 'env init' first.".to_string(),
-        ));
+impl ValidationResult {
+    /// Every issue found, blocking ones first.
+    pub(crate) fn issues(&self) -> Vec<ValidationIssue> {
+        let unused = self.unused_variables.iter().map(|name| {
+            ValidationIssue::new(
+                ValidationCheck::UnusedVariable,
+                Some(name),
+                format!("Variable '{}' is not used in the code", name),
+            )
+        });
+
+        self.format_errors
+            .iter()
+            .chain(&self.rule_errors)
+            .chain(&self.security_errors)
+            .chain(&self.security_warnings)
+            .chain(&self.deprecation_warnings)
+            .chain(&self.inline_conflicts)
+            .cloned()
+            .chain(unused)
+            .collect()
+    }
+
+    /// Whether the environment passed every blocking check.
+    pub(crate) fn is_valid(&self) -> bool {
+        self.required_passed
+            && self.format_errors.is_empty()
+            && self.security_errors.is_empty()
+            && self.rule_errors.is_empty()
     }
+}
+
+/// The outcome of validating one environment.
+#[derive(Debug, Serialize)]
+struct EnvironmentReport {
+    name: String,
+    file: PathBuf,
+    valid: bool,
+    /// Why the environment could not be loaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// The checks' findings, when it could be
+    #[serde(flatten)]
+    result: Option<ValidationResult>,
+}
+
+/// Results of `env validate`.
+#[derive(Debug, Serialize)]
+pub struct ValidateReport {
+    valid: bool,
+    environments: Vec<EnvironmentReport>,
+    #[serde(skip)]
+    all: bool,
+    #[serde(skip)]
+    config: Config,
+}
+
+impl ValidateReport {
+    fn failed(&self) -> usize {
+        self.environments
+            .iter()
+            .filter(|report| !report.valid)
+            .count()
+    }
+}
 
-    // Load configuration
-    let config_path = default_config_path();
-    let config = load_config(&config_path)?;
+impl Report for ValidateReport {
+    const EXTRA_FORMATS: &'static [OutputFormat] = &[OutputFormat::Sarif, OutputFormat::Junit];
+
+    fn print_text(&self, out: &Output) {
+        for report in &self.environments {
+            match (&report.result, &report.error) {
+                (Some(result), _) => print_validation_results(&report.name, result, out),
+                (None, message) => out.line(format!(
+                    "\n✗ Could not validate '{}': {}",
+                    report.name,
+                    message.as_deref().unwrap_or_default()
+                )),
+            }
+        }
 
-    // Determine which environment to validate
-    let env_name = if env.is_empty() {
-        get_current_environment()?
+        if self.all {
+            match self.failed() {
+                0 => out.line(format!(
+                    "\n✓ All {} environments are valid",
+                    self.environments.len()
+                )),
+                failed => out.line(format!(
+                    "\n✗ {} of {} environments failed validation",
+                    failed,
+                    self.environments.len()
+                )),
+            }
+        }
+    }
+
+    fn render_extra(&self, format: OutputFormat) -> Result<String> {
+        if format == OutputFormat::Junit {
+            let suites: Vec<_> = self
+                .environments
+                .iter()
+                .map(|report| junit_suite(&self.config, report))
+                .collect();
+            return Ok(junit::render("env validate", &suites));
+        }
+        Ok(serde_json::to_string_pretty(&sarif_log(
+            &self.environments,
+            &self.config.root,
+        )?)?)
+    }
+}
+
+/// Validate environment configuration.
+///
+/// Validates the given environment, the current one, or with `all` every
+/// configured environment. Fails when any of them is invalid.
+pub async fn execute(
+    env: Option<String>,
+    all: bool,
+    check_unused: bool,
+    raw: bool,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    out.ensure_supported::<ValidateReport>()?;
+
+    let (config, env_names) = if all {
+        let config = project.load_config()?;
+        let names = config.environments.iter().map(|e| e.name.clone()).collect();
+        (config, names)
     } else {
-        env.clone()
+        let (config, env_name) = load_target(project, env)?;
+        (config, vec![env_name])
     };
 
-    // Validate environment exists
-    let target_env = config
-        .environments
-        .iter()
-        .find(|e| e.name == env_name)
-        .ok_or_else(|| {
-            EnvCliError::Environment(format!(
-                "Environment '{}' not found. Available environments: {}",
-                env_name,
-                config
-                    .environments
-                    .iter()
-                    .map(|e| e.name.as_str())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            ))
-        })?;
-
-    println!("Loading environment: {}", target_env.name);
-
-    // Load environment variables
-    let env_file = if let Some(file) = &target_env.file {
-        file.clone()
+    // The code is scanned once, however many environments are checked
+    let used_variables = if check_unused {
+        out.progress("Scanning for unused variables...");
+        Some(used_variables(project.root(), &config.scan).await)
     } else {
-        env_dir
-            .join("environments")
-            .join(format!("{}.env", env_name))
+        None
+    };
+
+    let mut environments = Vec::new();
+    for env_name in env_names {
+        out.progress(format!(
+            "Validating environment configuration for: {}",
+            env_name
+        ));
+        let result = validate_environment(
+            project,
+            &config,
+            &env_name,
+            raw,
+            used_variables.as_ref(),
+            out,
+        );
+
+        // A single environment that cannot be loaded is an ordinary error
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(e) if !all => return Err(e),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        environments.push(EnvironmentReport {
+            file: config.environment_file(&env_name),
+            name: env_name,
+            valid: result.as_ref().is_some_and(ValidationResult::is_valid),
+            error,
+            result,
+        });
+    }
+
+    let mut report = ValidateReport {
+        valid: false,
+        environments,
+        all,
+        config,
     };
+    let failed = report.failed();
+    report.valid = failed == 0;
+    out.render(&report)?;
 
-    if !env_file.exists() {
-        return Err(EnvCliError::FileSystem(format!(
-            "Environment file not found: {}",
-            env_file.display()
-        )));
+    // Return error if validation failed
+    match failed {
+        0 => Ok(()),
+        _ if all => Err(EnvCliError::Validation(format!(
+            "{} of {} environments failed validation",
+            failed,
+            report.environments.len()
+        ))),
+        _ => Err(EnvCliError::Validation(
+            "Environment validation failed".to_string(),
+        )),
     }
+}
 
-    let mut env_manager = EnvManager::new();
-    env_manager.load_from_file(&env_file)?;
+/// Load one environment and run every check against it.
+fn validate_environment(
+    project: &Project,
+    config: &crate::config::Config,
+    env_name: &str,
+    raw: bool,
+    used_variables: Option<&std::result::Result<HashSet<String>, String>>,
+    out: &Output,
+) -> Result<ValidationResult> {
+    out.progress(format!("Loading environment: {}", env_name));
+    let env_manager = load_variables(project, config, env_name, raw)?;
 
     // Perform validation
+    if !config.validation.required.is_empty() {
+        out.progress("Validating required variables...");
+    }
+    if !config.validation.formats.is_empty() || !config.variables.is_empty() {
+        out.progress("Checking variable formats...");
+    }
+    if !config.rules.is_empty() {
+        out.progress("Evaluating rules...");
+    }
+    out.progress("Verifying security constraints...");
+    let mut result = check_environment(config, env_name, &env_manager);
+
+    // Check for unused variables (if requested)
+    match used_variables {
+        Some(Ok(used)) => {
+            for (var_name, _) in env_manager.list() {
+                if !used.contains(var_name) {
+                    result.unused_variables.push(var_name.clone());
+                }
+            }
+        }
+        Some(Err(message)) => result.security_warnings.push(ValidationIssue::new(
+            ValidationCheck::UsageScanFailed,
+            None,
+            message.clone(),
+        )),
+        None => {}
+    }
+
+    Ok(result)
+}
+
+/// Names of the variables read anywhere in the code.
+async fn used_variables(
+    root: &Path,
+    scan: &ScanConfig,
+) -> std::result::Result<HashSet<String>, String> {
+    let scanner = crate::scan::CodeScanner::new()
+        .map_err(|e| format!("Could not initialize code scanner: {}", e))?
+        .with_exclusions(scan);
+    let usage_info = scanner
+        .scan_directory(&root.to_path_buf())
+        .await
+        .map_err(|e| format!("Could not scan for unused variables: {}", e))?;
+
+    Ok(usage_info.into_iter().map(|usage| usage.name).collect())
+}
+
+/// Run the required, format, schema and security checks against loaded variables.
+pub(crate) fn check_environment(
+    config: &crate::config::Config,
+    env_name: &str,
+    env_manager: &EnvManager,
+) -> ValidationResult {
     let mut result = ValidationResult {
         required_passed: true,
         format_errors: Vec::new(),
         security_warnings: Vec::new(),
         security_errors: Vec::new(),
         unused_variables: Vec::new(),
-        total_variables: 0,
+        deprecation_warnings: Vec::new(),
+        inline_conflicts: Vec::new(),
+        rule_errors: Vec::new(),
+        rules_checked: 0,
+        total_variables: env_manager.list().count(),
     };
 
-    // Count total variables
-    result.total_variables = env_manager.list().count();
-
     // Validate required variables
-    if !config.validation.required.is_empty() {
-        println!("Validating required variables...");
-        let missing = env_manager.validate(&config.validation.required);
-        if !missing.is_empty() {
-            result.required_passed = false;
-            for var in &missing {
-                result
-                    .format_errors
-                    .push(format!("Missing required variable: {}", var));
-            }
+    let missing = env_manager.validate(&config.validation.required);
+    if !missing.is_empty() {
+        result.required_passed = false;
+        for var in &missing {
+            result.format_errors.push(ValidationIssue::new(
+                ValidationCheck::RequiredVariable,
+                Some(var),
+                format!("Missing required variable: {}", var),
+            ));
         }
     }
 
     // Validate variable formats
-    if !config.validation.formats.is_empty() {
-        println!("Checking variable formats...");
-        for (var_name, pattern) in &config.validation.formats {
-            if let Some(value) = env_manager.get(var_name) {
-                if let Ok(regex) = Regex::new(pattern) {
-                    if !regex.is_match(value) {
-                        result.format_errors.push(format!(
+    for (var_name, pattern) in &config.validation.formats {
+        if let Some(value) = env_manager.get(var_name) {
+            if let Ok(regex) = Regex::new(pattern) {
+                if !regex.is_match(value) {
+                    result.format_errors.push(ValidationIssue::new(
+                        ValidationCheck::FormatPattern,
+                        Some(var_name),
+                        format!(
                             "Variable '{}' has invalid format. Expected pattern: {}",
                             var_name, pattern
-                        ));
-                    }
-                } else {
-                    result.format_errors.push(format!(
-                        "Invalid regex pattern for variable '{}': {}",
-                        var_name, pattern
+                        ),
                     ));
                 }
+            } else {
+                result.format_errors.push(ValidationIssue::new(
+                    ValidationCheck::InvalidPattern,
+                    Some(var_name),
+                    format!(
+                        "Invalid regex pattern for variable '{}': {}",
+                        var_name, pattern
+                    ),
+                ));
             }
         }
     }
 
-    // Security validation
-    println!("Verifying security constraints...");
-    validate_security(&env_manager, &config.validation.security, &mut result)?;
+    // Validate against the variable schema
+    validate_schema(config, env_name, env_manager, &mut result);
 
-    // Check for unused variables (if requested)
-    if check_unused {
-        println!("Scanning for unused variables...");
-        match crate::scan::CodeScanner::new() {
-            Ok(scanner) => match scanner.scan_directory(&PathBuf::from(".")).await {
-                Ok(usage_info) => {
-                    let used_vars: std::collections::HashSet<String> =
-                        usage_info.into_iter().map(|usage| usage.name).collect();
-
-                    for (var_name, _) in env_manager.list() {
-                        if !used_vars.contains(var_name) {
-                            result.unused_variables.push(var_name.clone());
-                        }
-                    }
-                }
-                Err(e) => {
-                    result
-                        .security_warnings
-                        .push(format!("Could not scan for unused variables: {}", e));
-                }
-            },
-            Err(e) => {
-                result
-                    .security_warnings
-                    .push(format!("Could not initialize code scanner: {}", e));
-            }
-        }
-    }
+    // Evaluate cross-variable rules
+    validate_rules(config, env_manager, &mut result);
 
-    // Print results
-    print_validation_results(&env_name, &result);
+    // Security validation
+    validate_security(env_manager, config, &mut result);
 
-    // Return error if validation failed
-    if !result.required_passed
-        || !result.format_errors.is_empty()
-        || !result.security_errors.is_empty()
-    {
-        return Err(EnvCliError::Validation(
-            "Environment validation failed".to_string(),
-        ));
-    }
+    // Inline values shadowed by the environment's file
+    validate_inline_values(env_name, env_manager, &mut result);
 
-    Ok(())
+    result
 }
 
-/// Get the current environment name.
-fn get_current_environment() -> Result<String> {
-    let current_path = PathBuf::from(".env/.current");
-
-    if !current_path.exists() {
-        return Err(EnvCliError::Environment(
-            "No current environment set. Use 'env switch <environment>' first.".to_string(),
-        ));
-    }
-
-    #[cfg(unix)]
-    {
-        let target = std::fs::read_link(&current_path)?;
-
-        // Extract environment name from path like "environments/development.env"
-        if let Some(file_name) = target
-            .file_name()
-            .and_then(|n: &std::ffi::OsStr| n.to_str())
-        {
-            if let Some(env_name) = file_name.strip_suffix(".env") {
-                return Ok(env_name.to_string());
+/// Warn about inline `config.toml` values that the environment's own file,
+/// or its `.local` override, sets to something else.
+fn validate_inline_values(env_name: &str, env_manager: &EnvManager, result: &mut ValidationResult) {
+    let mut keys: Vec<&String> = env_manager.list().map(|(key, _)| key).collect();
+    keys.sort();
+
+    for key in keys {
+        let history = env_manager.history(key);
+        let own = || {
+            history
+                .iter()
+                .filter(|(layer, _)| layer.environment == env_name)
+        };
+        let inline = own().find(|(layer, _)| layer.kind == LayerKind::Inline);
+        let file = own()
+            .filter(|(layer, _)| layer.kind != LayerKind::Inline)
+            .last();
+
+        if let (Some((_, inline_value)), Some((layer, value))) = (inline, file) {
+            if inline_value != value {
+                result.inline_conflicts.push(ValidationIssue::new(
+                    ValidationCheck::InlineConflict,
+                    Some(key),
+                    format!(
+                        "Variable '{}' is set inline in config.toml and to a different value in {}, which takes precedence",
+                        key,
+                        layer.file.display()
+                    ),
+                ));
             }
         }
     }
+}
 
-    #[cfg(windows)]
-    {
-        let target = std::fs::read_link(&current_path)?;
-
-        // Extract environment name from path like "environments\development.env"
-        if let Some(file_name) = target
-            .file_name()
-            .and_then(|n: &std::ffi::OsStr| n.to_str())
-        {
-            if let Some(env_name) = file_name.strip_suffix(".env") {
-                return Ok(env_name.to_string());
+/// Validate variables against their `[variables.NAME]` schema entries.
+fn validate_schema(
+    config: &crate::config::Config,
+    env_name: &str,
+    env_manager: &EnvManager,
+    result: &mut ValidationResult,
+) {
+    for (var_name, spec) in &config.variables {
+        match env_manager.get(var_name) {
+            Some(value) => {
+                if let Some(note) = &spec.deprecated {
+                    result.deprecation_warnings.push(ValidationIssue::new(
+                        ValidationCheck::DeprecatedVariable,
+                        Some(var_name),
+                        format!("Variable '{}' is deprecated: {}", var_name, note),
+                    ));
+                }
+                if let Err(expected) = spec.check(value) {
+                    result.format_errors.push(ValidationIssue::new(
+                        ValidationCheck::SchemaValue,
+                        Some(var_name),
+                        format!("Variable '{}' has an invalid value: {}", var_name, expected),
+                    ));
+                }
+            }
+            None => {
+                let already_reported = config.validation.required.contains(var_name);
+                if spec.is_required_in(env_name) && spec.default.is_none() && !already_reported {
+                    result.required_passed = false;
+                    result.format_errors.push(ValidationIssue::new(
+                        ValidationCheck::RequiredVariable,
+                        Some(var_name),
+                        format!("Missing required variable: {}", var_name),
+                    ));
+                }
             }
         }
     }
+}
 
-    Err(EnvCliError::Environment(
-        "Unable to determine current environment".to_string(),
-    ))
+/// Evaluate the `[[rules]]` declared in the configuration.
+fn validate_rules(
+    config: &crate::config::Config,
+    env_manager: &EnvManager,
+    result: &mut ValidationResult,
+) {
+    let variables: std::collections::HashMap<String, String> = env_manager
+        .list()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+
+    for rule in &config.rules {
+        result.rules_checked += 1;
+        if let Err(failure) = rule.evaluate(&variables) {
+            // Point at the first variable the rule reads that is set
+            let variable = failure
+                .variables
+                .iter()
+                .find(|name| variables.contains_key(*name))
+                .cloned();
+            result.rule_errors.push(ValidationIssue {
+                check: ValidationCheck::Rule,
+                variable,
+                rule: Some(failure.rule.clone()),
+                message: failure.to_string(),
+            });
+        }
+    }
 }
 
 /// Validate security constraints.
 fn validate_security(
     env_manager: &EnvManager,
-    security_config: &crate::config::SecurityConfig,
+    config: &crate::config::Config,
     result: &mut ValidationResult,
-) -> Result<()> {
+) {
+    let security_config = &config.validation.security;
+
     for (var_name, var_value) in env_manager.list() {
-        // Check for sensitive patterns
-        for pattern in &security_config.sensitive_patterns {
-            if let Ok(regex) = Regex::new(pattern) {
-                if regex.is_match(var_name) {
-                    // Check if value looks like a placeholder or is too short
-                    if is_insecure_value(var_value, security_config) {
-                        result.security_errors.push(format!(
-                            "Sensitive variable '{}' has an insecure value",
-                            var_name
-                        ));
-                    } else {
-                        result.security_warnings.push(format!(
-                            "Sensitive variable '{}' detected, ensure value is properly secured",
-                            var_name
-                        ));
-                    }
-                }
+        // Check for sensitive patterns and variables declared as secrets
+        let declared_secret = config
+            .variables
+            .get(var_name)
+            .map_or(false, |spec| spec.secret);
+        let matches_pattern = security_config
+            .sensitive_patterns
+            .iter()
+            .filter_map(|pattern| Regex::new(pattern).ok())
+            .any(|regex| regex.is_match(var_name));
+
+        if declared_secret || matches_pattern {
+            // Check if value looks like a placeholder or is too short
+            if is_insecure_value(var_value, security_config) {
+                result.security_errors.push(ValidationIssue::new(
+                    ValidationCheck::InsecureSecret,
+                    Some(var_name),
+                    format!("Sensitive variable '{}' has an insecure value", var_name),
+                ));
+            } else {
+                result.security_warnings.push(ValidationIssue::new(
+                    ValidationCheck::SensitiveVariable,
+                    Some(var_name),
+                    format!(
+                        "Sensitive variable '{}' detected, ensure value is properly secured",
+                        var_name
+                    ),
+                ));
             }
         }
 
@@ -256,38 +659,64 @@ fn validate_security(
             || var_name.to_lowercase().contains("key")
         {
             if var_value.is_empty() {
-                result
-                    .security_errors
-                    .push(format!("Secret variable '{}' cannot be empty", var_name));
+                result.security_errors.push(ValidationIssue::new(
+                    ValidationCheck::EmptySecret,
+                    Some(var_name),
+                    format!("Secret variable '{}' cannot be empty", var_name),
+                ));
             }
         }
     }
-
-    Ok(())
 }
 
-/// Check if a value is insecure for a sensitive variable.
-fn is_insecure_value(value: &str, security_config: &crate::config::SecurityConfig) -> bool {
-    // Check for placeholder values
-    let placeholders = [
-        "change_me",
-        "placeholder",
-        "your_api_key",
-        "your_secret",
-        "change_this",
-        "todo",
-        "xxx",
-        "111111",
-        "123456",
-    ];
-
+/// Values that are obviously placeholders rather than real configuration.
+const PLACEHOLDER_VALUES: &[&str] = &[
+    "change_me",
+    "placeholder",
+    "your_api_key",
+    "your_secret",
+    "change_this",
+    "todo",
+    "xxx",
+    "111111",
+    "123456",
+];
+
+/// Check if a value looks like a placeholder.
+pub(crate) fn is_placeholder(value: &str) -> bool {
     let lower_value = value.to_lowercase();
-    for placeholder in &placeholders {
-        if lower_value.contains(placeholder) {
+    PLACEHOLDER_VALUES
+        .iter()
+        .any(|placeholder| lower_value.contains(placeholder))
+}
+
+/// Check if a variable is a secret, by schema, configured pattern or keyword.
+pub(crate) fn is_sensitive_key(var_name: &str, config: &crate::config::Config) -> bool {
+    if let Some(spec) = config.variables.get(var_name) {
+        if spec.secret {
             return true;
         }
     }
 
+    let lower_name = var_name.to_lowercase();
+    config
+        .validation
+        .security
+        .sensitive_patterns
+        .iter()
+        .filter_map(|pattern| Regex::new(pattern).ok())
+        .any(|regex| regex.is_match(var_name))
+        || ["password", "secret", "key", "token"]
+            .iter()
+            .any(|kw| lower_name.contains(kw))
+}
+
+/// Check if a value is insecure for a sensitive variable.
+fn is_insecure_value(value: &str, security_config: &crate::config::SecurityConfig) -> bool {
+    if is_placeholder(value) {
+        return true;
+    }
+
     // Check minimum length
     if let Some(min_length) = security_config.min_secret_length {
         if value.len() < min_length {
@@ -308,76 +737,278 @@ fn is_insecure_value(value: &str, security_config: &crate::config::SecurityConfi
     false
 }
 
+/// Build a SARIF log of the issues, located in the environment files.
+fn sarif_log(reports: &[EnvironmentReport], root: &Path) -> Result<sarif::Log> {
+    let rules = ValidationCheck::ALL
+        .iter()
+        .map(|check| {
+            let rule = sarif::Rule::new(check.id(), check.description(), check.level())
+                .with_tag("configuration");
+            if check.is_security() {
+                rule.with_tag("security")
+                    .with_security_severity(match check.level() {
+                        Level::Error => 7.0,
+                        Level::Warning => 4.0,
+                        Level::Note => 1.0,
+                    })
+            } else {
+                rule
+            }
+        })
+        .collect();
+
+    let mut log = sarif::Log::new(rules);
+    for report in reports {
+        let Some(result) = &report.result else {
+            continue;
+        };
+        let uri = sarif::uri(&report.file, root);
+        let positions = variable_positions(&std::fs::read_to_string(&report.file)?);
+
+        for issue in result.issues() {
+            let location = match issue.variable.as_ref().and_then(|v| positions.get(v)) {
+                Some(&(line, column, end_column)) => {
+                    sarif::Location::file(&uri).with_region(line, column, Some(end_column))
+                }
+                None => sarif::Location::file(&uri),
+            };
+            log.add_result(issue.check.id(), &issue.message, Some(location), None);
+        }
+    }
+    Ok(log)
+}
+
+/// Build the JUnit test suite of one environment.
+///
+/// Each required variable, each variable with a format or schema, and each
+/// rule is a test case; the security checks together form one more. Warnings
+/// are kept as the suite's output.
+fn junit_suite(config: &Config, report: &EnvironmentReport) -> TestSuite {
+    let env_name = &report.name;
+    let result = match &report.result {
+        Some(result) => result,
+        None => {
+            let message = report.error.clone().unwrap_or_default();
+            return TestSuite {
+                name: env_name.clone(),
+                cases: vec![TestCase {
+                    classname: format!("{}.load", env_name),
+                    name: "load".to_string(),
+                    outcome: Outcome::Error { message },
+                }],
+                system_out: Vec::new(),
+            };
+        }
+    };
+
+    let issues = result.issues();
+    let case = |group: &str, name: &str, failed: &dyn Fn(&ValidationIssue) -> bool| {
+        let failures: Vec<&ValidationIssue> = issues
+            .iter()
+            .filter(|issue| issue.check.level() == Level::Error && failed(issue))
+            .collect();
+        TestCase {
+            classname: format!("{}.{}", env_name, group),
+            name: name.to_string(),
+            outcome: match failures.first() {
+                None => Outcome::Passed,
+                Some(first) => Outcome::Failed {
+                    kind: first.check.id().to_string(),
+                    messages: failures.iter().map(|issue| issue.message.clone()).collect(),
+                },
+            },
+        }
+    };
+    let about = |issue: &ValidationIssue, name: &str| issue.variable.as_deref() == Some(name);
+
+    let mut required: Vec<&str> = config
+        .validation
+        .required
+        .iter()
+        .map(String::as_str)
+        .collect();
+    for (name, spec) in &config.variables {
+        if spec.is_required_in(env_name)
+            && spec.default.is_none()
+            && !required.contains(&name.as_str())
+        {
+            required.push(name);
+        }
+    }
+
+    let mut formatted: Vec<&str> = config
+        .validation
+        .formats
+        .keys()
+        .chain(config.variables.keys())
+        .map(String::as_str)
+        .collect();
+    formatted.sort_unstable();
+    formatted.dedup();
+
+    let mut cases = Vec::new();
+    for name in required {
+        cases.push(case("required", name, &|issue| {
+            issue.check == ValidationCheck::RequiredVariable && about(issue, name)
+        }));
+    }
+    for name in formatted {
+        cases.push(case("format", name, &|issue| {
+            matches!(
+                issue.check,
+                ValidationCheck::FormatPattern
+                    | ValidationCheck::InvalidPattern
+                    | ValidationCheck::SchemaValue
+            ) && about(issue, name)
+        }));
+    }
+    cases.push(case("security", "security", &|issue| {
+        issue.check.is_security()
+    }));
+    for rule in &config.rules {
+        cases.push(case("rule", &rule.name, &|issue| {
+            issue.rule.as_deref() == Some(rule.name.as_str())
+        }));
+    }
+
+    TestSuite {
+        name: env_name.clone(),
+        cases,
+        system_out: issues
+            .iter()
+            .filter(|issue| issue.check.level() != Level::Error)
+            .map(|issue| issue.message.clone())
+            .collect(),
+    }
+}
+
+/// Line and column span of the key of each assignment.
+///
+/// When a key is assigned twice the later assignment, which wins, is used.
+fn variable_positions(content: &str) -> HashMap<String, (usize, usize, usize)> {
+    let body = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let lines: Vec<&str> = body.lines().collect();
+
+    parser::parse(body)
+        .map(|entries| {
+            entries
+                .into_iter()
+                .filter_map(|entry| {
+                    let line = lines.get(entry.line - 1)?;
+                    let start = line.find(&entry.key)?;
+                    let column = line[..start].chars().count() + 1;
+                    let end_column = column + entry.key.chars().count();
+                    Some((entry.key, (entry.line, column, end_column)))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Print validation results in a user-friendly format.
-fn print_validation_results(env_name: &str, result: &ValidationResult) {
-    println!("\nValidation Results for '{}':", env_name);
-    println!("  Total variables: {}", result.total_variables);
+fn print_validation_results(env_name: &str, result: &ValidationResult, out: &Output) {
+    out.line(format!("\nValidation Results for '{}':", env_name));
+    out.line(format!("  Total variables: {}", result.total_variables));
 
     // Required variables
     if result.required_passed {
-        println!("  ✓ Required variables: All present");
+        out.line("  ✓ Required variables: All present");
     } else {
-        println!("  ✗ Required variables: Missing");
+        out.line("  ✗ Required variables: Missing");
     }
 
     // Format validation
     if result.format_errors.is_empty() {
-        println!("  ✓ Variable formats: All valid");
+        out.line("  ✓ Variable formats: All valid");
     } else {
-        println!(
+        out.line(format!(
             "  ✗ Variable formats: {} errors",
             result.format_errors.len()
-        );
+        ));
         for error in &result.format_errors {
-            println!("    - {}", error);
+            out.line(format!("    - {}", error));
+        }
+    }
+
+    // Cross-variable rules
+    if result.rules_checked > 0 {
+        if result.rule_errors.is_empty() {
+            out.line(format!("  ✓ Rules: All {} passed", result.rules_checked));
+        } else {
+            out.line(format!(
+                "  ✗ Rules: {} of {} failed",
+                result.rule_errors.len(),
+                result.rules_checked
+            ));
+            for error in &result.rule_errors {
+                out.line(format!("    - {}", error));
+            }
         }
     }
 
     // Security validation
     if result.security_errors.is_empty() && result.security_warnings.is_empty() {
-        println!("  ✓ Security check: Passed");
+        out.line("  ✓ Security check: Passed");
     } else {
         if !result.security_errors.is_empty() {
-            println!(
+            out.line(format!(
                 "  ✗ Security check: {} errors",
                 result.security_errors.len()
-            );
+            ));
             for error in &result.security_errors {
-                println!("    - {}", error);
+                out.line(format!("    - {}", error));
             }
         }
 
         if !result.security_warnings.is_empty() {
-            println!(
+            out.line(format!(
                 "  ⚠ Security warnings: {} warnings",
                 result.security_warnings.len()
-            );
+            ));
             for warning in &result.security_warnings {
-                println!("    - {}", warning);
+                out.line(format!("    - {}", warning));
             }
         }
     }
 
+    // Deprecated variables
+    if !result.deprecation_warnings.is_empty() {
+        out.line(format!(
+            "  ⚠ Deprecated variables: {} found",
+            result.deprecation_warnings.len()
+        ));
+        for warning in &result.deprecation_warnings {
+            out.line(format!("    - {}", warning));
+        }
+    }
+
+    // Inline values overridden by the file
+    if !result.inline_conflicts.is_empty() {
+        out.line(format!(
+            "  ⚠ Inline conflicts: {} found",
+            result.inline_conflicts.len()
+        ));
+        for warning in &result.inline_conflicts {
+            out.line(format!("    - {}", warning));
+        }
+    }
+
     // Unused variables
     if !result.unused_variables.is_empty() {
-        println!(
+        out.line(format!(
             "  ⚠ Unused variables: {} found",
             result.unused_variables.len()
-        );
+        ));
         for var in &result.unused_variables {
-            println!("    - {} (consider removing if not needed)", var);
+            out.line(format!("    - {} (consider removing if not needed)", var));
         }
     }
 
     // Overall status
-    let is_valid = result.required_passed
-        && result.format_errors.is_empty()
-        && result.security_errors.is_empty();
-
-    if is_valid {
-        println!("\n✓ Environment '{}' is valid!", env_name);
+    if result.is_valid() {
+        out.line(format!("\n✓ Environment '{}' is valid!", env_name));
     } else {
-        println!("\n✗ Environment '{}' validation failed!", env_name);
-        println!("Fix the errors above and run 'env validate' again.");
+        out.line(format!("\n✗ Environment '{}' validation failed!", env_name));
+        out.info("Fix the errors above and run 'env validate' again.");
     }
 }
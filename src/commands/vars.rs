@@ -0,0 +1,261 @@
+//! Variable commands: `get`, `set`, `unset` and `list`.
+//!
+//! Each command targets the current environment unless `--env` names another
+//! one. Edits go through [`EnvDocument`] so the rest of the file is untouched,
+//! and a backup is written to `.env/backups` before every change. Values set
+//! in an encrypted file are encrypted before they are written. With
+//! `--inline`, `set` and `unset` edit the environment's `variables` table in
+//! `config.toml` instead.
+
+use super::status::mask_sensitive_value;
+use super::validate::is_sensitive_key;
+use super::{backup_environment, load_target, load_variables};
+use crate::config::{Config, Project};
+use crate::crypto::{self, Keyring};
+use crate::env::{parser, EnvDocument};
+use crate::error::{EnvCliError, Result};
+use crate::report::{Output, Report};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// The value of one variable.
+#[derive(Debug, Serialize)]
+struct VariableValue {
+    key: String,
+    value: String,
+    environment: String,
+}
+
+impl Report for VariableValue {
+    fn print_text(&self, _out: &Output) {
+        // The value is the payload, printed as is even with `--no-emoji`
+        println!("{}", self.value);
+    }
+}
+
+/// Print the value of a single variable.
+pub async fn get(
+    key: String,
+    env: Option<String>,
+    raw: bool,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    out.ensure_supported::<VariableValue>()?;
+    let (config, env_name) = load_target(project, env)?;
+    let env_manager = load_variables(project, &config, &env_name, raw)?;
+
+    match env_manager.get(&key) {
+        Some(value) => out.render(&VariableValue {
+            value: value.clone(),
+            key,
+            environment: env_name,
+        }),
+        None => Err(EnvCliError::Environment(format!(
+            "Variable '{}' is not set in environment '{}'",
+            key, env_name
+        ))),
+    }
+}
+
+/// Set a variable, creating it if needed.
+pub async fn set(
+    key: String,
+    value: String,
+    env: Option<String>,
+    inline: bool,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    if !parser::is_valid_env_key(&key) {
+        return Err(EnvCliError::InvalidArgument(format!(
+            "Invalid environment variable key: '{}'",
+            key
+        )));
+    }
+
+    let (config, env_name) = load_target(project, env)?;
+    if inline {
+        return edit_inline(project, &config, &env_name, &key, Some(value), out);
+    }
+    let env_file = config.environment_file(&env_name);
+    let mut document = EnvDocument::load_or_default(&env_file)?;
+
+    let current = match document.get(&key) {
+        Some(sealed) if crypto::is_encrypted(sealed) => {
+            Some(Keyring::load(project)?.decrypt_value(&key, sealed)?)
+        }
+        other => other.map(str::to_string),
+    };
+    if current.as_deref() == Some(value.as_str()) {
+        out.info(format!(
+            "'{}' is already set to that value in '{}'",
+            key, env_name
+        ));
+        return Ok(());
+    }
+
+    if let Some(backup) = backup_environment(project, &env_name, &env_file)? {
+        out.info(format!("✓ Created backup: {}", backup.display()));
+    }
+
+    let stored = crypto::seal_for_document(project, &document, &key, &value)?;
+    let previous = document.set(&key, &stored);
+    document.save(&env_file)?;
+
+    if previous.is_some() {
+        out.info(format!("✓ Updated {} in '{}'", key, env_name));
+    } else {
+        out.info(format!("✓ Added {} to '{}'", key, env_name));
+    }
+
+    Ok(())
+}
+
+/// Remove a variable.
+pub async fn unset(
+    key: String,
+    env: Option<String>,
+    inline: bool,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    let (config, env_name) = load_target(project, env)?;
+    if inline {
+        return edit_inline(project, &config, &env_name, &key, None, out);
+    }
+    let env_file = config.environment_file(&env_name);
+    let mut document = EnvDocument::load_or_default(&env_file)?;
+
+    if !document.contains_key(&key) {
+        return Err(EnvCliError::Environment(format!(
+            "Variable '{}' is not set in environment '{}'",
+            key, env_name
+        )));
+    }
+
+    if let Some(backup) = backup_environment(project, &env_name, &env_file)? {
+        out.info(format!("✓ Created backup: {}", backup.display()));
+    }
+
+    document.remove(&key);
+    document.save(&env_file)?;
+    out.info(format!("✓ Removed {} from '{}'", key, env_name));
+
+    Ok(())
+}
+
+/// Set, or with `None` remove, a value in an environment's inline
+/// `variables` table.
+fn edit_inline(
+    project: &Project,
+    config: &Config,
+    env_name: &str,
+    key: &str,
+    value: Option<String>,
+    out: &Output,
+) -> Result<()> {
+    let sensitive = is_sensitive_key(key, config);
+    let env_file = config.environment_file(env_name);
+    // Edit the project file alone, so user settings are not written into it
+    let mut config = project.load_own_config()?;
+    let environment = config.environment_mut(env_name)?;
+
+    match value {
+        Some(value) => {
+            if environment.variables.get(key) == Some(&value) {
+                out.info(format!(
+                    "'{}' is already set to that value inline for '{}'",
+                    key, env_name
+                ));
+                return Ok(());
+            }
+            if sensitive {
+                out.warn(format!(
+                    "⚠️  '{}' looks like a secret; inline values in config.toml are not encrypted",
+                    key
+                ));
+            }
+            let previous = environment.variables.insert(key.to_string(), value);
+            project.save_config(&config)?;
+            if previous.is_some() {
+                out.info(format!("✓ Updated {} inline for '{}'", key, env_name));
+            } else {
+                out.info(format!("✓ Added {} inline for '{}'", key, env_name));
+            }
+
+            let shadowed = EnvDocument::load_or_default(&env_file)?.contains_key(key);
+            if shadowed {
+                out.warn(format!(
+                    "⚠️  {} also sets '{}', and takes precedence over the inline value",
+                    env_file.display(),
+                    key
+                ));
+            }
+        }
+        None => {
+            if environment.variables.remove(key).is_none() {
+                return Err(EnvCliError::Environment(format!(
+                    "Variable '{}' is not set inline for environment '{}'",
+                    key, env_name
+                )));
+            }
+            project.save_config(&config)?;
+            out.info(format!("✓ Removed inline {} from '{}'", key, env_name));
+        }
+    }
+
+    Ok(())
+}
+
+/// The variables of one environment.
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+struct VariableList {
+    #[serde(skip)]
+    environment: String,
+    variables: BTreeMap<String, String>,
+}
+
+impl Report for VariableList {
+    fn print_text(&self, out: &Output) {
+        out.line(format!(
+            "Variables in '{}' ({}):",
+            self.environment,
+            self.variables.len()
+        ));
+        for (key, value) in &self.variables {
+            out.line(format!("  {}={}", key, value));
+        }
+    }
+}
+
+/// List the variables of an environment, masking secrets by default.
+pub async fn list(
+    env: Option<String>,
+    show_secrets: bool,
+    raw: bool,
+    project: &Project,
+    out: &Output,
+) -> Result<()> {
+    out.ensure_supported::<VariableList>()?;
+    let (config, env_name) = load_target(project, env)?;
+    let env_manager = load_variables(project, &config, &env_name, raw)?;
+
+    let variables: BTreeMap<String, String> = env_manager
+        .list()
+        .map(|(key, value)| {
+            let shown = if show_secrets {
+                value.clone()
+            } else {
+                mask_sensitive_value(key, value)
+            };
+            (key.clone(), shown)
+        })
+        .collect();
+
+    out.render(&VariableList {
+        environment: env_name,
+        variables,
+    })
+}
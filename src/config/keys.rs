@@ -0,0 +1,268 @@
+//! Known configuration keys.
+//!
+//! Deserializing ignores keys [`Config`](super::Config) does not have, so a
+//! misspelt setting would silently have no effect. [`unknown_keys`] walks a
+//! configuration table against the shape of `Config` instead, and suggests
+//! the closest known key for each one it does not recognize.
+
+use std::fmt;
+
+/// What a key holds.
+enum Shape {
+    /// A setting, whatever its value
+    Value,
+    /// A table with these keys
+    Table(&'static [(&'static str, Shape)]),
+    /// A table keyed by names, such as variables, with values of this shape
+    Map(&'static Shape),
+    /// An array of tables of this shape
+    Tables(&'static Shape),
+}
+
+use Shape::{Map, Table, Tables, Value};
+
+const CONFIG: &[(&str, Shape)] = &[
+    ("version", Value),
+    ("project", Value),
+    ("default_environment", Value),
+    ("environments", Tables(&ENVIRONMENT)),
+    ("scan", SCAN),
+    ("validation", VALIDATION),
+    ("variables", Map(&VARIABLE)),
+    ("rules", Tables(&RULE)),
+    ("recipients", Tables(&RECIPIENT)),
+    ("preferences", PREFERENCES),
+];
+
+const ENVIRONMENT: Shape = Table(&[
+    ("name", Value),
+    ("description", Value),
+    ("file", Value),
+    ("extends", Value),
+    ("variables", Map(&Value)),
+]);
+
+const SCAN: Shape = Table(&[
+    ("include_dirs", Value),
+    ("exclude_dirs", Value),
+    ("include_patterns", Value),
+    ("exclude_patterns", Value),
+]);
+
+const VALIDATION: Shape = Table(&[
+    ("required", Value),
+    ("formats", Map(&Value)),
+    (
+        "security",
+        Table(&[
+            ("sensitive_patterns", Value),
+            ("min_secret_length", Value),
+            ("require_special_chars", Value),
+        ]),
+    ),
+]);
+
+const VARIABLE: Shape = Table(&[
+    ("type", Value),
+    ("default", Value),
+    ("description", Value),
+    ("example", Value),
+    ("secret", Value),
+    ("deprecated", Value),
+    ("required", Value),
+    ("required_in", Value),
+    ("values", Value),
+]);
+
+const RULE: Shape = Table(&[
+    ("name", Value),
+    ("when", Value),
+    ("check", Value),
+    ("message", Value),
+]);
+
+const RECIPIENT: Shape = Table(&[("name", Value), ("key", Value)]);
+
+const PREFERENCES: Shape = Table(&[("format", Value), ("color", Value), ("editor", Value)]);
+
+/// A key no setting has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKey {
+    /// Dotted key, with `[index]` for entries of arrays of tables
+    pub key: String,
+    /// The closest known key at the same level
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown key `{}`", self.key)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "; did you mean `{}`?", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// The keys in a configuration table that no setting has.
+pub fn unknown_keys(table: &toml::Table) -> Vec<UnknownKey> {
+    let mut unknown = Vec::new();
+    check_table("", table, CONFIG, &mut unknown);
+    unknown
+}
+
+fn check_table(
+    prefix: &str,
+    table: &toml::Table,
+    keys: &[(&str, Shape)],
+    unknown: &mut Vec<UnknownKey>,
+) {
+    for (name, value) in table {
+        match keys.iter().find(|(key, _)| key == name) {
+            Some((_, shape)) => check(&join(prefix, name), value, shape, unknown),
+            None => unknown.push(UnknownKey {
+                key: join(prefix, name),
+                suggestion: closest(name, keys.iter().map(|(key, _)| *key))
+                    .map(|key| join(prefix, key)),
+            }),
+        }
+    }
+}
+
+fn check(key: &str, value: &toml::Value, shape: &Shape, unknown: &mut Vec<UnknownKey>) {
+    match (shape, value) {
+        (Table(keys), toml::Value::Table(table)) => check_table(key, table, keys, unknown),
+        (Map(shape), toml::Value::Table(table)) => {
+            for (name, value) in table {
+                check(&join(key, name), value, shape, unknown);
+            }
+        }
+        (Tables(shape), toml::Value::Array(entries)) => {
+            for (index, entry) in entries.iter().enumerate() {
+                check(&format!("{}[{}]", key, index), entry, shape, unknown);
+            }
+        }
+        // Values of the wrong type are reported by deserialization
+        _ => {}
+    }
+}
+
+/// The candidate within a few edits of `name`, if any.
+fn closest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .map(|candidate| (strsim::levenshtein(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Append `name` to a dotted key, quoting it unless it is a bare TOML key.
+pub fn join(prefix: &str, name: &str) -> String {
+    let bare = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    let name = if bare {
+        name.to_string()
+    } else {
+        format!("{:?}", name)
+    };
+    if prefix.is_empty() {
+        name
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::OutputFormat;
+    use crate::config::{Config, Environment, Preferences, Recipient, Rule, VariableSpec};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_unknown_keys() {
+        let table: toml::Table = toml::from_str(
+            r#"
+            project = "app"
+            colour = true
+
+            [[environments]]
+            name = "dev"
+            descripton = "Development"
+
+            [environments.variables]
+            ANYTHING_GOES = "1"
+
+            [scan]
+            exlude_dirs = ["fixtures"]
+
+            [variables.PORT]
+            type = "port"
+            requierd = true
+            "#,
+        )
+        .unwrap();
+
+        let unknown: Vec<String> = unknown_keys(&table).iter().map(|k| k.to_string()).collect();
+        assert_eq!(
+            unknown,
+            vec![
+                "unknown key `colour`",
+                "unknown key `environments[0].descripton`; did you mean `environments[0].description`?",
+                "unknown key `scan.exlude_dirs`; did you mean `scan.exclude_dirs`?",
+                "unknown key `variables.PORT.requierd`; did you mean `variables.PORT.required`?",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_every_setting_is_known() {
+        let mut config = Config::default();
+        config.environments.push(Environment {
+            name: "dev".to_string(),
+            description: Some("Development".to_string()),
+            file: Some(PathBuf::from("dev.env")),
+            extends: Some("base".to_string()),
+            variables: [("A".to_string(), "1".to_string())].into(),
+        });
+        config
+            .validation
+            .formats
+            .insert("A".to_string(), ".*".to_string());
+        config.variables.insert(
+            "PORT".to_string(),
+            VariableSpec {
+                default: Some("80".to_string()),
+                description: Some("Port".to_string()),
+                example: Some("8080".to_string()),
+                secret: true,
+                deprecated: Some("Use ADDR".to_string()),
+                required: true,
+                required_in: vec!["dev".to_string()],
+                values: vec!["80".to_string()],
+                ..Default::default()
+            },
+        );
+        config.rules.push(Rule {
+            name: "rule".to_string(),
+            when: Some("true".to_string()),
+            check: "true".to_string(),
+            message: Some("message".to_string()),
+        });
+        config.recipients.push(Recipient {
+            name: "ci".to_string(),
+            key: "envpk:key".to_string(),
+        });
+        config.preferences = Preferences {
+            format: Some(OutputFormat::Json),
+            color: Some(false),
+            editor: Some("vi".to_string()),
+        };
+
+        let table = toml::Table::try_from(&config).unwrap();
+        assert_eq!(unknown_keys(&table), Vec::new());
+    }
+}
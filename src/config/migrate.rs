@@ -0,0 +1,155 @@
+//! Configuration format versions.
+//!
+//! `.env/config.toml` records the `version` of the format it was written in;
+//! files from before versioning are version 0. Older files are upgraded in
+//! memory by running each [`Migration`] from their version up to
+//! [`CONFIG_VERSION`] before they are deserialized, so a format change never
+//! breaks an existing project. `env config migrate` writes the upgraded file
+//! back.
+
+use super::Config;
+use crate::error::{EnvCliError, Result};
+
+/// Version of the configuration format this build reads and writes.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// One step of the upgrade chain.
+#[derive(Debug)]
+pub struct Migration {
+    /// Version the migration upgrades from; it produces `from + 1`
+    pub from: u32,
+    /// What the migration changes
+    pub description: &'static str,
+    /// Rewrite a table of version `from`
+    pub apply: fn(&mut toml::Table),
+}
+
+/// Every migration, oldest first, one per version.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "fill in missing sections and turn environment names into [[environments]] tables",
+    apply: fill_defaults,
+}];
+
+/// The format version `table` was written in.
+pub fn version(table: &toml::Table) -> Result<u32> {
+    let version = match table.get("version") {
+        None => return Ok(0),
+        Some(value) => value
+            .as_integer()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| {
+                EnvCliError::Config(format!(
+                    "Invalid version {}: expected a whole number",
+                    value
+                ))
+            })?,
+    };
+    if version > CONFIG_VERSION {
+        return Err(EnvCliError::Config(format!(
+            "Configuration version {} is newer than this env-cli supports ({}); upgrade env-cli",
+            version, CONFIG_VERSION
+        )));
+    }
+    Ok(version)
+}
+
+/// Upgrade `table` to [`CONFIG_VERSION`], returning the migrations applied.
+pub fn migrate(table: &mut toml::Table) -> Result<Vec<&'static Migration>> {
+    let from = version(table)?;
+    let applied: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.from >= from).collect();
+    for migration in &applied {
+        (migration.apply)(table);
+        table.insert(
+            "version".to_string(),
+            toml::Value::Integer(i64::from(migration.from + 1)),
+        );
+    }
+    Ok(applied)
+}
+
+/// Version 0 to 1. Older files may leave out sections that are required,
+/// or list environments by name only, as in `environments = ["dev", "prod"]`.
+fn fill_defaults(table: &mut toml::Table) {
+    if let Some(toml::Value::Array(environments)) = table.get_mut("environments") {
+        for entry in environments.iter_mut() {
+            if let toml::Value::String(name) = entry {
+                let mut environment = toml::Table::new();
+                environment.insert("name".to_string(), toml::Value::String(name.clone()));
+                *entry = toml::Value::Table(environment);
+            }
+        }
+    }
+    if let Ok(toml::Value::Table(defaults)) = toml::Value::try_from(Config::default()) {
+        insert_missing(table, defaults);
+    }
+}
+
+/// Add the keys of `defaults` that `table` does not have, recursing into
+/// tables both have.
+fn insert_missing(table: &mut toml::Table, defaults: toml::Table) {
+    for (key, default) in defaults {
+        match (table.get_mut(&key), default) {
+            (Some(toml::Value::Table(table)), toml::Value::Table(defaults)) => {
+                insert_missing(table, defaults)
+            }
+            (Some(_), _) => {}
+            (None, default) => {
+                table.insert(key, default);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[test]
+    fn test_migrations_are_contiguous() {
+        let versions: Vec<u32> = MIGRATIONS.iter().map(|m| m.from).collect();
+        let expected: Vec<u32> = (0..CONFIG_VERSION).collect();
+        assert_eq!(versions, expected);
+    }
+
+    #[test]
+    fn test_migrate_unversioned() {
+        let mut table: toml::Table = toml::from_str(
+            r#"
+            project = "legacy"
+            default_environment = "dev"
+            environments = ["dev", "prod"]
+
+            [scan]
+            include_dirs = ["lib"]
+            "#,
+        )
+        .unwrap();
+
+        let applied = migrate(&mut table).unwrap();
+        assert_eq!(applied.len(), CONFIG_VERSION as usize);
+        assert_eq!(version(&table).unwrap(), CONFIG_VERSION);
+
+        let config = Config::deserialize(toml::Value::Table(table.clone())).unwrap();
+        assert_eq!(config.project, "legacy");
+        assert_eq!(config.environments[1].name, "prod");
+        assert_eq!(config.scan.include_dirs, vec!["lib"]);
+        assert_eq!(config.scan.exclude_dirs, vec!["target", "node_modules"]);
+        assert_eq!(config.validation.security.min_secret_length, Some(16));
+
+        // Current files are left alone
+        assert!(migrate(&mut table).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let table: toml::Table =
+            toml::from_str(&format!("version = {}", CONFIG_VERSION + 1)).unwrap();
+        let err = version(&table).unwrap_err();
+        assert!(err.to_string().contains("upgrade env-cli"));
+
+        let table: toml::Table = toml::from_str("version = \"one\"").unwrap();
+        assert!(version(&table).is_err());
+    }
+}
@@ -2,13 +2,31 @@
 //!
 //! This module handles loading, parsing, and managing configuration files.
 
-use crate::error::{EnvCliError, Result};
+pub mod keys;
+pub mod migrate;
+pub mod project;
+pub mod rules;
+pub mod schema;
+pub mod user;
+
+pub use migrate::CONFIG_VERSION;
+pub use project::Project;
+pub use rules::{Rule, RuleFailure};
+pub use schema::{VariableSpec, VariableType};
+pub use user::Preferences;
+
+use crate::env::{parser, Layer, LayerKind};
+use crate::error::{EnvCliError, Result, Span};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 /// Configuration for env-cli.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Format version the file was written in; see [`migrate`]
+    #[serde(default)]
+    pub version: u32,
     /// Project name
     pub project: String,
     /// Default environment
@@ -19,6 +37,25 @@ pub struct Config {
     pub scan: ScanConfig,
     /// Validation rules
     pub validation: ValidationConfig,
+    /// Typed schema for individual variables, from `[variables.NAME]` tables
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, VariableSpec>,
+    /// Cross-variable rules, from `[[rules]]` tables
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<Rule>,
+    /// People and machines that can decrypt environment files, from
+    /// `[[recipients]]` tables
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<Recipient>,
+    /// Personal preferences, usually set in the user configuration
+    #[serde(default, skip_serializing_if = "Preferences::is_empty")]
+    pub preferences: Preferences,
+    /// Project root that relative environment files resolve against
+    #[serde(skip)]
+    pub root: PathBuf,
+    /// File the configuration was loaded from
+    #[serde(skip)]
+    pub path: PathBuf,
 }
 
 /// Environment configuration.
@@ -30,8 +67,22 @@ pub struct Environment {
     pub description: Option<String>,
     /// Configuration file path
     pub file: Option<PathBuf>,
-    /// Environment variables
-    pub variables: std::collections::HashMap<String, String>,
+    /// Environment whose variables this one inherits and overrides
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// Inline variables, below the environment's file in precedence; see
+    /// [`Config::layers`]
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+}
+
+/// A public key that encrypted values are sealed for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recipient {
+    /// Who holds the matching identity
+    pub name: String,
+    /// Public key, `envpk:<base64>`
+    pub key: String,
 }
 
 /// Scan configuration.
@@ -72,11 +123,18 @@ pub struct SecurityConfig {
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: CONFIG_VERSION,
             project: "env-cli project".to_string(),
             default_environment: "development".to_string(),
             environments: vec![],
             scan: ScanConfig::default(),
             validation: ValidationConfig::default(),
+            variables: BTreeMap::new(),
+            rules: Vec::new(),
+            recipients: Vec::new(),
+            preferences: Preferences::default(),
+            root: PathBuf::new(),
+            path: PathBuf::from(".env/config.toml"),
         }
     }
 }
@@ -122,8 +180,194 @@ impl Default for SecurityConfig {
     }
 }
 
-/// Load configuration from file.
-pub fn load_config(path: &PathBuf) -> Result<Config> {
+impl Config {
+    /// Find an environment by name.
+    pub fn environment(&self, name: &str) -> Result<&Environment> {
+        self.environments
+            .iter()
+            .find(|e| e.name == name)
+            .ok_or_else(|| self.unknown_environment(name))
+    }
+
+    /// Find an environment by name, for editing.
+    pub fn environment_mut(&mut self, name: &str) -> Result<&mut Environment> {
+        match self.environments.iter().position(|e| e.name == name) {
+            Some(index) => Ok(&mut self.environments[index]),
+            None => Err(self.unknown_environment(name)),
+        }
+    }
+
+    fn unknown_environment(&self, name: &str) -> EnvCliError {
+        EnvCliError::Environment(format!(
+            "Environment '{}' not found. Available environments: {}",
+            name,
+            self.environments
+                .iter()
+                .map(|e| e.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+
+    /// The environments `name` inherits from, root first and `name` last.
+    ///
+    /// Fails when an `extends` names an unknown environment or the chain
+    /// loops back on itself.
+    pub fn inheritance_chain(&self, name: &str) -> Result<Vec<&Environment>> {
+        let mut chain = vec![self.environment(name)?];
+        while let Some(parent) = chain.last().and_then(|e| e.extends.as_deref()) {
+            if chain.iter().any(|e| e.name == parent) {
+                let names: Vec<&str> = chain.iter().map(|e| e.name.as_str()).collect();
+                return Err(EnvCliError::Config(format!(
+                    "Environment inheritance cycle: {} -> {}",
+                    names.join(" -> "),
+                    parent
+                )));
+            }
+            let child = &chain[chain.len() - 1].name;
+            let parent = self.environment(parent).map_err(|_| {
+                EnvCliError::Config(format!(
+                    "Environment '{}' extends unknown environment '{}'",
+                    child, parent
+                ))
+            })?;
+            chain.push(parent);
+        }
+        chain.reverse();
+        Ok(chain)
+    }
+
+    /// The sources an environment's variables are read from, lowest
+    /// precedence first.
+    ///
+    /// Each environment in the inheritance chain contributes, in order, its
+    /// inline `variables` from `config.toml` when it has any, its own file,
+    /// and its uncommitted `.local` override file.
+    pub fn layers(&self, name: &str) -> Result<Vec<Layer>> {
+        let mut layers = Vec::new();
+        for environment in self.inheritance_chain(name)? {
+            let layer = |file: PathBuf, kind: LayerKind| Layer {
+                environment: environment.name.clone(),
+                file,
+                kind,
+                variables: Default::default(),
+            };
+            if !environment.variables.is_empty() {
+                layers.push(Layer {
+                    variables: environment.variables.clone(),
+                    ..layer(self.path.clone(), LayerKind::Inline)
+                });
+            }
+            let file = self.environment_file(&environment.name);
+            let local_file = local_override_file(&file);
+            layers.push(layer(file, LayerKind::File));
+            layers.push(layer(local_file, LayerKind::Local));
+        }
+        Ok(layers)
+    }
+
+    /// Get the path of the file holding an environment's variables,
+    /// resolved against the project root.
+    pub fn environment_file(&self, name: &str) -> PathBuf {
+        let file = self
+            .environments
+            .iter()
+            .find(|e| e.name == name)
+            .and_then(|e| e.file.clone())
+            .unwrap_or_else(|| {
+                PathBuf::from(".env")
+                    .join("environments")
+                    .join(format!("{}.env", name))
+            });
+        self.root.join(file)
+    }
+}
+
+/// The uncommitted override file next to an environment file:
+/// `production.env` becomes `production.local.env`.
+pub fn local_override_file(file: &Path) -> PathBuf {
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    match file.extension() {
+        Some(extension) => {
+            file.with_file_name(format!("{}.local.{}", stem, extension.to_string_lossy()))
+        }
+        None => file.with_file_name(format!("{}.local", stem)),
+    }
+}
+
+/// A configuration file contributing to the effective configuration.
+#[derive(Debug, Clone)]
+pub struct ConfigSource {
+    /// File the table was read from
+    pub path: PathBuf,
+    pub table: toml::Table,
+}
+
+impl ConfigSource {
+    /// Read and parse a configuration file.
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let table = toml::from_str(&content).map_err(|e| parse_error(e, &content, path))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            table,
+        })
+    }
+
+    /// Read a project configuration file, upgraded to [`CONFIG_VERSION`].
+    pub fn read_migrated(path: &Path) -> Result<Self> {
+        let mut source = Self::read(path)?;
+        source.migrate()?;
+        Ok(source)
+    }
+
+    /// Upgrade the table to [`CONFIG_VERSION`], returning the migrations
+    /// applied.
+    pub fn migrate(&mut self) -> Result<Vec<&'static migrate::Migration>> {
+        migrate::migrate(&mut self.table).map_err(|e| match e {
+            EnvCliError::Config(message) => {
+                EnvCliError::Config(format!("{}: {}", self.path.display(), message))
+            }
+            e => e,
+        })
+    }
+}
+
+impl Config {
+    /// Build the configuration from its sources, lowest precedence first,
+    /// merging each over the ones before it; see [`user::merge`].
+    pub fn from_sources(sources: &[ConfigSource]) -> Result<Config> {
+        let mut merged = toml::Table::new();
+        for source in sources {
+            user::merge(&mut merged, source.table.clone());
+        }
+        let config = Config::deserialize(toml::Value::Table(merged))
+            .map_err(|e| EnvCliError::Config(format!("Invalid configuration: {}", e.message())))?;
+        config.check_variable_names()?;
+        Ok(config)
+    }
+
+    /// Refuse inline variables whose names are not valid variable names;
+    /// `env export` writes them into code the shell evaluates.
+    fn check_variable_names(&self) -> Result<()> {
+        for environment in &self.environments {
+            if let Some(key) = environment
+                .variables
+                .keys()
+                .find(|key| !parser::is_valid_env_key(key))
+            {
+                return Err(EnvCliError::Config(format!(
+                    "Invalid variable name {:?} in the variables of environment '{}'",
+                    key, environment.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Load configuration from file, upgrading it if it is of an older version.
+pub fn load_config(path: &Path) -> Result<Config> {
     if !path.exists() {
         return Err(EnvCliError::Config(format!(
             "Configuration file not found: {}",
@@ -132,18 +376,131 @@ pub fn load_config(path: &PathBuf) -> Result<Config> {
     }
 
     let content = std::fs::read_to_string(path)?;
-    let config: Config = toml::from_str(&content)?;
+    let mut source = ConfigSource {
+        path: path.to_path_buf(),
+        table: toml::from_str(&content).map_err(|e| parse_error(e, &content, path))?,
+    };
+    let config: Config = if source.migrate()?.is_empty() {
+        // Deserializing the text keeps the position of mistakes
+        toml::from_str(&content).map_err(|e| parse_error(e, &content, path))?
+    } else {
+        Config::from_sources(&[source])?
+    };
+    config.check_variable_names().map_err(|e| match e {
+        EnvCliError::Config(message) => {
+            EnvCliError::Config(format!("{}: {}", path.display(), message))
+        }
+        e => e,
+    })?;
     Ok(config)
 }
 
+/// Point a TOML error at the position in `path` it was found at.
+fn parse_error(e: toml::de::Error, content: &str, path: &Path) -> EnvCliError {
+    match e.span() {
+        Some(range) => EnvCliError::Syntax {
+            message: e.message().trim().replace('\n', ": "),
+            span: Span::at_offset(content, range.start),
+        }
+        .in_file(path),
+        None => e.into(),
+    }
+}
+
 /// Save configuration to file.
-pub fn save_config(config: &Config, path: &PathBuf) -> Result<()> {
+pub fn save_config(config: &Config, path: &Path) -> Result<()> {
     let content = toml::to_string_pretty(config)?;
     std::fs::write(path, content)?;
     Ok(())
 }
 
-/// Get default configuration file path.
-pub fn default_config_path() -> PathBuf {
-    PathBuf::from(".env/config.toml")
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn environment(name: &str, extends: Option<&str>) -> Environment {
+        Environment {
+            name: name.to_string(),
+            description: None,
+            file: None,
+            extends: extends.map(str::to_string),
+            variables: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_inheritance_layers() {
+        let mut config = Config::default();
+        config.environments = vec![
+            environment("base", None),
+            environment("staging", Some("base")),
+            environment("production", Some("staging")),
+        ];
+
+        let layers = config.layers("production").unwrap();
+        let files: Vec<String> = layers
+            .iter()
+            .map(|layer| {
+                layer
+                    .file
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        assert_eq!(
+            files,
+            [
+                "base.env",
+                "base.local.env",
+                "staging.env",
+                "staging.local.env",
+                "production.env",
+                "production.local.env"
+            ]
+        );
+        assert!(layers[5].kind == LayerKind::Local && layers[5].environment == "production");
+
+        config.environments[1]
+            .variables
+            .insert("LOG_LEVEL".to_string(), "info".to_string());
+        let layers = config.layers("staging").unwrap();
+        assert_eq!(layers[2].kind, LayerKind::Inline);
+        assert_eq!(layers[2].variables["LOG_LEVEL"], "info");
+        assert_eq!(layers[3].kind, LayerKind::File);
+
+        config.environments[0].extends = Some("production".to_string());
+        let err = config.layers("staging").unwrap_err().to_string();
+        assert!(
+            err.contains("staging -> base -> production -> staging"),
+            "{}",
+            err
+        );
+
+        config.environments[0].extends = Some("missing".to_string());
+        let err = config.layers("base").unwrap_err().to_string();
+        assert!(
+            err.contains("extends unknown environment 'missing'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_invalid_inline_names_are_refused() {
+        let mut config = Config::default();
+        let mut development = environment("development", None);
+        development
+            .variables
+            .insert("X;echo PWNED;Y".to_string(), "1".to_string());
+        config.environments.push(development);
+
+        let source = ConfigSource {
+            path: PathBuf::from("config.toml"),
+            table: toml::Table::try_from(&config).unwrap(),
+        };
+        let err = Config::from_sources(&[source]).unwrap_err().to_string();
+        assert!(err.contains("Invalid variable name"), "{}", err);
+    }
 }
@@ -0,0 +1,315 @@
+//! Locating the project a command works on.
+//!
+//! A project is the directory holding `.env/config.toml`. Commands find it by
+//! walking up from the working directory, stopping at the root of the git
+//! repository or at a filesystem boundary, unless `--config` or
+//! `ENV_CLI_CONFIG` names the configuration file directly.
+
+use super::{
+    load_config, migrate, save_config, user, Config, ConfigSource, Preferences, CONFIG_VERSION,
+};
+use crate::error::{EnvCliError, Result};
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming the configuration file to use.
+pub const CONFIG_ENV_VAR: &str = "ENV_CLI_CONFIG";
+
+/// The project a command works on, and where its files live.
+///
+/// The root is kept relative to the working directory (empty when they are
+/// the same), so paths shown to the user read the way they were typed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    root: PathBuf,
+    config_path: PathBuf,
+}
+
+impl Default for Project {
+    /// The project in the working directory.
+    fn default() -> Self {
+        Self::at("")
+    }
+}
+
+impl Project {
+    /// The project rooted at `root`, whether or not it is initialized.
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        let config_path = root.join(".env").join("config.toml");
+        Self { root, config_path }
+    }
+
+    /// The project whose configuration file is `path`.
+    ///
+    /// The root is the directory containing the `.env` directory the file
+    /// sits in, or the file's own directory when it lives elsewhere.
+    pub fn with_config(path: impl Into<PathBuf>) -> Self {
+        let config_path = path.into();
+        let dir = config_path.parent().unwrap_or(Path::new(""));
+        let root = match dir.file_name() {
+            Some(name) if name == ".env" => dir.parent().unwrap_or(Path::new("")),
+            _ => dir,
+        };
+        Self {
+            root: root.to_path_buf(),
+            config_path,
+        }
+    }
+
+    /// Locate the project: `config` if given, then `ENV_CLI_CONFIG`, then
+    /// the nearest `.env/config.toml` above the working directory.
+    ///
+    /// Falls back to the working directory when no project is found, so
+    /// commands can report that it is not initialized.
+    pub fn discover(config: Option<PathBuf>) -> Result<Self> {
+        if let Some(path) = config.or_else(config_from_env) {
+            return Ok(Self::with_config(path));
+        }
+        let cwd = std::env::current_dir()?;
+        Ok(match find_root(&cwd) {
+            Some(levels) => Self::at(std::iter::repeat("..").take(levels).collect::<PathBuf>()),
+            None => Self::default(),
+        })
+    }
+
+    /// The project `init` creates: `config` if given, then `ENV_CLI_CONFIG`,
+    /// then the working directory, without searching parent directories.
+    pub fn here(config: Option<PathBuf>) -> Self {
+        match config.or_else(config_from_env) {
+            Some(path) => Self::with_config(path),
+            None => Self::default(),
+        }
+    }
+
+    /// The project's root directory; `.` for the working directory.
+    pub fn root(&self) -> &Path {
+        if self.root.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            &self.root
+        }
+    }
+
+    /// The project's `config.toml`.
+    pub fn config_path(&self) -> &Path {
+        &self.config_path
+    }
+
+    /// The `.env` directory holding configuration, environments and keys.
+    pub fn env_dir(&self) -> PathBuf {
+        self.root.join(".env")
+    }
+
+    /// Where environment backups are written.
+    pub fn backups_dir(&self) -> PathBuf {
+        self.env_dir().join("backups")
+    }
+
+    /// The `.current` link naming the active environment.
+    pub fn current_link(&self) -> PathBuf {
+        self.env_dir().join(".current")
+    }
+
+    /// The project key used when no recipients are configured.
+    pub fn key_path(&self) -> PathBuf {
+        self.env_dir().join("key")
+    }
+
+    /// The log of sync operations.
+    pub fn audit_log_path(&self) -> PathBuf {
+        self.env_dir().join("sync-audit.json")
+    }
+
+    /// Whether the project has a configuration file.
+    pub fn is_initialized(&self) -> bool {
+        self.config_path.is_file()
+    }
+
+    /// The configuration files that apply, lowest precedence first: the
+    /// user configuration and the project's, whichever exist. The project's
+    /// is upgraded to the current version.
+    pub fn config_sources(&self) -> Result<Vec<ConfigSource>> {
+        let mut sources = Vec::new();
+        if let Some(path) = user::user_config_path().filter(|path| path.is_file()) {
+            sources.push(ConfigSource::read(&path)?);
+        }
+        if self.is_initialized() {
+            sources.push(ConfigSource::read_migrated(&self.config_path)?);
+        }
+        Ok(sources)
+    }
+
+    /// Load the project's configuration over the user configuration,
+    /// resolving its paths against the root.
+    pub fn load_config(&self) -> Result<Config> {
+        self.ensure_config()?;
+        let sources = self.config_sources()?;
+        let mut config = Config::from_sources(&sources).map_err(|e| {
+            // A project file that is wrong on its own is reported with the
+            // position of the mistake; otherwise the user configuration is
+            // what broke it
+            if let Err(project_error) = load_config(&self.config_path) {
+                return project_error;
+            }
+            match (e, sources.first()) {
+                (EnvCliError::Config(message), Some(user)) if sources.len() > 1 => {
+                    EnvCliError::Config(format!(
+                        "{} (merged with user configuration {})",
+                        message,
+                        user.path.display()
+                    ))
+                }
+                (e, _) => e,
+            }
+        })?;
+        config.root = self.root.clone();
+        config.path = self.config_path.clone();
+        Ok(config)
+    }
+
+    /// Load only the project's configuration file, without the user
+    /// configuration, for commands that change and save it.
+    ///
+    /// Files of an older version are refused: saving them would upgrade them
+    /// without the backup `env config migrate` keeps.
+    pub fn load_own_config(&self) -> Result<Config> {
+        self.ensure_config()?;
+        let version = migrate::version(&ConfigSource::read(&self.config_path)?.table)?;
+        if version < CONFIG_VERSION {
+            return Err(EnvCliError::Config(format!(
+                "{} is version {} of the configuration format; run 'env config migrate' to upgrade it to version {} before changing it",
+                self.config_path.display(),
+                version,
+                CONFIG_VERSION
+            )));
+        }
+        let mut config = load_config(&self.config_path)?;
+        config.root = self.root.clone();
+        config.path = self.config_path.clone();
+        Ok(config)
+    }
+
+    fn ensure_config(&self) -> Result<()> {
+        if !self.config_path.exists() && !self.env_dir().exists() {
+            return Err(EnvCliError::NotInitialized);
+        }
+        if !self.config_path.exists() {
+            return Err(EnvCliError::Config(format!(
+                "Configuration file not found: {}",
+                self.config_path.display()
+            )));
+        }
+        Ok(())
+    }
+
+    /// The preferences in effect: those of the project configuration over
+    /// the user's.
+    pub fn preferences(&self) -> Result<Preferences> {
+        Preferences::from_sources(&self.config_sources()?)
+    }
+
+    /// Save the project's configuration. Pass a configuration from
+    /// [`Project::load_own_config`], so user settings are not copied into it.
+    pub fn save_config(&self, config: &Config) -> Result<()> {
+        save_config(config, &self.config_path)
+    }
+
+    /// Get the name of the environment `.current` points to.
+    pub fn current_environment(&self) -> Result<String> {
+        let target = std::fs::read_link(self.current_link()).map_err(|_| {
+            EnvCliError::Environment(
+                "No current environment set. Use 'env switch <environment>' first.".to_string(),
+            )
+        })?;
+
+        target
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix(".env"))
+            .map(str::to_string)
+            .ok_or_else(|| {
+                EnvCliError::Environment("Unable to determine current environment".to_string())
+            })
+    }
+}
+
+fn config_from_env() -> Option<PathBuf> {
+    std::env::var_os(CONFIG_ENV_VAR)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// How many levels above `start` the nearest project is.
+///
+/// The search stops after a directory containing `.git`, and before
+/// crossing onto another filesystem.
+fn find_root(start: &Path) -> Option<usize> {
+    let start_device = device(start);
+    for (levels, dir) in start.ancestors().enumerate() {
+        if start_device.is_some() && device(dir) != start_device {
+            break;
+        }
+        if dir.join(".env").join("config.toml").is_file() {
+            return Some(levels);
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+#[cfg(unix)]
+fn device(dir: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(dir).ok().map(|metadata| metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_dir: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn touch(path: &Path) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "").unwrap();
+    }
+
+    #[test]
+    fn test_find_root() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path().join("repo");
+        let nested = repo.join("app").join("src");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(&nested).unwrap();
+
+        assert_eq!(find_root(&nested), None);
+
+        touch(&repo.join(".env/config.toml"));
+        assert_eq!(find_root(&nested), Some(2));
+        assert_eq!(find_root(&repo), Some(0));
+
+        // A project above the git root belongs to another repository.
+        std::fs::remove_file(repo.join(".env/config.toml")).unwrap();
+        touch(&temp.path().join(".env/config.toml"));
+        assert_eq!(find_root(&nested), None);
+    }
+
+    #[test]
+    fn test_with_config() {
+        let project = Project::with_config("app/.env/config.toml");
+        assert_eq!(project.root(), Path::new("app"));
+        assert_eq!(project.key_path(), Path::new("app/.env/key"));
+
+        let project = Project::with_config("ci/env.toml");
+        assert_eq!(project.root(), Path::new("ci"));
+        assert_eq!(project.config_path(), Path::new("ci/env.toml"));
+
+        assert_eq!(Project::at("").config_path(), Path::new(".env/config.toml"));
+    }
+}
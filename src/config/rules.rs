@@ -0,0 +1,628 @@
+//! Cross-variable validation rules.
+//!
+//! A `[[rules]]` entry in `config.toml` checks a condition that spans several
+//! variables:
+//!
+//! ```toml
+//! [[rules]]
+//! name = "s3-settings"
+//! when = "FEATURE_S3 == true"
+//! check = "set(S3_BUCKET) && set(AWS_REGION)"
+//! message = "S3 uploads need a bucket and a region"
+//!
+//! [[rules]]
+//! name = "pool-bounds"
+//! when = "set(DATABASE_POOL_MIN) && set(DATABASE_POOL_MAX)"
+//! check = "DATABASE_POOL_MIN <= DATABASE_POOL_MAX"
+//!
+//! [[rules]]
+//! name = "redis-exclusive"
+//! check = "!(set(REDIS_URL) && set(REDIS_HOST))"
+//! ```
+//!
+//! A rule is skipped when its optional `when` expression is false, and fails
+//! when its `check` expression is false.
+//!
+//! Expressions are made of:
+//!
+//! - variable names, which evaluate to the variable's value (or to nothing
+//!   when it is unset);
+//! - string literals in single or double quotes, numbers, `true` and `false`;
+//! - comparisons `==`, `!=`, `<`, `<=`, `>`, `>=`. Ordering comparisons are
+//!   numeric. Equality is numeric when one side is a number, boolean when one
+//!   side is `true`/`false`, and textual otherwise;
+//! - `&&`, `||`, `!` and parentheses. A variable used as a condition is true
+//!   unless it is unset, empty, `false`, `0`, `no` or `off`;
+//! - the functions `set(X)`, `empty(X)`, `len(X)` and `matches(X, 'regex')`.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A named validation rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    /// Name cited when the rule fails
+    pub name: String,
+    /// Condition under which the rule applies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+    /// Expression that must hold
+    pub check: String,
+    /// Message shown when the rule fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Why a rule did not pass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleFailure {
+    /// Name of the failed rule
+    pub rule: String,
+    /// What went wrong
+    pub message: String,
+    /// Variables referenced by the rule, in order of first use
+    pub variables: Vec<String>,
+}
+
+impl fmt::Display for RuleFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Rule '{}' failed: {}", self.rule, self.message)?;
+        if !self.variables.is_empty() {
+            write!(f, " (involves {})", self.variables.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Rule {
+    /// Evaluate the rule against a set of variables.
+    ///
+    /// Invalid expressions and evaluation errors, such as comparing a
+    /// non-numeric value with `<`, are reported as failures.
+    pub fn evaluate(&self, variables: &HashMap<String, String>) -> Result<(), RuleFailure> {
+        let failure = |message: String, referenced: Vec<String>| RuleFailure {
+            rule: self.name.clone(),
+            message,
+            variables: referenced
+                .into_iter()
+                .map(|name| {
+                    if variables.contains_key(&name) {
+                        name
+                    } else {
+                        format!("{} (not set)", name)
+                    }
+                })
+                .collect(),
+        };
+
+        let when = match &self.when {
+            Some(source) => Some(
+                parse(source)
+                    .map_err(|e| failure(format!("invalid 'when' expression: {}", e), vec![]))?,
+            ),
+            None => None,
+        };
+        let check = parse(&self.check)
+            .map_err(|e| failure(format!("invalid 'check' expression: {}", e), vec![]))?;
+
+        let mut referenced = Vec::new();
+        if let Some(when) = &when {
+            when.collect_variables(&mut referenced);
+        }
+        check.collect_variables(&mut referenced);
+
+        if let Some(when) = &when {
+            match when.eval(variables).map(|value| value.truthy()) {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                Err(e) => return Err(failure(e, referenced)),
+            }
+        }
+
+        match check.eval(variables).map(|value| value.truthy()) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(failure(
+                self.message
+                    .clone()
+                    .unwrap_or_else(|| format!("`{}` is not satisfied", self.check)),
+                referenced,
+            )),
+            Err(e) => Err(failure(e, referenced)),
+        }
+    }
+}
+
+/// A parsed expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Var(String),
+    Str(String),
+    Num(f64),
+    Bool(bool),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(CompareOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Set,
+    Empty,
+    Len,
+    Matches,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "set" => Some(Function::Set),
+            "empty" => Some(Function::Empty),
+            "len" => Some(Function::Len),
+            "matches" => Some(Function::Matches),
+            _ => None,
+        }
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Function::Matches => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// The result of evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Unset,
+    Str(String),
+    Num(f64),
+    Bool(bool),
+}
+
+impl Value {
+    fn truthy(&self) -> bool {
+        match self {
+            Value::Unset => false,
+            Value::Bool(b) => *b,
+            Value::Num(n) => *n != 0.0,
+            Value::Str(s) => !matches!(
+                s.to_ascii_lowercase().as_str(),
+                "" | "false" | "0" | "no" | "off"
+            ),
+        }
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Num(n) => Some(*n),
+            Value::Str(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_text(&self) -> String {
+        match self {
+            Value::Unset => String::new(),
+            Value::Str(s) => s.clone(),
+            Value::Num(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+        }
+    }
+}
+
+impl Expr {
+    fn collect_variables(&self, out: &mut Vec<String>) {
+        match self {
+            Expr::Var(name) => {
+                if !out.contains(name) {
+                    out.push(name.clone());
+                }
+            }
+            Expr::Str(_) | Expr::Num(_) | Expr::Bool(_) => {}
+            Expr::Not(inner) => inner.collect_variables(out),
+            Expr::And(a, b) | Expr::Or(a, b) | Expr::Compare(_, a, b) => {
+                a.collect_variables(out);
+                b.collect_variables(out);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|arg| arg.collect_variables(out)),
+        }
+    }
+
+    /// Describe an operand for error messages.
+    fn describe(&self) -> String {
+        match self {
+            Expr::Var(name) => name.clone(),
+            Expr::Str(s) => format!("'{}'", s),
+            Expr::Num(n) => n.to_string(),
+            Expr::Bool(b) => b.to_string(),
+            _ => "expression".to_string(),
+        }
+    }
+
+    fn eval(&self, variables: &HashMap<String, String>) -> Result<Value, String> {
+        Ok(match self {
+            Expr::Var(name) => variables
+                .get(name)
+                .map_or(Value::Unset, |v| Value::Str(v.clone())),
+            Expr::Str(s) => Value::Str(s.clone()),
+            Expr::Num(n) => Value::Num(*n),
+            Expr::Bool(b) => Value::Bool(*b),
+            Expr::Not(inner) => Value::Bool(!inner.eval(variables)?.truthy()),
+            Expr::And(a, b) => {
+                Value::Bool(a.eval(variables)?.truthy() && b.eval(variables)?.truthy())
+            }
+            Expr::Or(a, b) => {
+                Value::Bool(a.eval(variables)?.truthy() || b.eval(variables)?.truthy())
+            }
+            Expr::Compare(op, a, b) => {
+                let left = a.eval(variables)?;
+                let right = b.eval(variables)?;
+                Value::Bool(compare(*op, (a.as_ref(), &left), (b.as_ref(), &right))?)
+            }
+            Expr::Call(function, args) => {
+                let value = args[0].eval(variables)?;
+                match function {
+                    Function::Set => Value::Bool(value != Value::Unset),
+                    Function::Empty => Value::Bool(value.as_text().is_empty()),
+                    Function::Len => Value::Num(value.as_text().chars().count() as f64),
+                    Function::Matches => {
+                        let pattern = args[1].eval(variables)?.as_text();
+                        let regex = Regex::new(&pattern)
+                            .map_err(|e| format!("invalid pattern '{}': {}", pattern, e))?;
+                        Value::Bool(value != Value::Unset && regex.is_match(&value.as_text()))
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn compare(
+    op: CompareOp,
+    (left_expr, left): (&Expr, &Value),
+    (right_expr, right): (&Expr, &Value),
+) -> Result<bool, String> {
+    if matches!(op, CompareOp::Eq | CompareOp::Ne) {
+        let equal = match (left, right) {
+            (Value::Unset, Value::Unset) => true,
+            (Value::Unset, _) | (_, Value::Unset) => false,
+            (Value::Bool(b), other) | (other, Value::Bool(b)) => *b == other.truthy(),
+            (Value::Num(n), other) | (other, Value::Num(n)) => other.as_number() == Some(*n),
+            (Value::Str(a), Value::Str(b)) => a == b,
+        };
+        return Ok(equal == (op == CompareOp::Eq));
+    }
+
+    let number = |expr: &Expr, value: &Value| match value {
+        Value::Unset => Err(format!("{} is not set", expr.describe())),
+        _ => value
+            .as_number()
+            .ok_or_else(|| format!("{} is not a number", expr.describe())),
+    };
+    let (l, r) = (number(left_expr, left)?, number(right_expr, right)?);
+
+    Ok(match op {
+        CompareOp::Lt => l < r,
+        CompareOp::Le => l <= r,
+        CompareOp::Gt => l > r,
+        CompareOp::Ge => l >= r,
+        CompareOp::Eq | CompareOp::Ne => unreachable!(),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            ' ' | '\t' | '\n' | '\r' => i += 1,
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            '\'' | '"' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&ch| ch == c)
+                    .ok_or_else(|| format!("unterminated string starting at column {}", i + 1))?;
+                tokens.push(Token::Str(chars[i + 1..i + 1 + end].iter().collect()));
+                i += end + 2;
+            }
+            c if c.is_ascii_digit()
+                || (c == '-' && chars.get(i + 1).map_or(false, |d| d.is_ascii_digit())) =>
+            {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = text
+                    .parse()
+                    .map_err(|_| format!("invalid number '{}'", text))?;
+                tokens.push(Token::Num(number));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+            _ => {
+                let two: String = chars[i..chars.len().min(i + 2)].iter().collect();
+                let op = ["==", "!=", "<=", ">=", "&&", "||"]
+                    .into_iter()
+                    .find(|op| *op == two)
+                    .or_else(|| ["<", ">", "!"].into_iter().find(|op| op.starts_with(c)))
+                    .ok_or_else(|| format!("unexpected '{}' at column {}", c, i + 1))?;
+                tokens.push(Token::Op(op));
+                i += op.len();
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parse an expression.
+fn parse(source: &str) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+    };
+    let expr = parser.or()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(format!("unexpected {:?}", token)),
+    }
+}
+
+/// Recursive-descent parser; `||` binds loosest, then `&&`, `!` and comparisons.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Op(o)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat_op("||") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.eat_op("&&") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat_op("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.primary()?;
+        let op = match self.peek() {
+            Some(Token::Op("==")) => CompareOp::Eq,
+            Some(Token::Op("!=")) => CompareOp::Ne,
+            Some(Token::Op("<")) => CompareOp::Lt,
+            Some(Token::Op("<=")) => CompareOp::Le,
+            Some(Token::Op(">")) => CompareOp::Gt,
+            Some(Token::Op(">=")) => CompareOp::Ge,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        let right = self.primary()?;
+        Ok(Expr::Compare(op, Box::new(left), Box::new(right)))
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Str(s)) => Ok(Expr::Str(s)),
+            Some(Token::Num(n)) => Ok(Expr::Num(n)),
+            Some(Token::LParen) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err("expected ')'".to_string()),
+                }
+            }
+            Some(Token::Ident(name)) if name == "true" => Ok(Expr::Bool(true)),
+            Some(Token::Ident(name)) if name == "false" => Ok(Expr::Bool(false)),
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                let function = Function::from_name(&name)
+                    .ok_or_else(|| format!("unknown function '{}'", name))?;
+                self.pos += 1;
+
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::RParen) {
+                    loop {
+                        args.push(self.or()?);
+                        if self.peek() == Some(&Token::Comma) {
+                            self.pos += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                if self.next() != Some(Token::RParen) {
+                    return Err(format!("expected ')' after arguments to '{}'", name));
+                }
+                if args.len() != function.arity() {
+                    return Err(format!(
+                        "'{}' takes {} argument(s), got {}",
+                        name,
+                        function.arity(),
+                        args.len()
+                    ));
+                }
+                Ok(Expr::Call(function, args))
+            }
+            Some(Token::Ident(name)) => Ok(Expr::Var(name)),
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn rule(when: Option<&str>, check: &str) -> Rule {
+        Rule {
+            name: "test".to_string(),
+            when: when.map(str::to_string),
+            check: check.to_string(),
+            message: None,
+        }
+    }
+
+    #[test]
+    fn test_conditional_requirement() {
+        let r = rule(
+            Some("FEATURE_S3 == true"),
+            "set(S3_BUCKET) && set(AWS_REGION)",
+        );
+
+        assert!(r.evaluate(&vars(&[("FEATURE_S3", "false")])).is_ok());
+        assert!(r
+            .evaluate(&vars(&[
+                ("FEATURE_S3", "true"),
+                ("S3_BUCKET", "b"),
+                ("AWS_REGION", "eu-west-1")
+            ]))
+            .is_ok());
+
+        let failure = r
+            .evaluate(&vars(&[("FEATURE_S3", "true"), ("S3_BUCKET", "b")]))
+            .unwrap_err();
+        assert_eq!(
+            failure.variables,
+            vec!["FEATURE_S3", "S3_BUCKET", "AWS_REGION (not set)"]
+        );
+    }
+
+    #[test]
+    fn test_numeric_comparison() {
+        let r = rule(None, "DATABASE_POOL_MIN <= DATABASE_POOL_MAX");
+        assert!(r
+            .evaluate(&vars(&[
+                ("DATABASE_POOL_MIN", "5"),
+                ("DATABASE_POOL_MAX", "20")
+            ]))
+            .is_ok());
+        assert!(r
+            .evaluate(&vars(&[
+                ("DATABASE_POOL_MIN", "50"),
+                ("DATABASE_POOL_MAX", "20")
+            ]))
+            .is_err());
+
+        let failure = r
+            .evaluate(&vars(&[("DATABASE_POOL_MIN", "five")]))
+            .unwrap_err();
+        assert_eq!(failure.message, "DATABASE_POOL_MIN is not a number");
+    }
+
+    #[test]
+    fn test_mutual_exclusion_and_functions() {
+        let r = rule(None, "!(set(REDIS_URL) && set(REDIS_HOST))");
+        assert!(r.evaluate(&vars(&[("REDIS_URL", "redis://x")])).is_ok());
+        assert!(r
+            .evaluate(&vars(&[("REDIS_URL", "redis://x"), ("REDIS_HOST", "x")]))
+            .is_err());
+
+        let r = rule(
+            None,
+            "len(SECRET) >= 8 && matches(REGION, '^[a-z]+-[a-z]+-[0-9]$')",
+        );
+        assert!(r
+            .evaluate(&vars(&[("SECRET", "12345678"), ("REGION", "eu-west-1")]))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        for check in ["A ==", "set(A", "nope(A)", "A = 1", "'open"] {
+            let failure = rule(None, check).evaluate(&HashMap::new()).unwrap_err();
+            assert!(
+                failure.message.starts_with("invalid 'check' expression"),
+                "{}: {}",
+                check,
+                failure.message
+            );
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! Typed variable schema.
+//!
+//! Each `[variables.NAME]` table in `config.toml` declares what a variable is
+//! expected to hold:
+//!
+//! ```toml
+//! [variables.DATABASE_POOL_SIZE]
+//! type = "int"
+//! default = "10"
+//! description = "Maximum number of pooled database connections"
+//!
+//! [variables.LOG_LEVEL]
+//! type = "enum"
+//! values = ["debug", "info", "warn", "error"]
+//! required_in = ["production"]
+//!
+//! [variables.LEGACY_DB_HOST]
+//! deprecated = "Use DATABASE_URL instead"
+//! ```
+//!
+//! Every field is optional; a variable without a `type` is a plain string.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The kind of value a variable holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableType {
+    /// Any text
+    #[default]
+    String,
+    /// A signed 64-bit integer
+    Int,
+    /// A floating point number
+    Float,
+    /// `true`/`false`, `1`/`0`, `yes`/`no` or `on`/`off`
+    Bool,
+    /// An absolute URL with a scheme and host
+    Url,
+    /// A TCP/UDP port between 1 and 65535
+    Port,
+    /// An email address
+    Email,
+    /// A duration such as `30s`, `500ms` or `1h30m`
+    Duration,
+    /// One of the strings listed in `values`
+    Enum,
+    /// A JSON document
+    Json,
+    /// A filesystem path
+    Path,
+}
+
+impl fmt::Display for VariableType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            VariableType::String => "string",
+            VariableType::Int => "int",
+            VariableType::Float => "float",
+            VariableType::Bool => "bool",
+            VariableType::Url => "url",
+            VariableType::Port => "port",
+            VariableType::Email => "email",
+            VariableType::Duration => "duration",
+            VariableType::Enum => "enum",
+            VariableType::Json => "json",
+            VariableType::Path => "path",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Schema entry for a single variable.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VariableSpec {
+    /// Expected value type
+    #[serde(rename = "type")]
+    pub kind: VariableType,
+    /// Value used when the variable is not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Human-readable description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Example value shown in generated files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<String>,
+    /// Whether the value is a secret and should be masked
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub secret: bool,
+    /// Deprecation note; set when the variable should no longer be used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+    /// Required in every environment
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub required: bool,
+    /// Environments in which the variable is required
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub required_in: Vec<String>,
+    /// Allowed values for `enum` variables
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+}
+
+impl VariableSpec {
+    /// Whether the variable must be set in the given environment.
+    pub fn is_required_in(&self, environment: &str) -> bool {
+        self.required || self.required_in.iter().any(|env| env == environment)
+    }
+
+    /// Check a value against the declared type.
+    ///
+    /// Returns a short explanation of what was expected when it does not match.
+    pub fn check(&self, value: &str) -> std::result::Result<(), String> {
+        let valid = match self.kind {
+            VariableType::String => true,
+            VariableType::Int => value.parse::<i64>().is_ok(),
+            VariableType::Float => value.parse::<f64>().is_ok(),
+            VariableType::Bool => matches!(
+                value.to_ascii_lowercase().as_str(),
+                "true" | "false" | "1" | "0" | "yes" | "no" | "on" | "off"
+            ),
+            VariableType::Url => is_url(value),
+            VariableType::Port => value.parse::<u16>().map_or(false, |port| port > 0),
+            VariableType::Email => is_email(value),
+            VariableType::Duration => is_duration(value),
+            VariableType::Enum => self.values.iter().any(|allowed| allowed == value),
+            VariableType::Json => serde_json::from_str::<serde_json::Value>(value).is_ok(),
+            VariableType::Path => !value.is_empty() && !value.contains('\0'),
+        };
+
+        if valid {
+            return Ok(());
+        }
+
+        Err(match self.kind {
+            VariableType::Enum => format!("expected one of: {}", self.values.join(", ")),
+            VariableType::Port => "expected a port between 1 and 65535".to_string(),
+            VariableType::Duration => "expected a duration such as 30s or 1h30m".to_string(),
+            kind => format!("expected a valid {}", kind),
+        })
+    }
+}
+
+fn is_url(value: &str) -> bool {
+    let Some((scheme, rest)) = value.split_once("://") else {
+        return false;
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+
+    scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !host.is_empty()
+        && !value.chars().any(char::is_whitespace)
+}
+
+fn is_email(value: &str) -> bool {
+    match value.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !value.chars().any(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+/// Accept one or more `<number><unit>` parts, e.g. `90s` or `1h30m`.
+fn is_duration(value: &str) -> bool {
+    let mut rest = value;
+    if rest.is_empty() {
+        return false;
+    }
+
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        if digits == 0 || rest[..digits].parse::<f64>().is_err() {
+            return false;
+        }
+        rest = &rest[digits..];
+
+        let unit = ["ms", "s", "m", "h", "d", "w"]
+            .into_iter()
+            .find(|unit| rest.starts_with(unit));
+        match unit {
+            Some(unit) => rest = &rest[unit.len()..],
+            None => return false,
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(kind: VariableType) -> VariableSpec {
+        VariableSpec {
+            kind,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_type_checks() {
+        assert!(spec(VariableType::Int).check("-42").is_ok());
+        assert!(spec(VariableType::Int).check("4.2").is_err());
+        assert!(spec(VariableType::Bool).check("Yes").is_ok());
+        assert!(spec(VariableType::Url)
+            .check("postgres://db:5432/app")
+            .is_ok());
+        assert!(spec(VariableType::Url).check("localhost:5432").is_err());
+        assert!(spec(VariableType::Port).check("0").is_err());
+        assert!(spec(VariableType::Email).check("ops@example.com").is_ok());
+        assert!(spec(VariableType::Duration).check("1h30m").is_ok());
+        assert!(spec(VariableType::Duration).check("30").is_err());
+        assert!(spec(VariableType::Json).check("{\"a\": [1]}").is_ok());
+
+        let level = VariableSpec {
+            kind: VariableType::Enum,
+            values: vec!["info".to_string(), "debug".to_string()],
+            ..Default::default()
+        };
+        assert!(level.check("info").is_ok());
+        assert_eq!(
+            level.check("trace").unwrap_err(),
+            "expected one of: info, debug"
+        );
+    }
+
+    #[test]
+    fn test_parses_from_toml() {
+        let parsed: std::collections::BTreeMap<String, VariableSpec> = toml::from_str(
+            r#"
+            [PORT]
+            type = "port"
+            default = "8080"
+            required_in = ["production"]
+
+            [OLD_HOST]
+            deprecated = "Use DATABASE_URL"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(parsed["PORT"].kind, VariableType::Port);
+        assert!(parsed["PORT"].is_required_in("production"));
+        assert!(!parsed["PORT"].is_required_in("development"));
+        assert_eq!(parsed["OLD_HOST"].kind, VariableType::String);
+        assert_eq!(
+            parsed["OLD_HOST"].deprecated.as_deref(),
+            Some("Use DATABASE_URL")
+        );
+    }
+}
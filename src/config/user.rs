@@ -0,0 +1,135 @@
+//! User-level configuration.
+//!
+//! Personal defaults live in `env-cli/config.toml` under the user's
+//! configuration directory and use the same keys as a project's
+//! `.env/config.toml`. The project configuration is merged over them with
+//! [`merge`], so a project can override anything a user sets.
+
+use super::ConfigSource;
+use crate::cli::OutputFormat;
+use crate::error::{EnvCliError, Result};
+use crate::utils;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Environment variable naming the user configuration file; empty to ignore it.
+pub const USER_CONFIG_ENV_VAR: &str = "ENV_CLI_USER_CONFIG";
+
+/// Location of the user configuration file.
+pub fn user_config_path() -> Option<PathBuf> {
+    match std::env::var_os(USER_CONFIG_ENV_VAR) {
+        Some(path) if path.is_empty() => None,
+        Some(path) => Some(PathBuf::from(path)),
+        None => utils::config_dir().map(|dir| dir.join("env-cli").join("config.toml")),
+    }
+}
+
+/// Personal preferences, from the `[preferences]` table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preferences {
+    /// Output format used when `--format` is not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<OutputFormat>,
+    /// `false` disables colored output, like `--no-color`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<bool>,
+    /// Command `env config edit` opens files with, before `$VISUAL` and
+    /// `$EDITOR`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+}
+
+impl Preferences {
+    /// Whether no preference is set.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The preferences set by `sources`, lowest precedence first.
+    pub fn from_sources(sources: &[ConfigSource]) -> Result<Self> {
+        let mut merged = toml::Table::new();
+        let mut files = Vec::new();
+        for source in sources {
+            if source.table.contains_key("preferences") {
+                files.push(source.path.display().to_string());
+            }
+            merge(&mut merged, source.table.clone());
+        }
+        match merged.remove("preferences") {
+            Some(preferences) => Self::deserialize(preferences).map_err(|e| {
+                EnvCliError::Config(format!(
+                    "Invalid [preferences] in {}: {}",
+                    files.join(" or "),
+                    e.message()
+                ))
+            }),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+/// Merge `overlay` over `base`.
+///
+/// Tables are merged key by key. Arrays are combined: the overlay's entries
+/// first, then those of `base` the overlay does not already have. Any other
+/// value in the overlay replaces the one in `base`.
+pub fn merge(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => merge(base, overlay),
+            (Some(toml::Value::Array(base)), toml::Value::Array(mut overlay)) => {
+                for entry in base.drain(..) {
+                    if !overlay.contains(&entry) {
+                        overlay.push(entry);
+                    }
+                }
+                *base = overlay;
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let mut base: toml::Table = toml::from_str(
+            r#"
+            project = "mine"
+            [preferences]
+            format = "json"
+            [scan]
+            exclude_dirs = ["fixtures", "target"]
+            "#,
+        )
+        .unwrap();
+        let overlay: toml::Table = toml::from_str(
+            r#"
+            project = "app"
+            [scan]
+            exclude_dirs = ["target", "node_modules"]
+            include_dirs = ["src"]
+            "#,
+        )
+        .unwrap();
+
+        merge(&mut base, overlay);
+        let expected: toml::Table = toml::from_str(
+            r#"
+            project = "app"
+            [preferences]
+            format = "json"
+            [scan]
+            exclude_dirs = ["target", "node_modules", "fixtures"]
+            include_dirs = ["src"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(base, expected);
+    }
+}
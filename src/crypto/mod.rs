@@ -0,0 +1,369 @@
+//! Encryption of environment values at rest.
+//!
+//! Values are encrypted one by one, so an encrypted file keeps its keys,
+//! comments and ordering readable and produces meaningful diffs:
+//!
+//! ```text
+//! DATABASE_URL=ENC[v1:q7W2...]
+//! ```
+//!
+//! Each value is sealed with XChaCha20-Poly1305 under the 256-bit project
+//! key, using a fresh random nonce. The variable name is bound as associated
+//! data, so a sealed value cannot be moved to another key without failing
+//! authentication.
+//!
+//! The project key is read from the `ENV_CLI_KEY` environment variable
+//! (base64) or from the key file `.env/key`, which must never be committed.
+//!
+//! Projects that list `[[recipients]]` in `config.toml` use public-key
+//! encryption instead (`ENC[v2:...]`), so access follows the recipient list
+//! rather than a shared key; see [`recipients`].
+
+pub mod recipients;
+
+pub use recipients::{Identity, PublicKey};
+
+use crate::config::Project;
+use crate::env::EnvDocument;
+use crate::error::{EnvCliError, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::path::{Path, PathBuf};
+
+/// Environment variable holding the base64-encoded project key.
+pub const KEY_ENV_VAR: &str = "ENV_CLI_KEY";
+
+const PREFIX: &str = "ENC[v1:";
+const SUFFIX: &str = "]";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+
+/// Check whether a value is an encrypted `ENC[...]` token.
+pub fn is_encrypted(value: &str) -> bool {
+    (value.starts_with(PREFIX) || value.starts_with(recipients::PREFIX)) && value.ends_with(SUFFIX)
+}
+
+/// Check whether any value in a document is encrypted.
+pub fn document_is_encrypted(document: &EnvDocument) -> bool {
+    document.iter().any(|(_, value)| is_encrypted(value))
+}
+
+/// Check whether an env file contains encrypted values.
+pub fn file_is_encrypted(path: &Path) -> Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    Ok(document_is_encrypted(&EnvDocument::load(path)?))
+}
+
+/// Symmetric key used to seal the values of a project.
+pub struct ProjectKey {
+    bytes: [u8; KEY_LEN],
+}
+
+impl ProjectKey {
+    /// Generate a new random key.
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; KEY_LEN];
+        OsRng.fill_bytes(&mut bytes);
+        Self { bytes }
+    }
+
+    /// Decode a base64-encoded key.
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        let decoded = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| EnvCliError::Encryption(format!("Invalid project key: {}", e)))?;
+        let bytes: [u8; KEY_LEN] = decoded.try_into().map_err(|_| {
+            EnvCliError::Encryption(format!("Project key must be {} bytes", KEY_LEN))
+        })?;
+        Ok(Self { bytes })
+    }
+
+    /// Encode the key as base64.
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(self.bytes)
+    }
+
+    /// Load the project key from `ENV_CLI_KEY` or the project's key file.
+    ///
+    /// Returns `None` when neither is present.
+    pub fn load(project: &Project) -> Result<Option<Self>> {
+        if let Ok(encoded) = std::env::var(KEY_ENV_VAR) {
+            return Self::from_base64(&encoded).map(Some);
+        }
+
+        let path = project.key_path();
+        if path.exists() {
+            let encoded = std::fs::read_to_string(&path)?;
+            return Self::from_base64(&encoded).map(Some);
+        }
+
+        Ok(None)
+    }
+
+    /// Write the key to a file readable only by the current user.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_private(path, &self.to_base64())
+    }
+
+    /// Seal the value of `name`.
+    pub fn encrypt_value(&self, name: &str, plaintext: &str) -> Result<String> {
+        let cipher = XChaCha20Poly1305::new((&self.bytes).into());
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let ciphertext = cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| EnvCliError::Encryption(format!("Failed to encrypt '{}'", name)))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}{}", PREFIX, BASE64.encode(sealed), SUFFIX))
+    }
+
+    /// Open a value sealed by [`ProjectKey::encrypt_value`] for `name`.
+    pub fn decrypt_value(&self, name: &str, sealed: &str) -> Result<String> {
+        let invalid =
+            || EnvCliError::Encryption(format!("Malformed encrypted value for '{}'", name));
+
+        let encoded = sealed
+            .strip_prefix(PREFIX)
+            .and_then(|rest| rest.strip_suffix(SUFFIX))
+            .ok_or_else(invalid)?;
+        let bytes = BASE64.decode(encoded).map_err(|_| invalid())?;
+        if bytes.len() < NONCE_LEN {
+            return Err(invalid());
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+
+        let cipher = XChaCha20Poly1305::new((&self.bytes).into());
+        let plaintext = cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                EnvCliError::Encryption(format!(
+                    "Cannot decrypt '{}': wrong key or tampered value",
+                    name
+                ))
+            })?;
+
+        String::from_utf8(plaintext).map_err(|_| invalid())
+    }
+}
+
+/// The keys available for sealing and opening values.
+///
+/// New values are sealed for the configured recipients when there are any,
+/// and with the project key otherwise. Opening picks the key that matches the
+/// format of the value.
+pub struct Keyring {
+    project_key: Option<ProjectKey>,
+    identity: Option<Identity>,
+    recipients: Vec<PublicKey>,
+    key_path: PathBuf,
+}
+
+impl Keyring {
+    /// Load the project key, the user's identity and the recipients listed in
+    /// the project configuration, whichever exist.
+    pub fn load(project: &Project) -> Result<Self> {
+        let recipients = if project.is_initialized() {
+            project
+                .load_config()?
+                .recipients
+                .iter()
+                .map(|recipient| PublicKey::parse(&recipient.key))
+                .collect::<Result<_>>()?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            project_key: ProjectKey::load(project)?,
+            identity: Identity::load()?,
+            recipients,
+            key_path: project.key_path(),
+        })
+    }
+
+    /// Use `key` as the project key.
+    pub fn with_project_key(mut self, key: ProjectKey) -> Self {
+        self.project_key = Some(key);
+        self
+    }
+
+    /// Whether new values are sealed for recipients rather than the project key.
+    pub fn uses_recipients(&self) -> bool {
+        !self.recipients.is_empty()
+    }
+
+    /// Whether a key is available for sealing new values.
+    pub fn can_encrypt(&self) -> bool {
+        self.uses_recipients() || self.project_key.is_some()
+    }
+
+    /// Seal the value of `name`.
+    pub fn encrypt_value(&self, name: &str, plaintext: &str) -> Result<String> {
+        if self.uses_recipients() {
+            return recipients::seal(&self.recipients, name, plaintext);
+        }
+        match &self.project_key {
+            Some(key) => key.encrypt_value(name, plaintext),
+            None => Err(missing_project_key(&self.key_path)),
+        }
+    }
+
+    /// Open a sealed value of `name`.
+    pub fn decrypt_value(&self, name: &str, sealed: &str) -> Result<String> {
+        if sealed.starts_with(recipients::PREFIX) {
+            return match &self.identity {
+                Some(identity) => recipients::open(identity, name, sealed),
+                None => Err(EnvCliError::Encryption(format!(
+                    "No identity found to decrypt '{}'. Run 'env keys generate' or set {}",
+                    name,
+                    recipients::IDENTITY_ENV_VAR
+                ))),
+            };
+        }
+        match &self.project_key {
+            Some(key) => key.decrypt_value(name, sealed),
+            None => Err(missing_project_key(&self.key_path)),
+        }
+    }
+}
+
+fn missing_project_key(key_path: &Path) -> EnvCliError {
+    EnvCliError::Encryption(format!(
+        "No project key found. Set {} or create {}",
+        KEY_ENV_VAR,
+        key_path.display()
+    ))
+}
+
+/// Prepare a value for writing into `document`.
+///
+/// Documents that already hold encrypted values stay encrypted, so new and
+/// changed values are sealed before they are written.
+pub fn seal_for_document(
+    project: &Project,
+    document: &EnvDocument,
+    name: &str,
+    value: &str,
+) -> Result<String> {
+    if document_is_encrypted(document) && !is_encrypted(value) {
+        Keyring::load(project)?.encrypt_value(name, value)
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+/// Write a key to a file readable only by the current user.
+///
+/// The key goes to a new file created with that mode, which then replaces
+/// `path`, so it is never readable by others, even when `path` exists.
+fn write_private(path: &Path, encoded: &str) -> Result<()> {
+    use std::io::Write;
+
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = PathBuf::from(temp_name);
+    // Left over from an interrupted write
+    if temp_path.exists() {
+        std::fs::remove_file(&temp_path)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&temp_path)?;
+    file.write_all(format!("{}\n", encoded).as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_private_replaces_readable_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("project.key");
+        std::fs::write(&path, "old\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private(&path, "new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new\n");
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!dir.path().join("project.key.tmp").exists());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let key = ProjectKey::generate();
+        let sealed = key.encrypt_value("API_KEY", "s3cr3t value").unwrap();
+
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("s3cr3t"));
+        assert_eq!(
+            key.decrypt_value("API_KEY", &sealed).unwrap(),
+            "s3cr3t value"
+        );
+
+        // Nonces are random, so sealing twice gives different output
+        assert_ne!(
+            sealed,
+            key.encrypt_value("API_KEY", "s3cr3t value").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_rejects_wrong_key_and_moved_values() {
+        let key = ProjectKey::generate();
+        let sealed = key.encrypt_value("API_KEY", "s3cr3t").unwrap();
+
+        assert!(key.decrypt_value("OTHER_KEY", &sealed).is_err());
+        assert!(ProjectKey::generate()
+            .decrypt_value("API_KEY", &sealed)
+            .is_err());
+
+        let restored = ProjectKey::from_base64(&key.to_base64()).unwrap();
+        assert_eq!(
+            restored.decrypt_value("API_KEY", &sealed).unwrap(),
+            "s3cr3t"
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//! Public-key encryption for a list of recipients.
+//!
+//! Each value gets a fresh random data key. The value is sealed with it using
+//! XChaCha20-Poly1305, and the data key is wrapped once for every recipient:
+//!
+//! ```text
+//! ENC[v2:base64(ephemeral public key || count || wrapped keys || nonce || ciphertext)]
+//! ```
+//!
+//! A wrap key is derived with HKDF-SHA256 from the X25519 shared secret
+//! between a per-value ephemeral key and the recipient's public key. Opening
+//! a value tries the caller's identity against each wrapped key in turn, so
+//! recipients are not named in the file.
+
+use crate::error::{EnvCliError, Result};
+use crate::utils;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::Sha256;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use x25519_dalek::StaticSecret;
+
+/// Environment variable holding the base64-encoded identity.
+pub const IDENTITY_ENV_VAR: &str = "ENV_CLI_IDENTITY";
+
+pub(super) const PREFIX: &str = "ENC[v2:";
+const PUBLIC_KEY_PREFIX: &str = "envpk:";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+const WRAPPED_LEN: usize = KEY_LEN + 16;
+const WRAP_INFO: &[u8] = b"env-cli v2 key wrap";
+
+/// Default location of the user's identity file.
+///
+/// Identities belong to a person rather than a project, so they live in the
+/// user's configuration directory.
+pub fn default_identity_path() -> Option<PathBuf> {
+    utils::config_dir().map(|dir| dir.join("env-cli").join("identity"))
+}
+
+/// A recipient's public key, written as `envpk:<base64>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey([u8; KEY_LEN]);
+
+impl PublicKey {
+    /// Parse a public key in its text form.
+    pub fn parse(text: &str) -> Result<Self> {
+        let invalid = || {
+            EnvCliError::Encryption(format!(
+                "Invalid public key '{}': expected {}<base64>",
+                text, PUBLIC_KEY_PREFIX
+            ))
+        };
+
+        let encoded = text
+            .trim()
+            .strip_prefix(PUBLIC_KEY_PREFIX)
+            .ok_or_else(invalid)?;
+        let bytes: [u8; KEY_LEN] = BASE64
+            .decode(encoded)
+            .map_err(|_| invalid())?
+            .try_into()
+            .map_err(|_| invalid())?;
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", PUBLIC_KEY_PREFIX, BASE64.encode(self.0))
+    }
+}
+
+/// A user's private key, used to open values sealed for their public key.
+pub struct Identity {
+    secret: StaticSecret,
+}
+
+impl Identity {
+    /// Generate a new random identity.
+    pub fn generate() -> Self {
+        Self {
+            secret: StaticSecret::random_from_rng(OsRng),
+        }
+    }
+
+    /// Decode a base64-encoded identity.
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        let bytes: [u8; KEY_LEN] = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| EnvCliError::Encryption(format!("Invalid identity: {}", e)))?
+            .try_into()
+            .map_err(|_| EnvCliError::Encryption(format!("Identity must be {} bytes", KEY_LEN)))?;
+        Ok(Self {
+            secret: StaticSecret::from(bytes),
+        })
+    }
+
+    /// Encode the identity as base64.
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(self.secret.to_bytes())
+    }
+
+    /// The public key others add as a recipient.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(x25519_dalek::PublicKey::from(&self.secret).to_bytes())
+    }
+
+    /// Load the identity from `ENV_CLI_IDENTITY` or the identity file.
+    ///
+    /// Returns `None` when neither is present.
+    pub fn load() -> Result<Option<Self>> {
+        if let Ok(encoded) = std::env::var(IDENTITY_ENV_VAR) {
+            return Self::from_base64(&encoded).map(Some);
+        }
+
+        match default_identity_path() {
+            Some(path) if path.exists() => {
+                let encoded = std::fs::read_to_string(&path)?;
+                Self::from_base64(&encoded).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Write the identity to a file readable only by the current user.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        super::write_private(path, &self.to_base64())
+    }
+}
+
+/// Seal `plaintext` for every recipient, binding it to `name`.
+pub(super) fn seal(recipients: &[PublicKey], name: &str, plaintext: &str) -> Result<String> {
+    let failed = || EnvCliError::Encryption(format!("Failed to encrypt '{}'", name));
+    if recipients.is_empty() || recipients.len() > u8::MAX as usize {
+        return Err(EnvCliError::Encryption(format!(
+            "Values need between 1 and {} recipients",
+            u8::MAX
+        )));
+    }
+
+    let mut data_key = [0u8; KEY_LEN];
+    OsRng.fill_bytes(&mut data_key);
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let ephemeral = StaticSecret::random_from_rng(OsRng);
+    let ephemeral_public = x25519_dalek::PublicKey::from(&ephemeral).to_bytes();
+
+    let mut sealed = ephemeral_public.to_vec();
+    sealed.push(recipients.len() as u8);
+    for recipient in recipients {
+        let wrap_key = wrap_key(&ephemeral, &ephemeral_public, recipient).ok_or_else(failed)?;
+        let wrapped = XChaCha20Poly1305::new((&wrap_key).into())
+            .encrypt(XNonce::from_slice(&[0u8; NONCE_LEN]), data_key.as_slice())
+            .map_err(|_| failed())?;
+        sealed.extend_from_slice(&wrapped);
+    }
+
+    let ciphertext = XChaCha20Poly1305::new((&data_key).into())
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext.as_bytes(),
+                aad: name.as_bytes(),
+            },
+        )
+        .map_err(|_| failed())?;
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+
+    Ok(format!("{}{}]", PREFIX, BASE64.encode(sealed)))
+}
+
+/// Open a value sealed by [`seal`] with `identity`.
+pub(super) fn open(identity: &Identity, name: &str, sealed: &str) -> Result<String> {
+    let invalid = || EnvCliError::Encryption(format!("Malformed encrypted value for '{}'", name));
+
+    let encoded = sealed
+        .strip_prefix(PREFIX)
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let bytes = BASE64.decode(encoded).map_err(|_| invalid())?;
+    if bytes.len() < KEY_LEN + 1 {
+        return Err(invalid());
+    }
+
+    let ephemeral_public: [u8; KEY_LEN] = bytes[..KEY_LEN].try_into().map_err(|_| invalid())?;
+    let count = bytes[KEY_LEN] as usize;
+    let stanzas_end = KEY_LEN + 1 + count * WRAPPED_LEN;
+    if bytes.len() < stanzas_end + NONCE_LEN {
+        return Err(invalid());
+    }
+
+    let shared = identity
+        .secret
+        .diffie_hellman(&x25519_dalek::PublicKey::from(ephemeral_public));
+    let wrap_key = derive_wrap_key(shared.as_bytes(), &ephemeral_public, &identity.public_key());
+    let unwrap = XChaCha20Poly1305::new((&wrap_key).into());
+    let data_key = bytes[KEY_LEN + 1..stanzas_end]
+        .chunks(WRAPPED_LEN)
+        .find_map(|wrapped| {
+            unwrap
+                .decrypt(XNonce::from_slice(&[0u8; NONCE_LEN]), wrapped)
+                .ok()
+        })
+        .ok_or_else(|| {
+            EnvCliError::Encryption(format!(
+                "Cannot decrypt '{}': your key {} is not one of its recipients",
+                name,
+                identity.public_key()
+            ))
+        })?;
+    if data_key.len() != KEY_LEN {
+        return Err(invalid());
+    }
+
+    let (nonce, ciphertext) = bytes[stanzas_end..].split_at(NONCE_LEN);
+    let plaintext = XChaCha20Poly1305::new(data_key.as_slice().into())
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: name.as_bytes(),
+            },
+        )
+        .map_err(|_| {
+            EnvCliError::Encryption(format!("Cannot decrypt '{}': tampered value", name))
+        })?;
+
+    String::from_utf8(plaintext).map_err(|_| invalid())
+}
+
+/// Derive the key wrapping the data key for `recipient`.
+///
+/// Returns `None` for low-order public keys, whose shared secret is not
+/// secret.
+fn wrap_key(
+    ephemeral: &StaticSecret,
+    ephemeral_public: &[u8; KEY_LEN],
+    recipient: &PublicKey,
+) -> Option<[u8; KEY_LEN]> {
+    let shared = ephemeral.diffie_hellman(&x25519_dalek::PublicKey::from(recipient.0));
+    if !shared.was_contributory() {
+        return None;
+    }
+    Some(derive_wrap_key(
+        shared.as_bytes(),
+        ephemeral_public,
+        recipient,
+    ))
+}
+
+fn derive_wrap_key(
+    shared: &[u8; KEY_LEN],
+    ephemeral_public: &[u8; KEY_LEN],
+    recipient: &PublicKey,
+) -> [u8; KEY_LEN] {
+    // Binding both public keys ties each wrap key to this exchange
+    let mut salt = ephemeral_public.to_vec();
+    salt.extend_from_slice(&recipient.0);
+
+    let mut key = [0u8; KEY_LEN];
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(WRAP_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_recipient_can_open() {
+        let alice = Identity::generate();
+        let bob = Identity::generate();
+        let recipients = [alice.public_key(), bob.public_key()];
+
+        let sealed = seal(&recipients, "API_KEY", "s3cr3t").unwrap();
+        assert!(sealed.starts_with(PREFIX));
+        assert_eq!(open(&alice, "API_KEY", &sealed).unwrap(), "s3cr3t");
+        assert_eq!(open(&bob, "API_KEY", &sealed).unwrap(), "s3cr3t");
+
+        let mallory = Identity::generate();
+        assert!(open(&mallory, "API_KEY", &sealed).is_err());
+        assert!(open(&alice, "OTHER_KEY", &sealed).is_err());
+    }
+
+    #[test]
+    fn test_key_text_round_trip() {
+        let identity = Identity::generate();
+        let public = identity.public_key();
+
+        assert_eq!(PublicKey::parse(&public.to_string()).unwrap(), public);
+        assert!(PublicKey::parse("not-a-key").is_err());
+
+        let restored = Identity::from_base64(&identity.to_base64()).unwrap();
+        assert_eq!(restored.public_key(), public);
+    }
+}
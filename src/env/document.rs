@@ -0,0 +1,377 @@
+//! Lossless, editable representation of a dotenv file.
+//!
+//! [`EnvDocument`] keeps every byte of the file it was parsed from: comments,
+//! blank lines, key order, `export` prefixes, quoting style and inline
+//! comments. Entries that are not modified render exactly as they were read,
+//! so changing one key produces a one-line diff.
+
+use super::parser::{self, EnvEntry, ParseError, QuoteStyle};
+use crate::error::{EnvCliError, Result};
+use std::fmt;
+use std::path::Path;
+
+/// An editable dotenv file.
+#[derive(Debug, Clone)]
+pub struct EnvDocument {
+    nodes: Vec<Node>,
+    /// Line terminator used when new lines are added
+    newline: &'static str,
+}
+
+/// A piece of the document.
+#[derive(Debug, Clone)]
+enum Node {
+    /// Text that is not an assignment: comments, blank lines, indentation and
+    /// line terminators, kept verbatim
+    Trivia(String),
+    /// A `KEY=VALUE` assignment
+    Entry(DocEntry),
+}
+
+/// An assignment together with the text it was parsed from.
+#[derive(Debug, Clone)]
+struct DocEntry {
+    key: String,
+    value: String,
+    quote: QuoteStyle,
+    exported: bool,
+    trailing: String,
+    /// Original source text, cleared once the entry is modified
+    raw: Option<String>,
+}
+
+impl DocEntry {
+    fn from_parsed(entry: EnvEntry, source: &str) -> Self {
+        Self {
+            raw: Some(source[entry.span.clone()].to_string()),
+            key: entry.key,
+            value: entry.value,
+            quote: entry.quote,
+            exported: entry.exported,
+            trailing: entry.trailing,
+        }
+    }
+
+    fn render(&self) -> String {
+        if let Some(raw) = &self.raw {
+            return raw.clone();
+        }
+
+        format!(
+            "{}{}={}{}",
+            if self.exported { "export " } else { "" },
+            self.key,
+            render_value(&self.value, self.quote),
+            self.trailing
+        )
+    }
+}
+
+/// Render a value in the requested quoting style, falling back to double
+/// quotes when the style cannot represent it.
+fn render_value(value: &str, quote: QuoteStyle) -> String {
+    match quote {
+        QuoteStyle::Single if !value.contains('\'') => format!("'{}'", value),
+        QuoteStyle::Backtick if !value.contains('`') => format!("`{}`", value),
+        QuoteStyle::Double => parser::double_quote(value),
+        QuoteStyle::Single | QuoteStyle::Backtick | QuoteStyle::None => parser::quote_value(value),
+    }
+}
+
+impl EnvDocument {
+    /// Create an empty document.
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            newline: "\n",
+        }
+    }
+
+    /// Parse dotenv content into a document.
+    pub fn parse(content: &str) -> std::result::Result<Self, ParseError> {
+        let (bom, body) = match content.strip_prefix('\u{feff}') {
+            Some(body) => ("\u{feff}", body),
+            None => ("", content),
+        };
+
+        let mut nodes = Vec::new();
+        let mut trivia = bom.to_string();
+        let mut cursor = 0;
+
+        for entry in parser::parse(body)? {
+            trivia.push_str(&body[cursor..entry.span.start]);
+            if !trivia.is_empty() {
+                nodes.push(Node::Trivia(std::mem::take(&mut trivia)));
+            }
+            cursor = entry.span.end;
+            nodes.push(Node::Entry(DocEntry::from_parsed(entry, body)));
+        }
+
+        trivia.push_str(&body[cursor..]);
+        if !trivia.is_empty() {
+            nodes.push(Node::Trivia(trivia));
+        }
+
+        Ok(Self {
+            nodes,
+            newline: if content.contains("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            },
+        })
+    }
+
+    /// Load a document from a file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content).map_err(|e| EnvCliError::from(e).in_file(path))
+    }
+
+    /// Load a document from a file, or start an empty one if it does not exist.
+    pub fn load_or_default(path: &Path) -> Result<Self> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::new())
+        }
+    }
+
+    /// Write the document to a file, creating parent directories as needed.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        std::fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// Get the effective value of a key (the last assignment wins).
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .filter(|e| e.key == key)
+            .last()
+            .map(|e| e.value.as_str())
+    }
+
+    /// Check whether a key is assigned anywhere in the document.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries().any(|e| e.key == key)
+    }
+
+    /// Iterate over the assignments in file order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries().map(|e| (e.key.as_str(), e.value.as_str()))
+    }
+
+    /// Keys in file order, without duplicates.
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = Vec::new();
+        for entry in self.entries() {
+            if !keys.contains(&entry.key.as_str()) {
+                keys.push(&entry.key);
+            }
+        }
+        keys
+    }
+
+    /// Set a key, keeping its position, quoting style and inline comment.
+    ///
+    /// New keys are appended to the end of the file. Returns the previous value.
+    pub fn set(&mut self, key: &str, value: &str) -> Option<String> {
+        if let Some(entry) = self.entries_mut().filter(|e| e.key == key).last() {
+            if entry.value == value {
+                return Some(value.to_string());
+            }
+            entry.raw = None;
+            return Some(std::mem::replace(&mut entry.value, value.to_string()));
+        }
+
+        self.append(DocEntry {
+            key: key.to_string(),
+            value: value.to_string(),
+            quote: QuoteStyle::None,
+            exported: false,
+            trailing: String::new(),
+            raw: None,
+        });
+        None
+    }
+
+    /// Remove every assignment of a key, together with its line.
+    ///
+    /// Returns the effective value the key had.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let previous = self.get(key).map(str::to_string);
+
+        while let Some(index) = self
+            .nodes
+            .iter()
+            .position(|n| matches!(n, Node::Entry(e) if e.key == key))
+        {
+            self.nodes.remove(index);
+
+            // Drop the indentation before the entry and the line break after it
+            if let Some(Node::Trivia(text)) =
+                index.checked_sub(1).and_then(|i| self.nodes.get_mut(i))
+            {
+                let line_start = text.rfind('\n').map_or(0, |i| i + 1);
+                if text[line_start..].chars().all(|c| c == ' ' || c == '\t') {
+                    text.truncate(line_start);
+                }
+            }
+            if let Some(Node::Trivia(text)) = self.nodes.get_mut(index) {
+                let rest = text
+                    .strip_prefix("\r\n")
+                    .or_else(|| text.strip_prefix('\n'))
+                    .map(str::to_string);
+                if let Some(rest) = rest {
+                    *text = rest;
+                }
+            }
+            self.nodes
+                .retain(|n| !matches!(n, Node::Trivia(text) if text.is_empty()));
+        }
+
+        previous
+    }
+
+    /// Rewrite every assignment's value in place, keeping its quoting.
+    ///
+    /// `f` receives the key and current value and returns the replacement, or
+    /// `None` to leave the entry untouched. Returns how many entries changed.
+    pub fn update_values<F>(&mut self, mut f: F) -> Result<usize>
+    where
+        F: FnMut(&str, &str) -> Result<Option<String>>,
+    {
+        let mut changed = 0;
+        for entry in self.entries_mut() {
+            if let Some(value) = f(&entry.key, &entry.value)? {
+                if value != entry.value {
+                    entry.value = value;
+                    entry.raw = None;
+                    changed += 1;
+                }
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Append a comment line to the end of the document.
+    pub fn push_comment(&mut self, comment: &str) {
+        self.ensure_trailing_newline();
+        self.nodes
+            .push(Node::Trivia(format!("# {}{}", comment, self.newline)));
+    }
+
+    /// Append a blank line to the end of the document.
+    pub fn push_blank_line(&mut self) {
+        self.ensure_trailing_newline();
+        self.nodes.push(Node::Trivia(self.newline.to_string()));
+    }
+
+    fn append(&mut self, entry: DocEntry) {
+        self.ensure_trailing_newline();
+        self.nodes.push(Node::Entry(entry));
+        self.nodes.push(Node::Trivia(self.newline.to_string()));
+    }
+
+    fn ensure_trailing_newline(&mut self) {
+        let needs_newline = match self.nodes.last() {
+            None => false,
+            Some(Node::Trivia(text)) => !text.ends_with('\n'),
+            Some(Node::Entry(_)) => true,
+        };
+        if needs_newline {
+            self.nodes.push(Node::Trivia(self.newline.to_string()));
+        }
+    }
+
+    fn entries(&self) -> impl Iterator<Item = &DocEntry> {
+        self.nodes.iter().filter_map(|n| match n {
+            Node::Entry(e) => Some(e),
+            Node::Trivia(_) => None,
+        })
+    }
+
+    fn entries_mut(&mut self) -> impl Iterator<Item = &mut DocEntry> {
+        self.nodes.iter_mut().filter_map(|n| match n {
+            Node::Entry(e) => Some(e),
+            Node::Trivia(_) => None,
+        })
+    }
+}
+
+impl Default for EnvDocument {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for EnvDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in &self.nodes {
+            match node {
+                Node::Trivia(text) => f.write_str(text)?,
+                Node::Entry(entry) => f.write_str(&entry.render())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "# Database\nexport DB_HOST=localhost # primary\n\n  DB_PASS='s3cr3t'\nMULTI=\"a\nb\"\nURL=http://x/#frag\n";
+
+    #[test]
+    fn test_round_trip_is_lossless() {
+        let doc = EnvDocument::parse(SAMPLE).unwrap();
+        assert_eq!(doc.to_string(), SAMPLE);
+        assert_eq!(doc.keys(), vec!["DB_HOST", "DB_PASS", "MULTI", "URL"]);
+        assert_eq!(doc.get("MULTI"), Some("a\nb"));
+    }
+
+    #[test]
+    fn test_set_changes_only_one_line() {
+        let mut doc = EnvDocument::parse(SAMPLE).unwrap();
+        assert_eq!(
+            doc.set("DB_HOST", "db.internal"),
+            Some("localhost".to_string())
+        );
+        assert_eq!(doc.set("DB_PASS", "new pass"), Some("s3cr3t".to_string()));
+        assert_eq!(
+            doc.to_string(),
+            "# Database\nexport DB_HOST=db.internal # primary\n\n  DB_PASS='new pass'\nMULTI=\"a\nb\"\nURL=http://x/#frag\n"
+        );
+    }
+
+    #[test]
+    fn test_set_appends_new_keys() {
+        let mut doc = EnvDocument::parse("A=1").unwrap();
+        doc.set("B", "two words");
+        assert_eq!(doc.to_string(), "A=1\nB=\"two words\"\n");
+    }
+
+    #[test]
+    fn test_remove_drops_whole_line() {
+        let mut doc = EnvDocument::parse(SAMPLE).unwrap();
+        assert_eq!(doc.remove("DB_PASS"), Some("s3cr3t".to_string()));
+        assert_eq!(
+            doc.to_string(),
+            "# Database\nexport DB_HOST=localhost # primary\n\nMULTI=\"a\nb\"\nURL=http://x/#frag\n"
+        );
+        assert_eq!(doc.remove("MISSING"), None);
+    }
+
+    #[test]
+    fn test_crlf_is_preserved() {
+        let mut doc = EnvDocument::parse("A=1\r\n").unwrap();
+        doc.set("B", "2");
+        assert_eq!(doc.to_string(), "A=1\r\nB=2\r\n");
+    }
+}
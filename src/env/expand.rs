@@ -0,0 +1,308 @@
+//! Variable interpolation for parsed environment files.
+//!
+//! Values may reference other variables using the following forms:
+//!
+//! - `$VAR` and `${VAR}` substitute the value of `VAR`, or an empty string
+//!   when it is not set.
+//! - `${VAR:-default}` substitutes `default` when `VAR` is unset or empty.
+//!   The default may itself contain references.
+//! - `${VAR:?message}` fails with `message` when `VAR` is unset or empty.
+//! - `\$` produces a literal `$`.
+//!
+//! References are resolved against the other variables in the same file first
+//! and, when enabled, fall back to the process environment. A variable that
+//! refers to itself (`PATH=${PATH}:/opt/bin`) reads the process environment
+//! value instead of recursing. Any other circular reference is an error.
+//! Single-quoted and backtick-quoted values are literal and never expanded.
+
+use crate::error::{EnvCliError, Result};
+use std::collections::{HashMap, HashSet};
+
+/// Options controlling how references are resolved.
+#[derive(Debug, Clone)]
+pub struct ExpandOptions {
+    /// Fall back to the process environment for names not defined in the file
+    pub use_process_env: bool,
+    /// Values to read for these names instead of the process environment;
+    /// `None` reads as unset
+    pub process_env_overrides: HashMap<String, Option<String>>,
+}
+
+impl Default for ExpandOptions {
+    fn default() -> Self {
+        Self {
+            use_process_env: true,
+            process_env_overrides: HashMap::new(),
+        }
+    }
+}
+
+/// Expand every value in `variables`, skipping the keys listed in `literals`.
+pub fn expand(
+    variables: &HashMap<String, String>,
+    literals: &HashSet<String>,
+    options: ExpandOptions,
+) -> Result<HashMap<String, String>> {
+    let mut expander = Expander {
+        variables,
+        literals,
+        options,
+        resolved: HashMap::new(),
+        stack: Vec::new(),
+    };
+
+    let mut keys: Vec<&String> = variables.keys().collect();
+    keys.sort();
+    for key in keys {
+        expander.resolve(key)?;
+    }
+
+    Ok(expander.resolved)
+}
+
+/// Resolver state shared across one expansion pass.
+struct Expander<'a> {
+    variables: &'a HashMap<String, String>,
+    literals: &'a HashSet<String>,
+    options: ExpandOptions,
+    resolved: HashMap<String, String>,
+    stack: Vec<String>,
+}
+
+impl Expander<'_> {
+    fn resolve(&mut self, key: &str) -> Result<String> {
+        if let Some(value) = self.resolved.get(key) {
+            return Ok(value.clone());
+        }
+
+        if let Some(start) = self.stack.iter().position(|k| k == key) {
+            let mut chain = self.stack[start..].to_vec();
+            chain.push(key.to_string());
+            return Err(EnvCliError::Environment(format!(
+                "Circular variable reference: {}",
+                chain.join(" -> ")
+            )));
+        }
+
+        let raw = self.variables.get(key).cloned().unwrap_or_default();
+        let value = if self.literals.contains(key) {
+            raw
+        } else {
+            self.stack.push(key.to_string());
+            let expanded = self.expand_str(&raw, key);
+            self.stack.pop();
+            expanded?
+        };
+
+        self.resolved.insert(key.to_string(), value.clone());
+        Ok(value)
+    }
+
+    fn lookup(&mut self, name: &str, owner: &str) -> Result<Option<String>> {
+        if name != owner && self.variables.contains_key(name) {
+            return self.resolve(name).map(Some);
+        }
+        if self.options.use_process_env {
+            if let Some(value) = self.options.process_env_overrides.get(name) {
+                return Ok(value.clone());
+            }
+            return Ok(std::env::var(name).ok());
+        }
+        Ok(None)
+    }
+
+    fn expand_str(&mut self, raw: &str, owner: &str) -> Result<String> {
+        let chars: Vec<char> = raw.chars().collect();
+        let mut out = String::with_capacity(raw.len());
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '\\' if chars.get(i + 1) == Some(&'$') => {
+                    out.push('$');
+                    i += 2;
+                }
+                '$' if chars.get(i + 1) == Some(&'{') => {
+                    let close = find_closing_brace(&chars, i + 2).ok_or_else(|| {
+                        EnvCliError::Environment(format!(
+                            "Unterminated '${{' in value of '{}'",
+                            owner
+                        ))
+                    })?;
+                    let inner: String = chars[i + 2..close].iter().collect();
+                    out.push_str(&self.expand_braced(&inner, owner)?);
+                    i = close + 1;
+                }
+                '$' if chars.get(i + 1).map_or(false, |c| is_name_start(*c)) => {
+                    let end = chars[i + 1..]
+                        .iter()
+                        .position(|c| !is_name_char(*c))
+                        .map_or(chars.len(), |p| i + 1 + p);
+                    let name: String = chars[i + 1..end].iter().collect();
+                    out.push_str(&self.lookup(&name, owner)?.unwrap_or_default());
+                    i = end;
+                }
+                c => {
+                    out.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn expand_braced(&mut self, inner: &str, owner: &str) -> Result<String> {
+        let name_len = inner
+            .char_indices()
+            .find(|(_, c)| !is_name_char(*c))
+            .map_or(inner.len(), |(idx, _)| idx);
+        let (name, operator) = inner.split_at(name_len);
+
+        if name.is_empty() || !name.starts_with(is_name_start) {
+            return Err(EnvCliError::Environment(format!(
+                "Invalid substitution '${{{}}}' in value of '{}'",
+                inner, owner
+            )));
+        }
+
+        let value = self.lookup(name, owner)?.filter(|v| !v.is_empty());
+
+        if operator.is_empty() {
+            Ok(value.unwrap_or_default())
+        } else if let Some(default) = operator.strip_prefix(":-") {
+            match value {
+                Some(value) => Ok(value),
+                None => self.expand_str(default, owner),
+            }
+        } else if let Some(message) = operator.strip_prefix(":?") {
+            value.ok_or_else(|| {
+                let message = if message.is_empty() {
+                    "is not set".to_string()
+                } else {
+                    message.to_string()
+                };
+                EnvCliError::Environment(format!(
+                    "Variable '{}' requires '{}': {}",
+                    owner, name, message
+                ))
+            })
+        } else {
+            Err(EnvCliError::Environment(format!(
+                "Invalid substitution '${{{}}}' in value of '{}'",
+                inner, owner
+            )))
+        }
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Find the `}` closing a `${` whose body starts at `start`, allowing nesting.
+fn find_closing_brace(chars: &[char], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '$' if chars.get(i + 1) == Some(&'{') => {
+                depth += 1;
+                i += 1;
+            }
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn isolated() -> ExpandOptions {
+        ExpandOptions {
+            use_process_env: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_expands_references_and_defaults() {
+        let input = vars(&[
+            ("DB_HOST", "db"),
+            ("DB_PORT", ""),
+            (
+                "DATABASE_URL",
+                "postgres://${DB_HOST}:${DB_PORT:-5432}/$APP_NAME",
+            ),
+            ("APP_NAME", "app"),
+        ]);
+        let out = expand(&input, &HashSet::new(), isolated()).unwrap();
+        assert_eq!(out["DATABASE_URL"], "postgres://db:5432/app");
+    }
+
+    #[test]
+    fn test_nested_default_and_escape() {
+        let input = vars(&[
+            ("FALLBACK", "fb"),
+            ("A", "${MISSING:-${FALLBACK}}"),
+            ("PRICE", "\\$5 and $"),
+        ]);
+        let out = expand(&input, &HashSet::new(), isolated()).unwrap();
+        assert_eq!(out["A"], "fb");
+        assert_eq!(out["PRICE"], "$5 and $");
+    }
+
+    #[test]
+    fn test_required_reference_errors() {
+        let input = vars(&[("URL", "${HOST:?set HOST first}")]);
+        let err = expand(&input, &HashSet::new(), isolated()).unwrap_err();
+        assert!(err.to_string().contains("set HOST first"));
+    }
+
+    #[test]
+    fn test_cycles_are_detected() {
+        let input = vars(&[("A", "${B}"), ("B", "$A")]);
+        let err = expand(&input, &HashSet::new(), isolated()).unwrap_err();
+        assert!(err.to_string().contains("Circular variable reference"));
+    }
+
+    #[test]
+    fn test_literals_and_self_reference() {
+        let input = vars(&[("RAW", "${HOME}"), ("SELF", "${SELF}:x")]);
+        let literals: HashSet<String> = ["RAW".to_string()].into_iter().collect();
+        let out = expand(&input, &literals, isolated()).unwrap();
+        assert_eq!(out["RAW"], "${HOME}");
+        assert_eq!(out["SELF"], ":x");
+    }
+
+    #[test]
+    fn test_process_env_overrides() {
+        let input = vars(&[("SELF", "${SELF}:x"), ("GONE", "${HOME:-none}")]);
+        let options = ExpandOptions {
+            process_env_overrides: HashMap::from([
+                ("SELF".to_string(), Some("before".to_string())),
+                ("HOME".to_string(), None),
+            ]),
+            ..Default::default()
+        };
+        let out = expand(&input, &HashSet::new(), options).unwrap();
+        assert_eq!(out["SELF"], "before:x");
+        assert_eq!(out["GONE"], "none");
+    }
+}
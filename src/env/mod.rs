@@ -2,22 +2,148 @@
 //!
 //! This module provides utilities for working with environment variables.
 
+pub mod document;
+pub mod expand;
+pub mod parser;
+
+pub use document::EnvDocument;
+pub use expand::ExpandOptions;
+pub use parser::{EnvEntry, ParseError, QuoteStyle};
+
+use crate::config::Project;
+use crate::crypto::{self, Keyring};
 use crate::error::{EnvCliError, Result};
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+/// One source of values in an environment's inheritance chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Layer {
+    /// Environment the values belong to
+    pub environment: String,
+    /// File the values are read from; `config.toml` for inline values
+    pub file: PathBuf,
+    pub kind: LayerKind,
+    /// The values of an [`LayerKind::Inline`] layer
+    #[serde(skip)]
+    pub variables: BTreeMap<String, String>,
+}
+
+/// Where a layer's values are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayerKind {
+    /// The environment's `variables` table in `config.toml`
+    Inline,
+    /// The environment's file
+    File,
+    /// The uncommitted `.local` override file next to it
+    Local,
+}
+
+impl std::fmt::Display for Layer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            LayerKind::Inline => write!(f, "{} (config)", self.environment),
+            LayerKind::File => f.write_str(&self.environment),
+            LayerKind::Local => write!(f, "{} (local)", self.environment),
+        }
+    }
+}
 
 /// Environment variable manager.
+#[derive(Debug, Clone)]
 pub struct EnvManager {
     variables: HashMap<String, String>,
+    /// Keys whose values were quoted literally and must not be expanded
+    literals: HashSet<String>,
+    /// Files loaded by [`EnvManager::load_layers`], lowest precedence first
+    layers: Vec<Layer>,
+    /// Every value each key was given, as (index into `layers`, value)
+    history: HashMap<String, Vec<(usize, String)>>,
+    /// Project whose keys decrypt encrypted values
+    project: Project,
 }
 
 impl EnvManager {
-    /// Create a new environment manager.
+    /// Create a new environment manager for the project in the working
+    /// directory.
     pub fn new() -> Self {
+        Self::for_project(&Project::default())
+    }
+
+    /// Create a new environment manager that decrypts values with
+    /// `project`'s keys.
+    pub fn for_project(project: &Project) -> Self {
         Self {
             variables: HashMap::new(),
+            literals: HashSet::new(),
+            layers: Vec::new(),
+            history: HashMap::new(),
+            project: project.clone(),
         }
     }
 
+    /// Load a stack of layers, each overriding the keys of those before it.
+    ///
+    /// Missing `.local` layers are skipped. So is a missing environment file
+    /// when layers come before it, from a parent environment or inline
+    /// variables; an environment with nothing but its file needs that file.
+    /// The layer each value came from is kept; see [`EnvManager::origin`]
+    /// and [`EnvManager::history`].
+    pub fn load_layers(&mut self, layers: Vec<Layer>) -> Result<()> {
+        for (position, layer) in layers.into_iter().enumerate() {
+            let mut loaded = EnvManager::for_project(&self.project);
+            match layer.kind {
+                LayerKind::Inline => {
+                    loaded.variables = layer.variables.clone().into_iter().collect()
+                }
+                LayerKind::Local if !layer.file.exists() => continue,
+                LayerKind::File if position > 0 && !layer.file.exists() => continue,
+                LayerKind::File | LayerKind::Local => loaded.load_from_file(&layer.file)?,
+            }
+
+            let index = self.layers.len();
+            for (key, value) in loaded.variables {
+                if loaded.literals.contains(&key) {
+                    self.literals.insert(key.clone());
+                } else {
+                    self.literals.remove(&key);
+                }
+                self.history
+                    .entry(key.clone())
+                    .or_default()
+                    .push((index, value.clone()));
+                self.variables.insert(key, value);
+            }
+            self.layers.push(layer);
+        }
+        Ok(())
+    }
+
+    /// The layers loaded by [`EnvManager::load_layers`], lowest precedence
+    /// first.
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// The layer a key's current value was read from.
+    pub fn origin(&self, key: &str) -> Option<&Layer> {
+        self.history(key).last().map(|(layer, _)| *layer)
+    }
+
+    /// Every value the layers gave a key, as written and lowest precedence
+    /// first. The last one is in effect.
+    pub fn history(&self, key: &str) -> Vec<(&Layer, &str)> {
+        self.history
+            .get(key)
+            .into_iter()
+            .flatten()
+            .map(|(index, value)| (&self.layers[*index], value.as_str()))
+            .collect()
+    }
+
     /// Load environment variables from current process.
     pub fn load_current(&mut self) -> Result<()> {
         for (key, value) in std::env::vars() {
@@ -36,21 +162,46 @@ impl EnvManager {
         }
 
         let content = std::fs::read_to_string(path)?;
+        self.load_from_str(&content).map_err(|e| e.in_file(path))
+    }
 
-        for line in content.lines() {
-            let line = line.trim();
+    /// Load environment variables from dotenv content.
+    ///
+    /// See [`parser`] for the accepted grammar. Encrypted `ENC[...]` values
+    /// are decrypted in memory with the available keys; see [`crate::crypto`].
+    pub fn load_from_str(&mut self, content: &str) -> Result<()> {
+        let entries = parser::parse(content)?;
 
-            // Skip empty lines and comments
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
+        let keyring = if entries.iter().any(|e| crypto::is_encrypted(&e.value)) {
+            Some(Keyring::load(&self.project)?)
+        } else {
+            None
+        };
 
-            // Parse KEY=VALUE format, handling quotes and exports
-            if let Some((key, value)) = parse_env_line(line)? {
-                self.variables.insert(key, value);
+        for entry in entries {
+            if matches!(entry.quote, QuoteStyle::Single | QuoteStyle::Backtick) {
+                self.literals.insert(entry.key.clone());
+            } else {
+                self.literals.remove(&entry.key);
             }
+            let value = match &keyring {
+                Some(keyring) if crypto::is_encrypted(&entry.value) => {
+                    keyring.decrypt_value(&entry.key, &entry.value)?
+                }
+                _ => entry.value,
+            };
+            self.variables.insert(entry.key, value);
         }
+        Ok(())
+    }
 
+    /// Expand `${VAR}` style references in every value.
+    ///
+    /// See [`expand`] for the supported syntax.
+    pub fn expand(&mut self, options: ExpandOptions) -> Result<()> {
+        self.variables = expand::expand(&self.variables, &self.literals, options)?;
+        // Expanded values are final; a second pass must not touch them again
+        self.literals = self.variables.keys().cloned().collect();
         Ok(())
     }
 
@@ -61,11 +212,15 @@ impl EnvManager {
 
     /// Set an environment variable.
     pub fn set(&mut self, key: String, value: String) {
+        self.literals.remove(&key);
+        self.history.remove(&key);
         self.variables.insert(key, value);
     }
 
     /// Remove an environment variable.
     pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.literals.remove(key);
+        self.history.remove(key);
         self.variables.remove(key)
     }
 
@@ -74,44 +229,6 @@ impl EnvManager {
         self.variables.iter()
     }
 
-    /// Save environment variables to file.
-    pub fn save_to_file(&self, path: &std::path::Path) -> Result<()> {
-        // Create parent directory if it doesn't exist
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        let mut content = String::new();
-
-        // Add header comment
-        content.push_str("# Environment variables\n");
-        content.push_str("# Generated by env-cli\n\n");
-
-        // Sort variables for consistent output
-        let mut sorted_vars: Vec<_> = self.variables.iter().collect();
-        sorted_vars.sort_by_key(|(k, _)| *k);
-
-        for (key, value) in sorted_vars {
-            // Quote values that contain spaces, special characters, or are empty
-            let formatted_value = if value.is_empty()
-                || value.contains(' ')
-                || value.contains('\n')
-                || value.contains('\t')
-                || value.contains('"')
-                || value.contains('\'')
-            {
-                format!("\"{}\"", value.replace('"', "\\\""))
-            } else {
-                value.clone()
-            };
-
-            content.push_str(&format!("{}={}\n", key, formatted_value));
-        }
-
-        std::fs::write(path, content)?;
-        Ok(())
-    }
-
     /// Validate environment variables.
     pub fn validate(&self, required: &[String]) -> Vec<String> {
         let mut missing = Vec::new();
@@ -151,10 +268,16 @@ pub struct EnvUsage {
     pub files: Vec<String>,
     /// Line numbers in each file
     pub lines: Vec<usize>,
+    /// Column of each usage, 1-based
+    pub columns: Vec<usize>,
+    /// Excerpt of the code around each usage
+    pub contexts: Vec<String>,
     /// Whether it's required
     pub required: bool,
     /// Description (if available)
     pub description: Option<String>,
+    /// Default value (if declared)
+    pub default: Option<String>,
 }
 
 impl EnvUsage {
@@ -164,15 +287,20 @@ impl EnvUsage {
             name,
             files: Vec::new(),
             lines: Vec::new(),
+            columns: Vec::new(),
+            contexts: Vec::new(),
             required: false,
             description: None,
+            default: None,
         }
     }
 
     /// Add a usage location.
-    pub fn add_usage(&mut self, file: String, line: usize) {
+    pub fn add_usage(&mut self, file: String, line: usize, column: usize, context: String) {
         self.files.push(file);
         self.lines.push(line);
+        self.columns.push(column);
+        self.contexts.push(context);
     }
 
     /// Mark as required.
@@ -184,75 +312,9 @@ impl EnvUsage {
     pub fn set_description(&mut self, description: String) {
         self.description = Some(description);
     }
-}
-
-/// Parse a single line from a .env file.
-/// Handles various formats including: KEY=VALUE, KEY="VALUE", export KEY=VALUE, etc.
-fn parse_env_line(line: &str) -> Result<Option<(String, String)>> {
-    let line = line.trim();
-
-    // Remove 'export ' prefix if present
-    let line = if line.starts_with("export ") {
-        &line[7..] // Remove "export " (7 characters)
-    } else {
-        line
-    };
-
-    // Find the first '=' character
-    if let Some(eq_pos) = line.find('=') {
-        let key = line[..eq_pos].trim().to_string();
-        let mut value = line[eq_pos + 1..].trim().to_string();
-
-        // Validate key format (should be alphanumeric, underscore, and not start with digit)
-        if !is_valid_env_key(&key) {
-            return Err(EnvCliError::InvalidFormat(format!(
-                "Invalid environment variable key: '{}'",
-                key
-            )));
-        }
-
-        // Handle quoted values
-        if (value.starts_with('"') && value.ends_with('"'))
-            || (value.starts_with('\'') && value.ends_with('\''))
-        {
-            // Remove quotes and unescape
-            value = value[1..value.len() - 1].to_string();
-            value = unescape_string(&value);
-        } else {
-            // Handle unquoted values - remove any trailing comments
-            if let Some(comment_pos) = value.find('#') {
-                value = value[..comment_pos].trim().to_string();
-            }
-        }
-
-        Ok(Some((key, value)))
-    } else {
-        // Line doesn't contain '=', skip it
-        Ok(None)
-    }
-}
-
-/// Check if a string is a valid environment variable key.
-fn is_valid_env_key(key: &str) -> bool {
-    if key.is_empty() {
-        return false;
-    }
 
-    // First character must be a letter or underscore
-    if !key.chars().next().unwrap().is_alphabetic() && key.chars().next().unwrap() != '_' {
-        return false;
+    /// Set default value.
+    pub fn set_default(&mut self, default: String) {
+        self.default = Some(default);
     }
-
-    // All characters must be alphanumeric or underscore
-    key.chars().all(|c| c.is_alphanumeric() || c == '_')
-}
-
-/// Unescape escape sequences in a string.
-fn unescape_string(s: &str) -> String {
-    s.replace("\\\"", "\"")
-        .replace("\\'", "'")
-        .replace("\\n", "\n")
-        .replace("\\t", "\t")
-        .replace("\\r", "\r")
-        .replace("\\\\", "\\")
 }
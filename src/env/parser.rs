@@ -0,0 +1,563 @@
+//! Dotenv file grammar.
+//!
+//! The parser accepts the following grammar, one assignment per logical line:
+//!
+//! ```text
+//! file        = { blank | comment | assignment }
+//! comment     = "#" { any } EOL
+//! assignment  = [ "export" WS ] KEY [ WS ] "=" [ WS ] value [ WS ] [ comment ] EOL
+//! KEY         = ( ALPHA | "_" ) { ALNUM | "_" }
+//! value       = double | single | backtick | unquoted
+//! ```
+//!
+//! - `double` values are wrapped in `"` and may span several lines. The escapes
+//!   `\n`, `\r`, `\t`, `\"` and `\\` are decoded; any other backslash sequence
+//!   (for example `\$`) is kept verbatim.
+//! - `single` values are wrapped in `'` and `backtick` values in `` ` ``. Both may
+//!   span lines and are taken literally, with no escape processing.
+//! - `unquoted` values run to the end of the line and have trailing whitespace
+//!   removed. A `#` only starts a comment when it follows whitespace, so
+//!   `URL=http://host/#frag` keeps its fragment.
+//!
+//! Lines containing a bare key with no `=` are ignored. Any other deviation is
+//! reported as a [`ParseError`] carrying the 1-based line and column.
+
+use crate::error::{EnvCliError, Span};
+use std::fmt;
+use std::ops::Range;
+
+/// Quoting style used for a value in a dotenv file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// Bare value running to the end of the line
+    None,
+    /// `'literal'`
+    Single,
+    /// `"escaped"`
+    Double,
+    /// `` `literal` ``
+    Backtick,
+}
+
+/// A single assignment parsed from a dotenv file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvEntry {
+    /// Variable name
+    pub key: String,
+    /// Decoded value
+    pub value: String,
+    /// Quoting style the value was written with
+    pub quote: QuoteStyle,
+    /// Whether the assignment was prefixed with `export`
+    pub exported: bool,
+    /// Line on which the assignment starts (1-based)
+    pub line: usize,
+    /// Byte range of the assignment, from the key to the end of its last line
+    pub span: Range<usize>,
+    /// Source text between the end of the value and the end of the line,
+    /// such as whitespace and an inline comment
+    pub trailing: String,
+}
+
+/// Error raised when dotenv content does not follow the grammar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line of the offending character (1-based)
+    pub line: usize,
+    /// Column of the offending character (1-based)
+    pub column: usize,
+    /// Description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for EnvCliError {
+    fn from(err: ParseError) -> Self {
+        EnvCliError::Syntax {
+            message: err.message,
+            span: Span {
+                file: None,
+                line: err.line,
+                column: err.column,
+            },
+        }
+    }
+}
+
+/// Parse dotenv content into its assignments, in file order.
+pub fn parse(content: &str) -> std::result::Result<Vec<EnvEntry>, ParseError> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    Parser::new(content).parse()
+}
+
+/// Check if a string is a valid environment variable key.
+pub fn is_valid_env_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(first) if first.is_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// Render a value so that [`parse`] and expansion read it back unchanged.
+///
+/// Plain values are written bare. Values with `$` are single-quoted so they
+/// are not expanded, unless they also contain single quotes or line breaks;
+/// those and anything containing whitespace, quotes, `#` or backslashes are
+/// double-quoted and escaped with [`double_quote`].
+pub fn quote_value(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '#' | '\\' | '$'));
+
+    if !needs_quotes {
+        return value.to_string();
+    }
+    if value.contains('$') && !value.contains(['\'', '\n', '\r']) {
+        return format!("'{}'", value);
+    }
+    double_quote(value)
+}
+
+/// Double-quote and escape a value. `$` is written as `\$`, which expansion
+/// reads as a literal dollar sign.
+pub fn double_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '$' => quoted.push_str("\\$"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Character cursor that tracks line, column and byte positions.
+struct Parser<'a> {
+    source: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+    byte: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            chars: source.chars().collect(),
+            pos: 0,
+            byte: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        self.byte += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, line: usize, column: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    fn at_line_end(&self) -> bool {
+        match self.peek() {
+            None | Some('\n') => true,
+            Some('\r') => self.peek_at(1).map_or(true, |c| c == '\n'),
+            _ => false,
+        }
+    }
+
+    /// Skip spaces and tabs, returning whether anything was skipped.
+    fn skip_blanks(&mut self) -> bool {
+        let start = self.pos;
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.bump();
+        }
+        self.pos != start
+    }
+
+    fn skip_to_line_end(&mut self) {
+        while !self.at_line_end() {
+            self.bump();
+        }
+    }
+
+    fn consume_line_end(&mut self) {
+        if self.peek() == Some('\r') {
+            self.bump();
+        }
+        if self.peek() == Some('\n') {
+            self.bump();
+        }
+    }
+
+    fn read_word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek() {
+            if c == '=' || c == '#' || c.is_whitespace() {
+                break;
+            }
+            word.push(c);
+            self.bump();
+        }
+        word
+    }
+
+    fn parse(mut self) -> std::result::Result<Vec<EnvEntry>, ParseError> {
+        let mut entries = Vec::new();
+
+        loop {
+            self.skip_blanks();
+            match self.peek() {
+                None => break,
+                Some('#') => self.skip_to_line_end(),
+                _ if self.at_line_end() => {}
+                _ => {
+                    if let Some(entry) = self.parse_assignment()? {
+                        entries.push(entry);
+                    }
+                }
+            }
+            self.consume_line_end();
+        }
+
+        Ok(entries)
+    }
+
+    fn parse_assignment(&mut self) -> std::result::Result<Option<EnvEntry>, ParseError> {
+        let line = self.line;
+        let start = self.byte;
+        let mut key_column = self.column;
+        let mut key = self.read_word();
+        let mut exported = false;
+
+        if key == "export" && matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.skip_blanks();
+            if !self.at_line_end() && self.peek() != Some('=') {
+                exported = true;
+                key_column = self.column;
+                key = self.read_word();
+            }
+        }
+
+        self.skip_blanks();
+        if self.at_line_end() {
+            // A bare key without a value carries no assignment
+            return Ok(None);
+        }
+        if self.peek() != Some('=') {
+            return Err(self.error(
+                self.line,
+                self.column,
+                format!("expected '=' after key '{}'", key),
+            ));
+        }
+        if !is_valid_env_key(&key) {
+            return Err(self.error(
+                line,
+                key_column,
+                format!("invalid environment variable key: '{}'", key),
+            ));
+        }
+        self.bump();
+        let after_equals = self.byte;
+
+        let spaced = self.skip_blanks();
+        let (value, quote) = match self.peek() {
+            Some('"') => (self.parse_double_quoted()?, QuoteStyle::Double),
+            Some('\'') => (self.parse_literal('\'', "single")?, QuoteStyle::Single),
+            Some('`') => (self.parse_literal('`', "backtick")?, QuoteStyle::Backtick),
+            _ => (String::new(), QuoteStyle::None),
+        };
+        let (value, value_end) = if quote == QuoteStyle::None {
+            self.parse_unquoted(spaced, after_equals)
+        } else {
+            (value, self.byte)
+        };
+
+        if quote != QuoteStyle::None {
+            self.skip_blanks();
+            match self.peek() {
+                Some('#') => self.skip_to_line_end(),
+                _ if self.at_line_end() => {}
+                Some(c) => {
+                    return Err(self.error(
+                        self.line,
+                        self.column,
+                        format!("unexpected character '{}' after closing quote", c),
+                    ))
+                }
+                None => {}
+            }
+        }
+
+        let end = self.byte;
+        Ok(Some(EnvEntry {
+            key,
+            value,
+            quote,
+            exported,
+            line,
+            span: start..end,
+            trailing: self.source[value_end..end].to_string(),
+        }))
+    }
+
+    /// Read a bare value, returning it along with the byte offset where it ends.
+    fn parse_unquoted(&mut self, mut after_blank: bool, start: usize) -> (String, usize) {
+        let mut value = String::new();
+        let mut end = start;
+        while !self.at_line_end() {
+            let c = self.peek().unwrap_or_default();
+            if c == '#' && after_blank {
+                self.skip_to_line_end();
+                break;
+            }
+            after_blank = c == ' ' || c == '\t';
+            value.push(c);
+            self.bump();
+            if !after_blank {
+                end = self.byte;
+            }
+        }
+        (value.trim_end().to_string(), end)
+    }
+
+    fn parse_double_quoted(&mut self) -> std::result::Result<String, ParseError> {
+        let (open_line, open_column) = (self.line, self.column);
+        self.bump();
+
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                None => {
+                    return Err(self.error(
+                        open_line,
+                        open_column,
+                        "unterminated double-quoted value",
+                    ))
+                }
+                Some('"') => return Ok(value),
+                Some('\\') => match self.bump() {
+                    Some('n') => value.push('\n'),
+                    Some('r') => value.push('\r'),
+                    Some('t') => value.push('\t'),
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    Some(other) => {
+                        value.push('\\');
+                        value.push(other);
+                    }
+                    None => {
+                        return Err(self.error(
+                            open_line,
+                            open_column,
+                            "unterminated double-quoted value",
+                        ))
+                    }
+                },
+                Some('\r') if self.peek() == Some('\n') => {}
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn parse_literal(
+        &mut self,
+        delimiter: char,
+        name: &str,
+    ) -> std::result::Result<String, ParseError> {
+        let (open_line, open_column) = (self.line, self.column);
+        self.bump();
+
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                None => {
+                    return Err(self.error(
+                        open_line,
+                        open_column,
+                        format!("unterminated {}-quoted value", name),
+                    ))
+                }
+                Some(c) if c == delimiter => return Ok(value),
+                Some('\r') if self.peek() == Some('\n') => {}
+                Some(c) => value.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(content: &str) -> Vec<(String, String)> {
+        parse(content)
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.key, e.value))
+            .collect()
+    }
+
+    #[test]
+    fn test_basic_assignments() {
+        let parsed = pairs("# comment\n\nA=1\nexport B = two\n  C=\n");
+        assert_eq!(
+            parsed,
+            vec![
+                ("A".to_string(), "1".to_string()),
+                ("B".to_string(), "two".to_string()),
+                ("C".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline_comments_require_whitespace() {
+        let parsed = pairs("URL=http://x/#frag\nPORT=80 # web\nEMPTY= # nothing\nHASH=#x\n");
+        assert_eq!(parsed[0].1, "http://x/#frag");
+        assert_eq!(parsed[1].1, "80");
+        assert_eq!(parsed[2].1, "");
+        assert_eq!(parsed[3].1, "#x");
+    }
+
+    #[test]
+    fn test_multiline_double_quoted() {
+        let parsed = pairs("KEY=\"-----BEGIN-----\nabc\n-----END-----\"\nNEXT=1\n");
+        assert_eq!(parsed[0].1, "-----BEGIN-----\nabc\n-----END-----");
+        assert_eq!(parsed[1], ("NEXT".to_string(), "1".to_string()));
+    }
+
+    #[test]
+    fn test_escapes_only_in_double_quotes() {
+        let parsed = pairs("D=\"a\\nb \\\"q\\\" \\$HOME\"\nS='a\\nb'\nB=`it's \"raw\"`\n");
+        assert_eq!(parsed[0].1, "a\nb \"q\" \\$HOME");
+        assert_eq!(parsed[1].1, "a\\nb");
+        assert_eq!(parsed[2].1, "it's \"raw\"");
+    }
+
+    #[test]
+    fn test_quote_style_and_export_recorded() {
+        let entries = parse("export A='x'\nB=\"y\" # trailing\nC=z\n").unwrap();
+        assert!(entries[0].exported);
+        assert_eq!(entries[0].quote, QuoteStyle::Single);
+        assert_eq!(entries[1].quote, QuoteStyle::Double);
+        assert_eq!(entries[2].quote, QuoteStyle::None);
+        assert_eq!(entries[2].line, 3);
+    }
+
+    #[test]
+    fn test_unterminated_quote_reports_position() {
+        let err = parse("A=1\nB=\"open\nstill open\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert!(err.message.contains("unterminated double-quoted"));
+
+        let err = parse("C='nope").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 3));
+    }
+
+    #[test]
+    fn test_invalid_syntax_errors() {
+        let err = parse("BAD-KEY=1\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 1));
+
+        let err = parse("A=\"x\" y\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 7));
+    }
+
+    #[test]
+    fn test_quote_value_round_trips() {
+        for value in [
+            "plain",
+            "",
+            "with space",
+            "a#b",
+            "line\nbreak",
+            "q\"uo'te",
+            "back\\slash",
+        ] {
+            let content = format!("K={}\n", quote_value(value));
+            assert_eq!(pairs(&content)[0].1, value, "round trip of {:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_quote_value_survives_expansion() {
+        use crate::env::expand::{expand, ExpandOptions};
+        use std::collections::{HashMap, HashSet};
+
+        for value in [
+            "pa$word",
+            "${HOME}",
+            "$",
+            "it's $5",
+            "\\$HOME",
+            "two\nlines $X",
+            "\\$'",
+        ] {
+            let content = format!("K={}\n", quote_value(value));
+            let entry = parse(&content).unwrap().remove(0);
+            let mut literals = HashSet::new();
+            if matches!(entry.quote, QuoteStyle::Single | QuoteStyle::Backtick) {
+                literals.insert(entry.key.clone());
+            }
+            let variables = HashMap::from([(entry.key, entry.value)]);
+            let options = ExpandOptions {
+                use_process_env: false,
+                ..Default::default()
+            };
+            let expanded = expand(&variables, &literals, options).unwrap();
+            assert_eq!(
+                expanded["K"], value,
+                "round trip of {:?} via {}",
+                value, content
+            );
+        }
+    }
+}
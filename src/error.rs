@@ -1,12 +1,64 @@
 //! Error types for the env-cli application.
 //!
 //! This module defines custom error types and provides error handling utilities.
+//!
+//! Every error has a stable code, a process exit status and usually a help
+//! hint. Exit statuses group the errors by category:
+//!
+//! | Status | Category | Codes |
+//! |--------|----------|-------|
+//! | 1 | A check failed | ENV010 |
+//! | 2 | Usage error | ENV007 |
+//! | 3 | Project or configuration error | ENV001–ENV005 |
+//! | 4 | Environment error | ENV006 |
+//! | 5 | I/O error | ENV020, ENV021 |
+//! | 6 | Encryption error | ENV030 |
+//! | 7 | Command error | ENV040 |
+//! | 8 | Internal error | ENV050, ENV051 |
 
+use serde::Serialize;
 use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A position in a file that an error points at.
+///
+/// Lines and columns are 1-based. The file is unknown when the text was
+/// not read from one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Span {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    /// Locate a byte offset into `content`.
+    pub fn at_offset(content: &str, offset: usize) -> Self {
+        let before = &content[..offset.min(content.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Span {
+            file: None,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}:{}", file.display(), self.line, self.column),
+            None => write!(f, "line {}, column {}", self.line, self.column),
+        }
+    }
+}
 
 /// Custom error type for env-cli operations.
 #[derive(Debug)]
 pub enum EnvCliError {
+    /// The current directory is not an env-cli project
+    NotInitialized,
     /// Configuration-related errors
     Config(String),
     /// Environment variable errors
@@ -21,6 +73,8 @@ pub enum EnvCliError {
     Validation(String),
     /// Invalid format errors
     InvalidFormat(String),
+    /// Syntax errors at a known position in an environment or config file
+    Syntax { message: String, span: Span },
     /// Already initialized error
     AlreadyInitialized(String),
     /// Invalid argument errors
@@ -29,11 +83,14 @@ pub enum EnvCliError {
     Io(std::io::Error),
     /// Serialization errors
     Serialization(String),
+    /// Encryption and decryption errors
+    Encryption(String),
 }
 
 impl fmt::Display for EnvCliError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            EnvCliError::NotInitialized => write!(f, "Not an env-cli project"),
             EnvCliError::Config(msg) => write!(f, "Configuration error: {}", msg),
             EnvCliError::Environment(msg) => write!(f, "Environment error: {}", msg),
             EnvCliError::FileSystem(msg) => write!(f, "File system error: {}", msg),
@@ -41,16 +98,101 @@ impl fmt::Display for EnvCliError {
             EnvCliError::Scan(msg) => write!(f, "Scanning error: {}", msg),
             EnvCliError::Validation(msg) => write!(f, "Validation error: {}", msg),
             EnvCliError::InvalidFormat(msg) => write!(f, "Invalid format: {}", msg),
+            EnvCliError::Syntax { message, span } => {
+                write!(f, "Syntax error: {}: {}", span, message)
+            }
             EnvCliError::AlreadyInitialized(msg) => write!(f, "Already initialized: {}", msg),
             EnvCliError::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
             EnvCliError::Io(err) => write!(f, "IO error: {}", err),
             EnvCliError::Serialization(msg) => write!(f, "Serialization error: {}", msg),
+            EnvCliError::Encryption(msg) => write!(f, "Encryption error: {}", msg),
         }
     }
 }
 
 impl std::error::Error for EnvCliError {}
 
+impl EnvCliError {
+    /// Stable identifier of the kind of error, such as `ENV001`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            EnvCliError::NotInitialized => "ENV001",
+            EnvCliError::AlreadyInitialized(_) => "ENV002",
+            EnvCliError::Config(_) => "ENV003",
+            EnvCliError::Syntax { .. } => "ENV004",
+            EnvCliError::InvalidFormat(_) => "ENV005",
+            EnvCliError::Environment(_) => "ENV006",
+            EnvCliError::InvalidArgument(_) => "ENV007",
+            EnvCliError::Validation(_) => "ENV010",
+            EnvCliError::FileSystem(_) => "ENV020",
+            EnvCliError::Io(_) => "ENV021",
+            EnvCliError::Encryption(_) => "ENV030",
+            EnvCliError::Command(_) => "ENV040",
+            EnvCliError::Scan(_) => "ENV050",
+            EnvCliError::Serialization(_) => "ENV051",
+        }
+    }
+
+    /// Process exit status for the error's category; see the module docs.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            EnvCliError::Validation(_) => 1,
+            EnvCliError::InvalidArgument(_) => 2,
+            EnvCliError::NotInitialized
+            | EnvCliError::AlreadyInitialized(_)
+            | EnvCliError::Config(_)
+            | EnvCliError::Syntax { .. }
+            | EnvCliError::InvalidFormat(_) => 3,
+            EnvCliError::Environment(_) => 4,
+            EnvCliError::FileSystem(_) | EnvCliError::Io(_) => 5,
+            EnvCliError::Encryption(_) => 6,
+            EnvCliError::Command(_) => 7,
+            EnvCliError::Scan(_) | EnvCliError::Serialization(_) => 8,
+        }
+    }
+
+    /// What the user can do about the error.
+    pub fn help(&self) -> Option<&'static str> {
+        Some(match self {
+            EnvCliError::NotInitialized => "Run 'env init' to set up the project.",
+            EnvCliError::AlreadyInitialized(_) => "Use --force to reinitialize.",
+            EnvCliError::Config(_) => "Check the settings in .env/config.toml.",
+            EnvCliError::Syntax { .. } | EnvCliError::InvalidFormat(_) => {
+                "Fix the file at the position shown."
+            }
+            EnvCliError::Environment(_) => {
+                "Run 'env status' to see the configured environments and the current one."
+            }
+            EnvCliError::InvalidArgument(_) => "Run the command with --help to see its options.",
+            EnvCliError::Validation(_) => "The output above lists what failed.",
+            EnvCliError::FileSystem(_) | EnvCliError::Io(_) => {
+                "Check that the path exists and that you can read and write it."
+            }
+            EnvCliError::Encryption(_) => {
+                "Make sure the project key (.env/key or ENV_CLI_KEY) or your identity can decrypt the values."
+            }
+            EnvCliError::Command(_) => "Check that the program exists and is on your PATH.",
+            EnvCliError::Scan(_) | EnvCliError::Serialization(_) => return None,
+        })
+    }
+
+    /// Where in a file the error is, when known.
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            EnvCliError::Syntax { span, .. } => Some(span),
+            _ => None,
+        }
+    }
+
+    /// Record the file a syntax error was found in.
+    pub fn in_file(mut self, path: &Path) -> Self {
+        if let EnvCliError::Syntax { span, .. } = &mut self {
+            span.file = Some(path.to_path_buf());
+        }
+        self
+    }
+}
+
 impl From<std::io::Error> for EnvCliError {
     fn from(err: std::io::Error) -> Self {
         EnvCliError::Io(err)
@@ -116,3 +258,32 @@ where
         self.map_err(|e| EnvCliError::Config(format!("{}: {}", msg, e.into())))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_span_at_offset() {
+        let content = "a = 1\nname = \"é\" x\n";
+        let span = Span::at_offset(content, content.find('x').unwrap());
+        assert_eq!((span.line, span.column), (2, 12));
+        assert_eq!(Span::at_offset(content, 0).line, 1);
+        assert_eq!(span.to_string(), "line 2, column 12");
+    }
+
+    #[test]
+    fn test_parse_errors_point_into_the_file() {
+        let err: EnvCliError = crate::env::parser::parse("A=1\nB=\"open\n")
+            .unwrap_err()
+            .into();
+        let err = err.in_file(Path::new(".env/environments/dev.env"));
+
+        assert_eq!(err.code(), "ENV004");
+        assert_eq!(err.exit_code(), 3);
+        assert_eq!(
+            err.span().unwrap().to_string(),
+            ".env/environments/dev.env:2:3"
+        );
+    }
+}
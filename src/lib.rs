@@ -37,9 +37,11 @@
 pub mod cli;
 pub mod commands;
 pub mod config;
+pub mod crypto;
 pub mod env;
 pub mod error;
 pub mod plugins;
+pub mod report;
 pub mod scan;
 pub mod sync;
 pub mod utils;
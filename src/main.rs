@@ -5,20 +5,84 @@
 #[cfg(not(test))]
 use clap::Parser;
 #[cfg(not(test))]
-use env_cli::cli::Cli;
+use env_cli::cli::{Cli, Commands, OutputFormat};
 #[cfg(not(test))]
 use env_cli::commands::execute_command;
 #[cfg(not(test))]
-use env_cli::error::Result;
+use env_cli::config::{keys, Preferences, Project};
+#[cfg(not(test))]
+use env_cli::error::{EnvCliError, Result};
+#[cfg(not(test))]
+use env_cli::report::Output;
+#[cfg(not(test))]
+use std::process::ExitCode;
 
 #[cfg(not(test))]
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     // Parse command line arguments
     let cli = Cli::parse();
 
+    let mut output = Output::new(
+        cli.format.unwrap_or(OutputFormat::Text),
+        cli.quiet,
+        cli.no_color,
+        cli.no_emoji,
+    );
+
     // Execute the appropriate command
-    execute_command(cli.command).await
+    match run(cli, &mut output).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            output.error(&err);
+            ExitCode::from(err.exit_code())
+        }
+    }
+}
+
+/// Locate the project the command works on, apply the user's preferences,
+/// then run it.
+#[cfg(not(test))]
+async fn run(cli: Cli, output: &mut Output) -> Result<()> {
+    if let Some(dir) = &cli.project {
+        std::env::set_current_dir(dir).map_err(|e| {
+            EnvCliError::FileSystem(format!("Cannot change to '{}': {}", dir.display(), e))
+        })?;
+    }
+
+    // `init` creates a project where it is asked to rather than finding the
+    // one above it.
+    let project = match cli.command {
+        Commands::Init { .. } => Project::here(cli.config),
+        _ => Project::discover(cli.config)?,
+    };
+
+    // Flags take precedence over configured preferences
+    let sources = project.config_sources()?;
+    let preferences = Preferences::from_sources(&sources)?;
+    *output = Output::new(
+        cli.format
+            .or(preferences.format)
+            .unwrap_or(OutputFormat::Text),
+        cli.quiet,
+        cli.no_color || preferences.color == Some(false),
+        cli.no_emoji,
+    );
+    // Shells run these at every prompt or startup, where a warning each
+    // time would only be noise
+    let quiet_config = matches!(
+        cli.command,
+        Commands::Hook { .. } | Commands::Export { .. } | Commands::Completion { .. }
+    );
+    if !quiet_config {
+        for source in &sources {
+            for key in keys::unknown_keys(&source.table) {
+                output.warn(format!("⚠️  {}: {}", source.path.display(), key));
+            }
+        }
+    }
+
+    execute_command(cli.command, &project, output).await
 }
 
 // Stub main for test mode to prevent binary execution
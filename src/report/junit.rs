@@ -0,0 +1,176 @@
+//! JUnit XML output.
+//!
+//! Writes the de facto format CI servers read: a `<testsuites>` root holding
+//! one `<testsuite>` per group of checks, each with a `<testcase>` per check.
+
+use std::fmt::Write;
+
+/// How a single check ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Passed,
+    /// The check ran and found problems
+    Failed {
+        /// Short identifier of the failure, such as a rule id
+        kind: String,
+        messages: Vec<String>,
+    },
+    /// The check could not run
+    Error {
+        message: String,
+    },
+}
+
+/// One check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCase {
+    pub classname: String,
+    pub name: String,
+    pub outcome: Outcome,
+}
+
+/// A group of checks, with notes that are not failures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestSuite {
+    pub name: String,
+    pub cases: Vec<TestCase>,
+    pub system_out: Vec<String>,
+}
+
+impl TestSuite {
+    fn count(&self, matches: fn(&Outcome) -> bool) -> usize {
+        self.cases
+            .iter()
+            .filter(|case| matches(&case.outcome))
+            .count()
+    }
+
+    fn failures(&self) -> usize {
+        self.count(|outcome| matches!(outcome, Outcome::Failed { .. }))
+    }
+
+    fn errors(&self) -> usize {
+        self.count(|outcome| matches!(outcome, Outcome::Error { .. }))
+    }
+}
+
+/// Render `suites` as a JUnit XML document.
+pub fn render(name: &str, suites: &[TestSuite]) -> String {
+    let total = |count: fn(&TestSuite) -> usize| suites.iter().map(count).sum::<usize>();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\">",
+        escape(name),
+        total(|suite| suite.cases.len()),
+        total(TestSuite::failures),
+        total(TestSuite::errors)
+    );
+
+    for suite in suites {
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\">",
+            escape(&suite.name),
+            suite.cases.len(),
+            suite.failures(),
+            suite.errors()
+        );
+
+        for case in &suite.cases {
+            let _ = write!(
+                xml,
+                "    <testcase classname=\"{}\" name=\"{}\"",
+                escape(&case.classname),
+                escape(&case.name)
+            );
+            match &case.outcome {
+                Outcome::Passed => xml.push_str("/>\n"),
+                Outcome::Failed { kind, messages } => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <failure type=\"{}\" message=\"{}\">{}</failure>\n    </testcase>",
+                        escape(kind),
+                        escape(messages.first().map(String::as_str).unwrap_or_default()),
+                        escape(&messages.join("\n"))
+                    );
+                }
+                Outcome::Error { message } => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <error message=\"{}\"/>\n    </testcase>",
+                        escape(message)
+                    );
+                }
+            }
+        }
+
+        if !suite.system_out.is_empty() {
+            let _ = writeln!(
+                xml,
+                "    <system-out>{}</system-out>",
+                escape(&suite.system_out.join("\n"))
+            );
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Escape text for use in XML content and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            // Other control characters are not allowed in XML 1.0
+            c if c.is_control() && c != '\t' => escaped.push('\u{fffd}'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counts_and_escapes() {
+        let suites = [TestSuite {
+            name: "production".to_string(),
+            cases: vec![
+                TestCase {
+                    classname: "production.required".to_string(),
+                    name: "DATABASE_URL".to_string(),
+                    outcome: Outcome::Passed,
+                },
+                TestCase {
+                    classname: "production.rule".to_string(),
+                    name: "pool-size".to_string(),
+                    outcome: Outcome::Failed {
+                        kind: "validation-rule".to_string(),
+                        messages: vec!["Rule 'pool-size' failed: POOL < 5 & \"x\"".to_string()],
+                    },
+                },
+            ],
+            system_out: vec!["Variable 'DEBUG' is deprecated".to_string()],
+        }];
+
+        let xml = render("env validate", &suites);
+        assert!(xml.contains(
+            "<testsuites name=\"env validate\" tests=\"2\" failures=\"1\" errors=\"0\">"
+        ));
+        assert!(xml.contains("<testcase classname=\"production.required\" name=\"DATABASE_URL\"/>"));
+        assert!(xml.contains("POOL &lt; 5 &amp; &quot;x&quot;"));
+        assert!(xml.contains("<system-out>Variable &apos;DEBUG&apos; is deprecated</system-out>"));
+    }
+}
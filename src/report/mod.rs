@@ -0,0 +1,8 @@
+//! Command output: the shared renderer, and machine-readable reports for CI
+//! and code-scanning tools.
+
+pub mod junit;
+pub mod output;
+pub mod sarif;
+
+pub use output::{Output, Report, Style};
@@ -0,0 +1,314 @@
+//! Printing command results.
+//!
+//! Every command writes through an [`Output`] built from the global flags.
+//! Results go to stdout, rendered by [`Output::render`] in the requested
+//! format; progress messages go to stderr so that machine-readable output
+//! stays parseable. `--quiet` drops everything but the result, `--no-emoji`
+//! strips pictographs and `--no-color` (or `NO_COLOR`) disables ANSI colors.
+//!
+//! Raw payloads meant for another program are exempt and printed as is: the
+//! file written by `decrypt --stdout`, and the shell code printed by `hook`,
+//! `export` and `completion`.
+
+use crate::cli::OutputFormat;
+use crate::error::{EnvCliError, Result, Span};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::io::IsTerminal;
+
+/// The result of a command, printed by [`Output::render`].
+pub trait Report: Serialize {
+    /// Formats the report supports besides text, JSON and YAML.
+    const EXTRA_FORMATS: &'static [OutputFormat] = &[];
+
+    /// Print the report for people.
+    fn print_text(&self, out: &Output);
+
+    /// Render the report in one of [`Report::EXTRA_FORMATS`].
+    fn render_extra(&self, format: OutputFormat) -> Result<String> {
+        Err(unsupported(format, Self::EXTRA_FORMATS))
+    }
+}
+
+/// ANSI text styles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Bold,
+    Red,
+    Green,
+    Yellow,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Bold => "1",
+            Style::Red => "31",
+            Style::Green => "32",
+            Style::Yellow => "33",
+        }
+    }
+}
+
+/// How command output is written.
+#[derive(Debug, Clone)]
+pub struct Output {
+    format: OutputFormat,
+    quiet: bool,
+    color: bool,
+    emoji: bool,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self::new(OutputFormat::Text, false, false, false)
+    }
+}
+
+impl Output {
+    /// Apply the global output flags. Colors are also disabled when stdout
+    /// is not a terminal or `NO_COLOR` is set.
+    pub fn new(format: OutputFormat, quiet: bool, no_color: bool, no_emoji: bool) -> Self {
+        Self {
+            format,
+            quiet,
+            color: !no_color
+                && std::env::var_os("NO_COLOR").is_none()
+                && std::io::stdout().is_terminal(),
+            emoji: !no_emoji,
+        }
+    }
+
+    /// The requested result format.
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Whether results are printed as text for people.
+    pub fn is_text(&self) -> bool {
+        self.format == OutputFormat::Text
+    }
+
+    /// Fail early when reports of type `R` cannot be rendered in the
+    /// requested format, before a command changes anything.
+    pub fn ensure_supported<R: Report>(&self) -> Result<()> {
+        match self.format {
+            OutputFormat::Text | OutputFormat::Json | OutputFormat::Yaml => Ok(()),
+            format if R::EXTRA_FORMATS.contains(&format) => Ok(()),
+            format => Err(unsupported(format, R::EXTRA_FORMATS)),
+        }
+    }
+
+    /// Print the result of a command in the requested format.
+    pub fn render<R: Report>(&self, report: &R) -> Result<()> {
+        match self.format {
+            OutputFormat::Text => report.print_text(self),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(report)?),
+            format => {
+                self.ensure_supported::<R>()?;
+                let rendered = report.render_extra(format)?;
+                print!("{}", rendered);
+                if !rendered.ends_with('\n') {
+                    println!();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Print a line of a text report to stdout.
+    pub fn line(&self, message: impl Display) {
+        println!("{}", self.plain(&message.to_string()));
+    }
+
+    /// Print a message about the result, such as a confirmation or a tip.
+    ///
+    /// Goes to stdout in text mode and to stderr otherwise; dropped with
+    /// `--quiet`.
+    pub fn info(&self, message: impl Display) {
+        if self.quiet {
+            return;
+        }
+        let message = self.plain(&message.to_string()).into_owned();
+        if self.is_text() {
+            println!("{}", message);
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+
+    /// Print a progress message to stderr; dropped with `--quiet`.
+    pub fn progress(&self, message: impl Display) {
+        if !self.quiet {
+            eprintln!("{}", self.plain(&message.to_string()));
+        }
+    }
+
+    /// Print a warning to stderr, even with `--quiet`.
+    pub fn warn(&self, message: impl Display) {
+        eprintln!("{}", self.plain(&message.to_string()));
+    }
+
+    /// Print a failed command's error to stderr, as JSON with `--format json`.
+    pub fn error(&self, err: &EnvCliError) {
+        if self.format == OutputFormat::Json {
+            let report = ErrorReport {
+                error: ErrorDetails {
+                    code: err.code(),
+                    message: err.to_string(),
+                    exit_code: err.exit_code(),
+                    help: err.help(),
+                    span: err.span(),
+                },
+            };
+            if let Ok(json) = serde_json::to_string_pretty(&report) {
+                eprintln!("{}", json);
+                return;
+            }
+        }
+
+        eprintln!(
+            "{}: {}",
+            self.paint(Style::Red, format!("error[{}]", err.code())),
+            err
+        );
+        if let Some(help) = err.help() {
+            eprintln!("  = help: {}", help);
+        }
+    }
+
+    /// Wrap `text` in an ANSI style when colors are enabled.
+    pub fn paint(&self, style: Style, text: impl Display) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", style.code(), text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// `text` with emoji removed when `--no-emoji` is set.
+    pub fn plain<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.emoji {
+            Cow::Borrowed(text)
+        } else {
+            strip_emoji(text)
+        }
+    }
+}
+
+/// A failed command's error, as printed with `--format json`.
+#[derive(Serialize)]
+struct ErrorReport<'a> {
+    error: ErrorDetails<'a>,
+}
+
+#[derive(Serialize)]
+struct ErrorDetails<'a> {
+    code: &'static str,
+    message: String,
+    exit_code: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    span: Option<&'a Span>,
+}
+
+/// Error for a format a report cannot be rendered in.
+fn unsupported(format: OutputFormat, extra: &[OutputFormat]) -> EnvCliError {
+    let name = |format: &OutputFormat| {
+        format
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    };
+    let supported: Vec<String> = [OutputFormat::Text, OutputFormat::Json, OutputFormat::Yaml]
+        .iter()
+        .chain(extra)
+        .map(name)
+        .collect();
+    EnvCliError::InvalidArgument(format!(
+        "--format {} is not supported by this command (use {})",
+        name(&format),
+        supported.join(", ")
+    ))
+}
+
+/// Remove emoji, and the spaces that follow them, from `text`.
+///
+/// Plain symbols such as ✓ and ✗ are kept, since they read the same
+/// everywhere.
+fn strip_emoji(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_emoji) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !is_emoji(c) {
+            stripped.push(c);
+            continue;
+        }
+        while chars.peek().is_some_and(|&next| is_emoji(next)) {
+            chars.next();
+        }
+        while chars.peek() == Some(&' ') {
+            chars.next();
+        }
+    }
+    Cow::Owned(stripped)
+}
+
+fn is_emoji(c: char) -> bool {
+    match c {
+        '✓' | '✗' => false,
+        '\u{1F000}'..='\u{1FAFF}' | '\u{2600}'..='\u{27BF}' => true,
+        // Variation selector, zero-width joiner and the information source
+        '\u{FE0F}' | '\u{200D}' | '\u{2139}' => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_emoji() {
+        assert_eq!(strip_emoji("🔍 Scanning code"), "Scanning code");
+        assert_eq!(strip_emoji("\n⚠️  Security Issues:"), "\nSecurity Issues:");
+        assert_eq!(strip_emoji("  ℹ️ [jwt] token"), "  [jwt] token");
+        assert_eq!(
+            strip_emoji("  ✓ Project initialized"),
+            "  ✓ Project initialized"
+        );
+        assert!(matches!(strip_emoji("plain"), Cow::Borrowed("plain")));
+    }
+
+    #[test]
+    fn test_ensure_supported() {
+        #[derive(Serialize)]
+        struct Table;
+        impl Report for Table {
+            const EXTRA_FORMATS: &'static [OutputFormat] = &[OutputFormat::Csv];
+            fn print_text(&self, _out: &Output) {}
+        }
+
+        let output = |format| Output::new(format, false, true, false);
+        assert!(output(OutputFormat::Yaml)
+            .ensure_supported::<Table>()
+            .is_ok());
+        assert!(output(OutputFormat::Csv)
+            .ensure_supported::<Table>()
+            .is_ok());
+        let error = output(OutputFormat::Sarif)
+            .ensure_supported::<Table>()
+            .unwrap_err();
+        assert!(error.to_string().contains(
+            "--format sarif is not supported by this command (use text, json, yaml, csv)"
+        ));
+    }
+}
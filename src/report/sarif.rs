@@ -0,0 +1,355 @@
+//! SARIF 2.1.0 output.
+//!
+//! Only the parts of the format that code-scanning services read are modelled:
+//! the rules a run can report, and results with a level, a message, a
+//! physical location and a fingerprint. Columns are counted in Unicode code
+//! points, as they are everywhere else in env-cli.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const VERSION: &str = "2.1.0";
+
+/// Key under which result fingerprints are recorded.
+pub const FINGERPRINT_KEY: &str = "envCliFingerprint/v1";
+
+/// Severity of a result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warning,
+    Note,
+}
+
+/// A complete SARIF document with a single run.
+#[derive(Debug, Clone, Serialize)]
+pub struct Log {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run {
+    tool: Tool,
+    column_kind: &'static str,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Message {
+    text: String,
+}
+
+/// Metadata about a rule that results refer to.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rule {
+    id: String,
+    short_description: Message,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    full_description: Option<Message>,
+    default_configuration: RuleConfiguration,
+    properties: RuleProperties,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct RuleConfiguration {
+    level: Level,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct RuleProperties {
+    tags: Vec<String>,
+    /// Score from 0.0 to 10.0 that GitHub code scanning uses to rank
+    /// security results
+    #[serde(rename = "security-severity", skip_serializing_if = "Option::is_none")]
+    security_severity: Option<String>,
+}
+
+impl Rule {
+    /// Describe a rule reported at `level` by default.
+    pub fn new(id: &str, description: &str, level: Level) -> Self {
+        Self {
+            id: id.to_string(),
+            short_description: Message {
+                text: description.to_string(),
+            },
+            full_description: None,
+            default_configuration: RuleConfiguration { level },
+            properties: RuleProperties {
+                tags: Vec::new(),
+                security_severity: None,
+            },
+        }
+    }
+
+    /// Add a longer explanation of what the rule checks.
+    pub fn with_help(mut self, text: &str) -> Self {
+        self.full_description = Some(Message {
+            text: text.to_string(),
+        });
+        self
+    }
+
+    /// Add a tag such as `security`.
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.properties.tags.push(tag.to_string());
+        self
+    }
+
+    /// Rank the rule as a security issue, from 0.0 to 10.0.
+    pub fn with_security_severity(mut self, score: f32) -> Self {
+        self.properties.security_severity = Some(format!("{:.1}", score));
+        self
+    }
+}
+
+/// Where a result was found.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    physical_location: PhysicalLocation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<Region>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactLocation {
+    uri: String,
+    uri_base_id: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+    start_column: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_column: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snippet: Option<Message>,
+}
+
+/// URI of `path` relative to the project `root`, with `/` separators.
+///
+/// Code-scanning services resolve URIs against the repository, so paths
+/// given relative to a subdirectory are rebased onto the root. Leading `..`
+/// components of paths outside the project are dropped.
+pub fn uri(path: &Path, root: &Path) -> String {
+    let root = if root.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        root
+    };
+    let relative = match (path.canonicalize(), root.canonicalize()) {
+        (Ok(path), Ok(root)) => path
+            .strip_prefix(&root)
+            .map(Path::to_path_buf)
+            .unwrap_or(path),
+        _ => path.strip_prefix(root).unwrap_or(path).to_path_buf(),
+    };
+    relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+impl Location {
+    /// A whole file, given relative to the project root with `/` separators.
+    pub fn file(uri: &str) -> Self {
+        Self {
+            physical_location: PhysicalLocation {
+                artifact_location: ArtifactLocation {
+                    uri: uri.to_string(),
+                    uri_base_id: "%SRCROOT%",
+                },
+                region: None,
+            },
+        }
+    }
+
+    /// Narrow the location to a 1-based line and column range.
+    pub fn with_region(mut self, line: usize, column: usize, end_column: Option<usize>) -> Self {
+        self.physical_location.region = Some(Region {
+            start_line: line,
+            start_column: column,
+            end_column,
+            snippet: None,
+        });
+        self
+    }
+
+    /// Attach the source text of the region. Has no effect without a region.
+    pub fn with_snippet(mut self, text: &str) -> Self {
+        if let Some(region) = &mut self.physical_location.region {
+            region.snippet = Some(Message {
+                text: text.to_string(),
+            });
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    rule_index: usize,
+    level: Level,
+    message: Message,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    locations: Vec<Location>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    partial_fingerprints: BTreeMap<&'static str, String>,
+}
+
+impl Log {
+    /// Start a log for a run that can report `rules`.
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self {
+            schema: SCHEMA,
+            version: VERSION,
+            runs: vec![Run {
+                tool: Tool {
+                    driver: Driver {
+                        name: "env-cli",
+                        version: env!("CARGO_PKG_VERSION"),
+                        information_uri: env!("CARGO_PKG_REPOSITORY"),
+                        rules,
+                    },
+                },
+                column_kind: "unicodeCodePoints",
+                results: Vec::new(),
+            }],
+        }
+    }
+
+    /// Record a result for `rule_id` at the rule's default level.
+    ///
+    /// Results for rules that were not declared are dropped, since SARIF
+    /// consumers reject them.
+    pub fn add_result(
+        &mut self,
+        rule_id: &str,
+        message: &str,
+        location: Option<Location>,
+        fingerprint: Option<String>,
+    ) {
+        let run = &mut self.runs[0];
+        let Some((rule_index, rule)) = run
+            .tool
+            .driver
+            .rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.id == rule_id)
+        else {
+            return;
+        };
+
+        run.results.push(SarifResult {
+            rule_id: rule_id.to_string(),
+            rule_index,
+            level: rule.default_configuration.level,
+            message: Message {
+                text: message.to_string(),
+            },
+            locations: location.into_iter().collect(),
+            partial_fingerprints: fingerprint
+                .map(|fingerprint| BTreeMap::from([(FINGERPRINT_KEY, fingerprint)]))
+                .unwrap_or_default(),
+        });
+    }
+
+    /// Number of results recorded so far.
+    pub fn result_count(&self) -> usize {
+        self.runs[0].results.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_shape() {
+        let mut log = Log::new(vec![
+            Rule::new("jwt", "JSON Web Token", Level::Error)
+                .with_tag("security")
+                .with_security_severity(8.0),
+            Rule::new("unused-variable", "Unused variable", Level::Note),
+        ]);
+        log.add_result(
+            "unused-variable",
+            "DEBUG is not read by any code",
+            Some(Location::file(".env/environments/development.env").with_region(3, 1, Some(6))),
+            None,
+        );
+        log.add_result("undeclared", "dropped", None, None);
+
+        let value = serde_json::to_value(&log).unwrap();
+        assert_eq!(value["version"], "2.1.0");
+        let run = &value["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "jwt");
+        assert_eq!(
+            run["tool"]["driver"]["rules"][0]["properties"]["security-severity"],
+            "8.0"
+        );
+        assert_eq!(log.result_count(), 1);
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleIndex"], 1);
+        assert_eq!(result["level"], "note");
+        let region = &result["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startLine"], 3);
+        assert_eq!(region["endColumn"], 6);
+    }
+
+    #[test]
+    fn test_uri_is_relative_to_the_root() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().join("repo");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join(".env")).unwrap();
+        std::fs::write(root.join(".env/app.env"), "").unwrap();
+
+        let from_subdir = root.join("src/../.env/app.env");
+        assert_eq!(uri(&from_subdir, &root.join("src/..")), ".env/app.env");
+        assert_eq!(
+            uri(Path::new("./src/main.rs"), Path::new("")),
+            "src/main.rs"
+        );
+        assert_eq!(uri(Path::new("../../elsewhere.rs"), &root), "elsewhere.rs");
+    }
+}
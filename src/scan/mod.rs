@@ -3,23 +3,46 @@
 //! This module provides comprehensive multi-language source code analysis
 //! for environment variable usage with parallel processing capabilities.
 
+pub mod secrets;
+pub mod suppress;
+
+pub use secrets::{Confidence, RuleInfo, SecretDetector};
+pub use suppress::Baseline;
+
+use crate::config::ScanConfig;
 use crate::env::EnvUsage;
 use crate::error::Result;
 use ignore::WalkBuilder;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
 /// Represents a security issue found during scanning
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SecurityIssue {
     pub severity: SecuritySeverity,
+    /// Identifier of the rule that matched, e.g. `aws-access-key-id`
+    pub rule_id: String,
+    pub confidence: Confidence,
+    /// Description of the finding; the secret itself is redacted
     pub message: String,
     pub file: String,
     pub line: usize,
-    pub variable: String,
+    /// 1-based column where the secret starts
+    pub column: usize,
+    /// 1-based column just past the end of the secret
+    pub end_column: usize,
+    /// Variable or key the secret is assigned to, when there is one
+    pub variable: Option<String>,
+    /// The offending line with the secret redacted
+    pub snippet: String,
+    /// SHA-256 of the matched secret, so baselines can tell secrets apart
+    /// without storing them
+    #[serde(skip)]
+    pub secret_digest: String,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub enum SecuritySeverity {
     Low,
     Medium,
@@ -43,6 +66,8 @@ pub struct ScanResult {
     pub files_scanned: usize,
     pub patterns_matched: usize,
     pub security_issues: Vec<SecurityIssue>,
+    /// Findings silenced by inline `env-cli:ignore` comments
+    pub ignored_issues: usize,
     pub scan_duration: std::time::Duration,
     pub languages_detected: HashMap<String, usize>,
 }
@@ -53,14 +78,14 @@ pub struct CodeScanner {
     languages: HashMap<String, LanguageConfig>,
     /// Compiled regex patterns for each language
     patterns: HashMap<String, Vec<regex::Regex>>,
-    /// Security scanning patterns
-    security_patterns: Vec<regex::Regex>,
+    /// Hardcoded secret detector
+    secrets: SecretDetector,
     /// File inclusion patterns
     include_patterns: Vec<glob::Pattern>,
     /// File exclusion patterns
     exclude_patterns: Vec<glob::Pattern>,
-    /// Directories to exclude
-    _exclude_dirs: Vec<String>,
+    /// Names of directories whose contents are skipped
+    exclude_dirs: Vec<String>,
     /// Whether to enable parallel scanning
     parallel: bool,
     /// Number of worker threads for parallel processing
@@ -71,8 +96,17 @@ pub struct CodeScanner {
 #[derive(Debug)]
 struct FileScanResult {
     path: String,
-    variables: HashMap<String, Vec<(String, usize)>>,
+    variables: HashMap<String, Vec<UsageSite>>,
     security_issues: Vec<SecurityIssue>,
+    ignored_issues: usize,
+}
+
+/// Where a variable is read within a file
+#[derive(Debug)]
+struct UsageSite {
+    line: usize,
+    column: usize,
+    context: String,
 }
 
 impl CodeScanner {
@@ -81,10 +115,10 @@ impl CodeScanner {
         let mut scanner = Self {
             languages: HashMap::new(),
             patterns: HashMap::new(),
-            security_patterns: Vec::new(),
+            secrets: SecretDetector::new(),
             include_patterns: Vec::new(),
             exclude_patterns: Vec::new(),
-            _exclude_dirs: vec![
+            exclude_dirs: vec![
                 "target".to_string(),
                 "node_modules".to_string(),
                 ".git".to_string(),
@@ -112,6 +146,36 @@ impl CodeScanner {
         Ok(scanner)
     }
 
+    /// Also skip the directories and files a project's `[scan]` settings
+    /// exclude.
+    pub fn with_exclusions(mut self, scan: &ScanConfig) -> Self {
+        for dir in &scan.exclude_dirs {
+            if !self.exclude_dirs.contains(dir) {
+                self.exclude_dirs.push(dir.clone());
+            }
+        }
+        for pattern in &scan.exclude_patterns {
+            if let Ok(glob) = glob::Pattern::new(pattern) {
+                self.exclude_patterns.push(glob);
+            }
+        }
+        self
+    }
+
+    /// Whether the walk should descend into `entry`.
+    fn is_walked(exclude_dirs: &[String], entry: &ignore::DirEntry) -> bool {
+        entry.depth() == 0
+            || !entry.file_type().is_some_and(|t| t.is_dir())
+            || !exclude_dirs
+                .iter()
+                .any(|dir| entry.file_name().to_str() == Some(dir.as_str()))
+    }
+
+    /// Metadata for every rule that can produce a [`SecurityIssue`].
+    pub fn security_rules(&self) -> Vec<RuleInfo> {
+        self.secrets.rules()
+    }
+
     /// Initialize language-specific configurations.
     fn init_languages(&mut self) {
         // Rust
@@ -307,39 +371,6 @@ impl CodeScanner {
             self.patterns.insert(language.clone(), compiled_patterns);
         }
 
-        // Initialize security patterns
-        let security_patterns = vec![
-            (
-                r#"(?i)password\s*=\s*["']?[^"'\s]+["']?"#,
-                SecuritySeverity::High,
-            ),
-            (
-                r#"(?i)secret.*=\s*["']?[^"'\s]{8,}"#,
-                SecuritySeverity::High,
-            ),
-            (
-                r#"(?i)api[_-]?key.*=\s*["']?[^"'\s]{16,}"#,
-                SecuritySeverity::Medium,
-            ),
-            (
-                r#"(?i)token.*=\s*["']?[^"'\s]{16,}"#,
-                SecuritySeverity::Medium,
-            ),
-            (r#"(?i)private[_-]?key"#, SecuritySeverity::Critical),
-            (r#"(?i)aws[_-]?secret"#, SecuritySeverity::Critical),
-            (r#"(?i)database[_-]?url.*=.*://.*:"#, SecuritySeverity::High),
-            (
-                r#"(?i)connection[_-]?string.*=.*password"#,
-                SecuritySeverity::High,
-            ),
-        ];
-
-        for (pattern, _) in security_patterns {
-            if let Ok(regex) = regex::Regex::new(pattern) {
-                self.security_patterns.push(regex);
-            }
-        }
-
         // Initialize file patterns
         let include_patterns = vec![
             "*.rs",
@@ -399,6 +430,7 @@ impl CodeScanner {
         let start_time = std::time::Instant::now();
         let mut variables = HashMap::new();
         let mut security_issues = Vec::new();
+        let mut ignored_issues = 0;
         let mut languages_detected = HashMap::new();
         let mut files_scanned = 0;
         let mut patterns_matched = 0;
@@ -419,11 +451,13 @@ impl CodeScanner {
         // Build walk iterator with ignore support - split paths to avoid type mismatch
         let scan_results = std::sync::Mutex::new(Vec::new());
 
+        let exclude_dirs = self.exclude_dirs.clone();
         if self.parallel {
             let walk = WalkBuilder::new(path)
                 .hidden(false)
                 .git_ignore(false)
                 .git_exclude(false)
+                .filter_entry(move |entry| Self::is_walked(&exclude_dirs, entry))
                 .build_parallel();
 
             walk.run(|| {
@@ -446,6 +480,7 @@ impl CodeScanner {
                 .hidden(false)
                 .git_ignore(false)
                 .git_exclude(false)
+                .filter_entry(move |entry| Self::is_walked(&exclude_dirs, entry))
                 .build()
             {
                 if let Ok(entry) = entry_result {
@@ -475,16 +510,22 @@ impl CodeScanner {
                     .entry(var_name.clone())
                     .or_insert_with(|| EnvUsage::new(var_name));
 
-                for (file, line) in locations {
-                    usage.add_usage(file, line);
+                for site in locations {
+                    usage.add_usage(result.path.clone(), site.line, site.column, site.context);
                     patterns_matched += 1;
                 }
             }
 
             // Process security issues
             security_issues.extend(result.security_issues);
+            ignored_issues += result.ignored_issues;
         }
 
+        // Files finish in any order when scanning in parallel
+        security_issues.sort_by(|a, b| {
+            (&a.file, a.line, a.column, &a.rule_id).cmp(&(&b.file, b.line, b.column, &b.rule_id))
+        });
+
         let scan_duration = start_time.elapsed();
 
         Ok(ScanResult {
@@ -492,6 +533,7 @@ impl CodeScanner {
             files_scanned,
             patterns_matched,
             security_issues,
+            ignored_issues,
             scan_duration,
             languages_detected,
         })
@@ -509,63 +551,74 @@ impl CodeScanner {
         let path_str = path.to_string_lossy().to_string();
         let mut variables = HashMap::new();
         let mut security_issues = Vec::new();
+        let mut ignored_issues = 0;
+        let mut previous_directive = None;
 
         // Detect language for this file
-        let language = self.detect_language(path);
-
-        if let Some(lang) = language {
-            if let Some(patterns) = self.patterns.get(&lang) {
-                for (line_num, line) in content.lines().enumerate() {
-                    // Scan for environment variable usage
-                    for pattern in patterns {
-                        for captures in pattern.captures_iter(line) {
-                            if let Some(var_match) = captures.get(1) {
-                                let var_name = var_match.as_str().to_string();
-                                variables
-                                    .entry(var_name.clone())
-                                    .or_insert_with(Vec::new)
-                                    .push((path_str.clone(), line_num + 1));
-                            } else if let Some(var_match) = captures.get(2) {
-                                // Some patterns capture in group 2
-                                let var_name = var_match.as_str().to_string();
-                                variables
-                                    .entry(var_name.clone())
-                                    .or_insert_with(Vec::new)
-                                    .push((path_str.clone(), line_num + 1));
-                            }
-                        }
-                    }
-
-                    // Scan for security issues
-                    for (i, security_pattern) in self.security_patterns.iter().enumerate() {
-                        if security_pattern.is_match(line) {
-                            let severity = match i {
-                                0 | 1 | 6 | 7 => SecuritySeverity::High,
-                                2 | 3 => SecuritySeverity::Medium,
-                                4 | 5 => SecuritySeverity::Critical,
-                                _ => SecuritySeverity::Low,
-                            };
-
-                            security_issues.push(SecurityIssue {
-                                severity,
-                                message: format!(
-                                    "Potential security issue detected: {}",
-                                    line.trim()
-                                ),
-                                file: path_str.clone(),
+        let patterns = self
+            .detect_language(path)
+            .and_then(|lang| self.patterns.get(&lang));
+
+        for (line_num, line) in content.lines().enumerate() {
+            // Secrets are found first so that no excerpt repeats them
+            let secrets = self.secrets.scan_line(line);
+            let redacted = secrets::redact_line(line, &secrets);
+
+            // Scan for environment variable usage
+            for pattern in patterns.into_iter().flatten() {
+                for captures in pattern.captures_iter(line) {
+                    // Some patterns capture in group 2
+                    if let Some(var_match) = captures.get(1).or_else(|| captures.get(2)) {
+                        variables
+                            .entry(var_match.as_str().to_string())
+                            .or_insert_with(Vec::new)
+                            .push(UsageSite {
                                 line: line_num + 1,
-                                variable: "unknown".to_string(), // Could be extracted with more complex parsing
+                                column: line[..var_match.start()].chars().count() + 1,
+                                context: excerpt(&redacted),
                             });
-                        }
                     }
                 }
             }
+
+            // Scan for hardcoded secrets, in configuration files too. An
+            // ignore comment applies to its own line and the one below it
+            let directive = suppress::ignored_rules(line);
+            for found in secrets {
+                if suppress::is_ignored(directive.as_deref(), found.rule_id)
+                    || suppress::is_ignored(previous_directive.as_deref(), found.rule_id)
+                {
+                    ignored_issues += 1;
+                    continue;
+                }
+
+                let secret = &line[found.start..found.end];
+                security_issues.push(SecurityIssue {
+                    severity: found.severity,
+                    rule_id: found.rule_id.to_string(),
+                    confidence: found.confidence,
+                    message: format!(
+                        "{} detected: {}",
+                        found.description,
+                        secrets::redact(secret)
+                    ),
+                    file: path_str.clone(),
+                    line: line_num + 1,
+                    column: line[..found.start].chars().count() + 1,
+                    end_column: line[..found.end].chars().count() + 1,
+                    variable: secrets::assigned_name(line, found.start),
+                    snippet: excerpt(&redacted),
+                    secret_digest: suppress::digest(secret),
+                });
+            }
+            previous_directive = directive;
         }
 
         Some(FileScanResult {
             path: path_str,
             variables,
             security_issues,
+            ignored_issues,
         })
     }
 
@@ -645,7 +698,7 @@ impl CodeScanner {
             content.push_str("# ----------------------------\n");
 
             for usage in vars {
-                if comments {
+                if comments && !usage.files.is_empty() {
                     content.push_str(&format!("# Used in {} location(s)\n", usage.files.len()));
                     if usage.files.len() <= 3 {
                         content.push_str(&format!("# Locations: {}\n", usage.files.join(", ")));
@@ -658,7 +711,16 @@ impl CodeScanner {
                     }
                 }
 
-                content.push_str(&format!("{}=\n", usage.name));
+                if let Some(description) = &usage.description {
+                    content.push_str(&format!("# {}\n", description));
+                }
+
+                let value = usage
+                    .default
+                    .as_deref()
+                    .map(crate::env::parser::quote_value)
+                    .unwrap_or_default();
+                content.push_str(&format!("{}={}\n", usage.name, value));
 
                 if comments {
                     content.push('\n');
@@ -712,8 +774,8 @@ impl CodeScanner {
 
             if usage.files.len() > 1 {
                 content.push_str("- Usage locations:\n");
-                for file in &usage.files {
-                    content.push_str(&format!("  - {}\n", file));
+                for (file, line) in usage.files.iter().zip(&usage.lines) {
+                    content.push_str(&format!("  - {}:{}\n", file, line));
                 }
             }
             content.push('\n');
@@ -741,8 +803,8 @@ impl CodeScanner {
                 content.push_str("### Critical Issues\n\n");
                 for issue in &critical_issues {
                     content.push_str(&format!(
-                        "- {}: {}:{}\n",
-                        issue.message, issue.file, issue.line
+                        "- `{}` {}: {}:{}\n",
+                        issue.rule_id, issue.message, issue.file, issue.line
                     ));
                 }
                 content.push('\n');
@@ -752,8 +814,8 @@ impl CodeScanner {
                 content.push_str("### High Severity Issues\n\n");
                 for issue in &high_issues {
                     content.push_str(&format!(
-                        "- {}: {}:{}\n",
-                        issue.message, issue.file, issue.line
+                        "- `{}` {}: {}:{}\n",
+                        issue.rule_id, issue.message, issue.file, issue.line
                     ));
                 }
                 content.push('\n');
@@ -763,8 +825,8 @@ impl CodeScanner {
                 content.push_str("### Medium Severity Issues\n\n");
                 for issue in &medium_issues {
                     content.push_str(&format!(
-                        "- {}: {}:{}\n",
-                        issue.message, issue.file, issue.line
+                        "- `{}` {}: {}:{}\n",
+                        issue.rule_id, issue.message, issue.file, issue.line
                     ));
                 }
                 content.push('\n');
@@ -774,8 +836,8 @@ impl CodeScanner {
                 content.push_str("### Low Severity Issues\n\n");
                 for issue in &low_issues {
                     content.push_str(&format!(
-                        "- {}: {}:{}\n",
-                        issue.message, issue.file, issue.line
+                        "- `{}` {}: {}:{}\n",
+                        issue.rule_id, issue.message, issue.file, issue.line
                     ));
                 }
                 content.push('\n');
@@ -786,15 +848,29 @@ impl CodeScanner {
     }
 }
 
+/// Longest excerpt of a source line kept in scan results, in characters.
+const EXCERPT_LEN: usize = 120;
+
+/// Trim a source line to a short excerpt for reports.
+fn excerpt(line: &str) -> String {
+    let line = line.trim();
+    if line.chars().count() <= EXCERPT_LEN {
+        return line.to_string();
+    }
+    let mut short: String = line.chars().take(EXCERPT_LEN - 1).collect();
+    short.push('…');
+    short
+}
+
 impl Default for CodeScanner {
     fn default() -> Self {
         Self::new().unwrap_or_else(|_| Self {
             languages: HashMap::new(),
             patterns: HashMap::new(),
-            security_patterns: Vec::new(),
+            secrets: SecretDetector::new(),
             include_patterns: Vec::new(),
             exclude_patterns: Vec::new(),
-            _exclude_dirs: Vec::new(),
+            exclude_dirs: Vec::new(),
             parallel: true,
             worker_threads: None,
         })
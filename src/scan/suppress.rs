@@ -0,0 +1,264 @@
+//! Suppressing known scan findings.
+//!
+//! Findings can be silenced in two ways:
+//!
+//! - An inline `env-cli:ignore[rule-id]` comment on the offending line or the
+//!   line above it. Several rules can be listed as `[rule-a, rule-b]`, and a
+//!   bare `env-cli:ignore` silences every rule.
+//! - A baseline file of accepted findings, so CI only fails on new ones.
+//!   Findings are fingerprinted by rule, file, their whitespace normalized
+//!   line and a digest of the secret itself, which keeps them stable when
+//!   code above them moves but not when the secret changes.
+
+use super::SecurityIssue;
+use crate::error::{EnvCliError, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+
+const IGNORE_MARKER: &str = "env-cli:ignore";
+const BASELINE_VERSION: u32 = 1;
+
+/// Rules silenced by an inline `env-cli:ignore` comment on `line`.
+///
+/// Returns `None` when the line has no directive and an empty list when it
+/// silences every rule.
+pub fn ignored_rules(line: &str) -> Option<Vec<String>> {
+    let rest = &line[line.find(IGNORE_MARKER)? + IGNORE_MARKER.len()..];
+    let Some(list) = rest.strip_prefix('[') else {
+        return Some(Vec::new());
+    };
+    let list = &list[..list.find(']')?];
+
+    Some(
+        list.split(',')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// Whether a directive returned by [`ignored_rules`] covers `rule_id`.
+pub fn is_ignored(directive: Option<&[String]>, rule_id: &str) -> bool {
+    directive.is_some_and(|rules| rules.is_empty() || rules.iter().any(|rule| rule == rule_id))
+}
+
+/// Stable identifier of a finding, independent of its line number.
+///
+/// The redacted snippet alone would match any secret with the same prefix,
+/// so the digest of the secret is part of it.
+pub fn fingerprint(issue: &SecurityIssue, root: &Path) -> String {
+    let normalized: Vec<&str> = issue.snippet.split_whitespace().collect();
+
+    let mut hasher = Sha256::new();
+    hasher.update(issue.rule_id.as_bytes());
+    hasher.update([0]);
+    hasher.update(relative_path(&issue.file, root).as_bytes());
+    hasher.update([0]);
+    hasher.update(normalized.join(" ").as_bytes());
+    hasher.update([0]);
+    hasher.update(issue.secret_digest.as_bytes());
+    hex(hasher.finalize().as_slice())
+}
+
+/// Hex-encoded SHA-256 of a matched secret.
+pub fn digest(secret: &str) -> String {
+    hex(Sha256::digest(secret.as_bytes()).as_slice())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Path of a scanned file relative to the scan root, with `/` separators.
+pub fn relative_path(file: &str, root: &Path) -> String {
+    let path = Path::new(file);
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let relative = relative.strip_prefix(".").unwrap_or(relative);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// An accepted finding recorded in a baseline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub fingerprint: String,
+    pub rule_id: String,
+    pub file: String,
+    /// Line the finding was on when it was accepted; informational only
+    pub line: usize,
+}
+
+/// A set of accepted findings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub findings: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    /// Build a baseline accepting every given finding.
+    pub fn from_issues(issues: &[SecurityIssue], root: &Path) -> Self {
+        let mut findings: Vec<BaselineEntry> = issues
+            .iter()
+            .map(|issue| BaselineEntry {
+                fingerprint: fingerprint(issue, root),
+                rule_id: issue.rule_id.clone(),
+                file: relative_path(&issue.file, root),
+                line: issue.line,
+            })
+            .collect();
+        findings.sort_by(|a, b| {
+            (&a.file, a.line, &a.rule_id, &a.fingerprint).cmp(&(
+                &b.file,
+                b.line,
+                &b.rule_id,
+                &b.fingerprint,
+            ))
+        });
+
+        Self {
+            version: BASELINE_VERSION,
+            findings,
+        }
+    }
+
+    /// Load a baseline file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            EnvCliError::Config(format!("Cannot read baseline {}: {}", path.display(), e))
+        })?;
+        let baseline: Self = serde_json::from_str(&content).map_err(|e| {
+            EnvCliError::Config(format!("Invalid baseline {}: {}", path.display(), e))
+        })?;
+        if baseline.version != BASELINE_VERSION {
+            return Err(EnvCliError::Config(format!(
+                "Unsupported baseline version {} in {}",
+                baseline.version,
+                path.display()
+            )));
+        }
+        Ok(baseline)
+    }
+
+    /// Write the baseline as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        std::fs::write(path, format!("{}\n", serde_json::to_string_pretty(self)?))?;
+        Ok(())
+    }
+
+    /// Split findings into those not in the baseline and those it accepts.
+    ///
+    /// Each baseline entry accepts one finding, so a second copy of an
+    /// accepted line still counts as new.
+    pub fn partition(
+        &self,
+        issues: Vec<SecurityIssue>,
+        root: &Path,
+    ) -> (Vec<SecurityIssue>, Vec<SecurityIssue>) {
+        let mut remaining: HashMap<&str, usize> = HashMap::new();
+        for entry in &self.findings {
+            *remaining.entry(entry.fingerprint.as_str()).or_default() += 1;
+        }
+
+        issues.into_iter().partition(|issue| {
+            match remaining.get_mut(fingerprint(issue, root).as_str()) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    false
+                }
+                _ => true,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::{Confidence, SecuritySeverity};
+
+    fn issue(file: &str, line: usize, snippet: &str) -> SecurityIssue {
+        issue_with_secret(file, line, snippet, "hunter22")
+    }
+
+    fn issue_with_secret(file: &str, line: usize, snippet: &str, secret: &str) -> SecurityIssue {
+        SecurityIssue {
+            severity: SecuritySeverity::High,
+            rule_id: "hardcoded-credential".to_string(),
+            confidence: Confidence::Medium,
+            message: "Hardcoded credential detected: hunt****".to_string(),
+            file: file.to_string(),
+            line,
+            column: 1,
+            end_column: 5,
+            variable: None,
+            snippet: snippet.to_string(),
+            secret_digest: digest(secret),
+        }
+    }
+
+    #[test]
+    fn test_ignore_directives() {
+        assert_eq!(ignored_rules("let a = 1;"), None);
+        assert_eq!(ignored_rules("x = 'y' # env-cli:ignore"), Some(vec![]));
+        assert_eq!(
+            ignored_rules("// env-cli:ignore[jwt, generic-high-entropy]"),
+            Some(vec!["jwt".to_string(), "generic-high-entropy".to_string()])
+        );
+
+        let directive = ignored_rules("// env-cli:ignore[jwt]");
+        assert!(is_ignored(directive.as_deref(), "jwt"));
+        assert!(!is_ignored(directive.as_deref(), "github-token"));
+        assert!(!is_ignored(None, "jwt"));
+    }
+
+    #[test]
+    fn test_fingerprint_survives_line_shifts() {
+        let root = Path::new(".");
+        let before = issue("./src/app.js", 3, "const password = \"hunt****\";");
+        let after = issue("./src/app.js", 10, "const  password = \"hunt****\";");
+        let other = issue("./src/other.js", 3, "const password = \"hunt****\";");
+
+        assert_eq!(fingerprint(&before, root), fingerprint(&after, root));
+        assert_ne!(fingerprint(&before, root), fingerprint(&other, root));
+        assert_eq!(relative_path("./src/app.js", root), "src/app.js");
+    }
+
+    #[test]
+    fn test_fingerprint_tells_secrets_apart() {
+        let root = Path::new(".");
+        // Both redact to the same snippet
+        let snippet = "key = \"sk_l****\"";
+        let accepted = issue_with_secret("./app.py", 1, snippet, "sk_live_0123456789abcdef");
+        let replaced = issue_with_secret("./app.py", 1, snippet, "sk_live_fedcba9876543210");
+
+        assert_ne!(fingerprint(&accepted, root), fingerprint(&replaced, root));
+        let baseline = Baseline::from_issues(std::slice::from_ref(&accepted), root);
+        let (new, _) = baseline.partition(vec![replaced], root);
+        assert_eq!(new.len(), 1);
+    }
+
+    #[test]
+    fn test_partition_counts_duplicates() {
+        let root = Path::new(".");
+        let known = issue("./app.js", 1, "password = \"hunt****\"");
+        let baseline = Baseline::from_issues(std::slice::from_ref(&known), root);
+
+        let fresh = issue("./app.js", 2, "token = \"abcd****\"");
+        let (new, accepted) = baseline.partition(vec![known.clone(), known, fresh.clone()], root);
+        assert_eq!(accepted.len(), 1);
+        assert_eq!(new.len(), 2);
+        assert_eq!(new[1].snippet, fresh.snippet);
+    }
+}
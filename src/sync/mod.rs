@@ -3,11 +3,14 @@
 //! This module provides comprehensive environment variable synchronization
 //! with conflict detection, resolution strategies, and comprehensive audit logging.
 
+use crate::config::Project;
+use crate::crypto::{self, Keyring};
+use crate::env::{EnvDocument, EnvManager};
 use crate::error::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 /// Represents a synchronization conflict between environments
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +60,7 @@ pub enum SyncOperation {
     IncrementalSync,
     ConflictResolution,
     Rollback,
+    Rotation,
 }
 
 /// Configuration for synchronization operations
@@ -68,12 +72,30 @@ pub struct SyncConfig {
     pub security_check: bool,
     pub dry_run: bool,
     pub selective_variables: Option<Vec<String>>,
+    /// Project whose keys decrypt and seal values
+    pub project: Project,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            conflict_resolution: ConflictResolution::ManualReview,
+            backup_before_sync: true,
+            audit_log_path: Project::default().audit_log_path(),
+            security_check: true,
+            dry_run: false,
+            selective_variables: None,
+            project: Project::default(),
+        }
+    }
 }
 
 /// Environment synchronization engine with advanced conflict resolution
 pub struct EnvironmentSync {
     config: SyncConfig,
     audit_log: Vec<AuditLogEntry>,
+    /// Inline `config.toml` values beneath each environment file
+    inline: HashMap<PathBuf, BTreeMap<String, String>>,
 }
 
 impl EnvironmentSync {
@@ -82,9 +104,21 @@ impl EnvironmentSync {
         Self {
             config,
             audit_log: Vec::new(),
+            inline: HashMap::new(),
         }
     }
 
+    /// Treat `variables` as set beneath the file at `path`, the way an
+    /// environment's inline values sit beneath its file.
+    pub fn with_inline_variables(
+        mut self,
+        path: PathBuf,
+        variables: BTreeMap<String, String>,
+    ) -> Self {
+        self.inline.insert(path, variables);
+        self
+    }
+
     /// Synchronize environments with conflict detection and resolution.
     pub async fn sync_environments(
         &mut self,
@@ -108,9 +142,13 @@ impl EnvironmentSync {
         // Resolve conflicts
         let resolved_conflicts = self.resolve_conflicts(conflicts).await?;
 
+        // Keep secrets sealed when either side is encrypted
+        let seal =
+            crypto::file_is_encrypted(source_path)? || crypto::file_is_encrypted(target_path)?;
+
         // Perform synchronization
         let synced_variables = self
-            .perform_sync(&source_env, &target_path, &resolved_conflicts)
+            .perform_sync(&source_env, &target_path, &resolved_conflicts, seal)
             .await?;
 
         let duration = start_time.elapsed();
@@ -160,33 +198,28 @@ impl EnvironmentSync {
         })
     }
 
-    /// Load environment from file.
+    /// Load environment from file, over its inline values.
     async fn load_environment(&self, path: &PathBuf) -> Result<HashMap<String, String>> {
+        let mut variables: HashMap<String, String> = self
+            .inline
+            .get(path)
+            .map(|inline| inline.clone().into_iter().collect())
+            .unwrap_or_default();
         if !path.exists() {
-            return Ok(HashMap::new());
+            return Ok(variables);
         }
 
-        let content = std::fs::read_to_string(path)?;
-        let mut env_vars = HashMap::new();
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
+        let mut env_manager = EnvManager::for_project(&self.config.project);
+        env_manager.load_from_file(path)?;
 
-            if let Some((key, value)) = line.split_once('=') {
-                let key = key.trim().to_string();
-                let value = value.trim().to_string();
-                env_vars.insert(key, value);
-            }
-        }
-
-        Ok(env_vars)
+        variables.extend(env_manager.list().map(|(k, v)| (k.clone(), v.clone())));
+        Ok(variables)
     }
 
     /// Detect conflicts between source and target environments.
-    fn detect_conflicts(
+    ///
+    /// Conflicts are returned sorted by variable name.
+    pub fn detect_conflicts(
         &self,
         source: &HashMap<String, String>,
         target: &HashMap<String, String>,
@@ -229,6 +262,7 @@ impl EnvironmentSync {
             }
         }
 
+        conflicts.sort_by(|a, b| a.variable.cmp(&b.variable));
         conflicts
     }
 
@@ -288,20 +322,35 @@ impl EnvironmentSync {
     }
 
     /// Perform the actual synchronization.
+    ///
+    /// The target file is edited through [`EnvDocument`] so that untouched
+    /// lines, comments and quoting are preserved.
     async fn perform_sync(
         &self,
         _source_env: &HashMap<String, String>,
         target_path: &PathBuf,
         resolved_conflicts: &[SyncConflict],
+        seal: bool,
     ) -> Result<Vec<String>> {
         let mut synced_vars = Vec::new();
+        let mut document = EnvDocument::load_or_default(target_path)?;
+        let keyring = if seal {
+            Some(Keyring::load(&self.config.project)?)
+        } else {
+            None
+        };
 
         for conflict in resolved_conflicts {
             match conflict.recommendation {
                 ConflictResolution::KeepSource | ConflictResolution::Merge => {
                     // Update target with source value
-                    self.update_variable(target_path, &conflict.variable, &conflict.source_value)
-                        .await?;
+                    let value = match &keyring {
+                        Some(keyring) => {
+                            keyring.encrypt_value(&conflict.variable, &conflict.source_value)?
+                        }
+                        None => conflict.source_value.clone(),
+                    };
+                    document.set(&conflict.variable, &value);
                     synced_vars.push(conflict.variable.clone());
                 }
                 ConflictResolution::KeepTarget | ConflictResolution::Skip => {
@@ -313,43 +362,11 @@ impl EnvironmentSync {
             }
         }
 
-        Ok(synced_vars)
-    }
-
-    /// Update a single variable in the target environment file.
-    async fn update_variable(
-        &self,
-        target_path: &PathBuf,
-        variable: &str,
-        value: &str,
-    ) -> Result<()> {
-        let content = if target_path.exists() {
-            std::fs::read_to_string(target_path)?
-        } else {
-            String::new()
-        };
-
-        let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
-        let mut found = false;
-
-        // Try to update existing variable
-        for line in &mut lines {
-            if line.trim().starts_with(&format!("{}=", variable)) {
-                *line = format!("{}={}", variable, value);
-                found = true;
-                break;
-            }
-        }
-
-        // Add new variable if not found
-        if !found {
-            lines.push(format!("{}={}", variable, value));
+        if !synced_vars.is_empty() {
+            document.save(target_path)?;
         }
 
-        // Write back to file
-        std::fs::write(target_path, lines.join("\n") + "\n")?;
-
-        Ok(())
+        Ok(synced_vars)
     }
 
     /// Create backup of target environment.
@@ -457,7 +474,7 @@ impl EnvironmentSync {
                 .or_else(|| std::env::var("USERNAME").ok()),
         };
 
-        self.audit_log.push(entry);
+        self.audit_log.push(entry.clone());
 
         // Write audit log to file if configured
         if self
@@ -467,18 +484,23 @@ impl EnvironmentSync {
             .map(|p| p.exists())
             .unwrap_or(true)
         {
-            self.write_audit_log().await?;
+            append_audit_entry(&self.config.audit_log_path, entry)?;
         }
 
         Ok(())
     }
+}
 
-    /// Write audit log to file.
-    async fn write_audit_log(&self) -> Result<()> {
-        let log_content = serde_json::to_string_pretty(&self.audit_log)?;
-        std::fs::write(&self.config.audit_log_path, log_content)?;
-        Ok(())
-    }
+/// Append an entry to the JSON audit log at `path`, keeping earlier entries.
+pub fn append_audit_entry(path: &Path, entry: AuditLogEntry) -> Result<()> {
+    let mut entries: Vec<AuditLogEntry> = match std::fs::read_to_string(path) {
+        Ok(content) if !content.trim().is_empty() => serde_json::from_str(&content)?,
+        _ => Vec::new(),
+    };
+    entries.push(entry);
+
+    std::fs::write(path, serde_json::to_string_pretty(&entries)?)?;
+    Ok(())
 }
 
 /// Result of a synchronization operation
@@ -501,7 +523,7 @@ pub struct DryRunResult {
 }
 
 /// A proposed change during dry run
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ProposedChange {
     pub variable: String,
     pub change_type: ChangeType,
@@ -509,7 +531,7 @@ pub struct ProposedChange {
     pub new_value: String,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum ChangeType {
     Add,
     Update,
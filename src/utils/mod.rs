@@ -35,17 +35,34 @@ pub fn path_exists(path: &PathBuf) -> bool {
     path.exists()
 }
 
+/// Letters and digits.
+pub const CHARSET_ALNUM: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Letters, digits and shell-safe symbols.
+pub const CHARSET_SYMBOLS: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789!@#$%^&*";
+
+/// The standard base64 alphabet.
+pub const CHARSET_BASE64: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Lowercase hexadecimal digits.
+pub const CHARSET_HEX: &[u8] = b"0123456789abcdef";
+
 /// Generate a random secure string.
 pub fn generate_secret(length: usize) -> String {
+    generate_secret_from(length, CHARSET_SYMBOLS)
+}
+
+/// Generate a random secure string drawn from `charset`.
+pub fn generate_secret_from(length: usize, charset: &[u8]) -> String {
     use rand::Rng;
-    const CHARSET: &[u8] =
-        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789!@#$%^&*";
 
     let mut rng = rand::thread_rng();
     (0..length)
         .map(|_| {
-            let idx = rng.gen_range(0..CHARSET.len());
-            CHARSET[idx] as char
+            let idx = rng.gen_range(0..charset.len());
+            charset[idx] as char
         })
         .collect()
 }
@@ -88,19 +105,6 @@ pub fn config_dir() -> Option<PathBuf> {
     dirs::config_dir()
 }
 
-/// Prompt user for confirmation.
-pub fn confirm(message: &str) -> Result<bool> {
-    println!("{} [y/N]", message);
-
-    let mut input = String::new();
-    std::io::stdin()
-        .read_line(&mut input)
-        .map_err(|e| crate::error::EnvCliError::Io(e))?;
-
-    let input = input.trim().to_lowercase();
-    Ok(matches!(input.as_str(), "y" | "yes"))
-}
-
 /// Create a backup of a file.
 pub fn backup_file(path: &PathBuf) -> Result<PathBuf> {
     if !path.exists() {
@@ -157,5 +161,9 @@ mod tests {
         assert!(secret
             .chars()
             .all(|c| c.is_ascii_alphanumeric() || "!@#$%^&*".contains(c)));
+
+        let hex = generate_secret_from(64, CHARSET_HEX);
+        assert_eq!(hex.len(), 64);
+        assert!(hex.chars().all(|c| c.is_ascii_hexdigit()));
     }
 }